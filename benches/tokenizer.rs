@@ -0,0 +1,67 @@
+#![allow(clippy::needless_return)]
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rip::{html_tokenize, html_tokenize_borrowed, HtmlTokenizer};
+
+// a script body full of '<' that never closes the script, like an inlined minified bundle
+fn script_document(statements: usize) -> String {
+    let mut html = String::from("<html><body><script>");
+    for i in 0..statements {
+        html.push_str(&format!("if(a<{i}&&b<c){{x=\"<div>\"+'</p>'}}"));
+    }
+    html.push_str("</script></body></html>");
+    return html;
+}
+
+// plain markup, so every tag goes through new_token_html_from_tag
+fn markup_document(rows: usize) -> String {
+    let mut html = String::from("<!DOCTYPE html><table>");
+    for i in 0..rows {
+        html.push_str(&format!("<tr class='row'><td id=\"c{i}\">cell &amp; {i}</td><td><br/></td></tr>\n"));
+    }
+    html.push_str("</table>");
+    return html;
+}
+
+// doubling the input should roughly double the time, the throughput numbers stay flat when scanning is linear
+fn bench_script_bodies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("script_body");
+    for statements in [1_000, 2_000, 4_000, 8_000, 16_000] {
+        let html = script_document(statements);
+        group.throughput(Throughput::Bytes(html.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(statements), &html, |b, html| {
+            b.iter(|| html_tokenize(black_box(html)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn bench_markup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("markup");
+    for rows in [500, 1_000, 2_000, 4_000] {
+        let html = markup_document(rows);
+        group.throughput(Throughput::Bytes(html.len() as u64));
+        group.bench_with_input(BenchmarkId::new("owned", rows), &html, |b, html| {
+            b.iter(|| html_tokenize(black_box(html)).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", rows), &html, |b, html| {
+            b.iter(|| html_tokenize_borrowed(black_box(html)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+// pulling the first few tokens off a document shouldn't cost more as the rest of the document grows
+fn bench_stop_early(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stop_early");
+    for rows in [1_000, 10_000, 100_000] {
+        let html = markup_document(rows);
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &html, |b, html| {
+            b.iter(|| HtmlTokenizer::new(black_box(html)).take(3).count());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_script_bodies, bench_markup, bench_stop_early);
+criterion_main!(benches);
@@ -0,0 +1,130 @@
+use crate::error::RipError;
+use crate::span::Span;
+use crate::tokenizer::{HtmlTokenizer, HtmlTokenizerOptions, LexerState, SpannedTokenHtml};
+
+// a push-style tokenizer for input that arrives a piece at a time, like a body streamed from the network.
+// feed it chunks as they come in and call finish once the input is done, every token comes out exactly as
+// html_tokenize_spanned would have produced it from the whole document
+#[derive(Debug)]
+pub struct HtmlChunkTokenizer {
+    // input that has been fed but not turned into tokens yet, it always starts on a token boundary
+    buffer: String,
+    // the start of a utf-8 sequence that was cut off at the end of the last byte chunk
+    partial_bytes: Vec<u8>,
+    state: LexerState,
+    prelike_open: Option<(String, Span)>,
+    // how much of buffer the tokenizer already searched for the end of the token buffer starts with,
+    // so each chunk only costs what it adds instead of what has piled up
+    scanned: usize,
+    // where the start of buffer sits in the whole document
+    base: Span,
+    options: HtmlTokenizerOptions,
+    error: Option<RipError>,
+}
+
+impl Default for HtmlChunkTokenizer {
+    fn default() -> Self {
+        return HtmlChunkTokenizer::new();
+    }
+}
+
+impl HtmlChunkTokenizer {
+
+    pub fn new() -> HtmlChunkTokenizer {
+        return HtmlChunkTokenizer::with_options(HtmlTokenizerOptions::default());
+    }
+
+    pub fn with_options(options: HtmlTokenizerOptions) -> HtmlChunkTokenizer {
+        return HtmlChunkTokenizer {
+            buffer: String::new(),
+            partial_bytes: vec![],
+            state: LexerState::InTag,
+            prelike_open: None,
+            scanned: 0,
+            base: Span::new(0, 0, 1, 1),
+            options,
+            error: None,
+        };
+    }
+
+    // returns the tokens the chunk completed, a token cut off by the end of the chunk waits for the next one
+    pub fn feed(&mut self, chunk: &str) -> Result<Vec<SpannedTokenHtml>, RipError> {
+        if !self.partial_bytes.is_empty() {
+            return self.feed_bytes(chunk.as_bytes());
+        }
+        self.buffer.push_str(chunk);
+        return self.run(false);
+    }
+
+    // chunks may split a utf-8 sequence anywhere, invalid sequences become U+FFFD
+    pub fn feed_bytes(&mut self, chunk: &[u8]) -> Result<Vec<SpannedTokenHtml>, RipError> {
+        self.partial_bytes.extend_from_slice(chunk);
+        let mut rest: &[u8] = &self.partial_bytes;
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    self.buffer.push_str(valid);
+                    rest = &[];
+                    break;
+                },
+                Err(err) => {
+                    let (valid, after) = rest.split_at(err.valid_up_to());
+                    // the prefix up to valid_up_to is utf-8 by definition
+                    self.buffer.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match err.error_len() {
+                        Some(len) => {
+                            self.buffer.push('\u{FFFD}');
+                            rest = &after[len..];
+                        },
+                        None => {
+                            // the sequence may still be finished by the next chunk
+                            rest = after;
+                            break;
+                        },
+                    }
+                },
+            }
+        }
+        self.partial_bytes = rest.to_vec();
+        return self.run(false);
+    }
+
+    // ends the input and returns whatever tokens were still waiting on more of it
+    pub fn finish(mut self) -> Result<Vec<SpannedTokenHtml>, RipError> {
+        if !self.partial_bytes.is_empty() {
+            self.buffer.push('\u{FFFD}');
+            self.partial_bytes.clear();
+        }
+        return self.run(true);
+    }
+
+    fn run(&mut self, eof: bool) -> Result<Vec<SpannedTokenHtml>, RipError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        if self.buffer.is_empty() && !eof {
+            return Ok(vec![]);
+        }
+        let mut tokenizer = HtmlTokenizer::resume(&self.buffer, self.state, self.prelike_open.take(), self.scanned, self.base, eof, self.options);
+        let mut toks: Vec<SpannedTokenHtml> = vec![];
+        while let Some(tok) = tokenizer.next_spanned() {
+            match tok {
+                Ok(tok) => {
+                    toks.push(tok);
+                },
+                Err(err) => {
+                    self.error = Some(err.clone());
+                    return Err(err);
+                },
+            }
+        }
+        let (consumed, state, prelike_open, scanned, base) = tokenizer.suspend();
+        self.buffer.drain(..consumed);
+        self.state = state;
+        self.prelike_open = prelike_open;
+        self.scanned = scanned;
+        self.base = base;
+        return Ok(toks);
+    }
+
+}
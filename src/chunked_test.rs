@@ -0,0 +1,136 @@
+use crate::*;
+
+const CHUNKED_DOCUMENTS: [&str; 7] = [
+    "<!DOCTYPE html>\n<html><head><title>Hi &amp; bye</title></head><body class=\"a b\">text</body></html>",
+    "<div title='a > b' data-x=\"it's\"><p>one</p>  <br/><img src=x></div>tail",
+    "<p>before<!-- a <b> 'quoted' -- comment -->after</p><!----><!-->a<!--->b",
+    "<script>let a = '</script>'; if (a < b) { go(\"x\"); }</script><p>é ü</p>",
+    "<pre>\n  <b>kept</b>\n</pre><textarea>x</textarea>",
+    "<style></style>\n\n<p>line\nthree</p>",
+    "<textarea>a < b </text </ textarea></textarea \n>long text <i>-</i>",
+];
+
+fn chunked_tokenize(chunks: &[&str]) -> Result<Vec<SpannedTokenHtml>, RipError> {
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    let mut toks: Vec<SpannedTokenHtml> = vec![];
+    for chunk in chunks {
+        toks.extend(tokenizer.feed(chunk)?);
+    }
+    toks.extend(tokenizer.finish()?);
+    return Ok(toks);
+}
+
+#[test]
+fn test_html_chunk_tokenizer_every_split() {
+    for doc in CHUNKED_DOCUMENTS {
+        let expected = html_tokenize_spanned(doc).unwrap();
+        for (split, _) in doc.char_indices() {
+            let toks = chunked_tokenize(&[&doc[..split], &doc[split..]]).unwrap();
+            assert!(toks == expected, "split at {} of {:?}", split, doc);
+        }
+        let chars: Vec<String> = doc.chars().map(|c| c.to_string()).collect();
+        let chars: Vec<&str> = chars.iter().map(|c| c.as_str()).collect();
+        assert!(chunked_tokenize(&chars).unwrap() == expected);
+    }
+}
+
+#[test]
+fn test_html_chunk_tokenizer_bytes() {
+    for doc in CHUNKED_DOCUMENTS {
+        let expected = html_tokenize_spanned(doc).unwrap();
+        let bytes = doc.as_bytes();
+        for split in 0..=bytes.len() {
+            let mut tokenizer = HtmlChunkTokenizer::new();
+            let mut toks = tokenizer.feed_bytes(&bytes[..split]).unwrap();
+            toks.extend(tokenizer.feed_bytes(&bytes[split..]).unwrap());
+            toks.extend(tokenizer.finish().unwrap());
+            assert!(toks == expected, "split at byte {} of {:?}", split, doc);
+        }
+    }
+
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    let mut toks = tokenizer.feed_bytes(b"<p>a\xFFb").unwrap();
+    toks.extend(tokenizer.feed_bytes(b"</p>\xC3").unwrap());
+    toks.extend(tokenizer.finish().unwrap());
+    assert!(toks[1].token == TokenHtml::InnerText { text: "a\u{FFFD}b".to_string() });
+    assert!(toks[3].token == TokenHtml::InnerText { text: "\u{FFFD}".to_string() });
+}
+
+#[test]
+fn test_html_chunk_tokenizer_waits() {
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    assert!(tokenizer.feed("<div class='a >").unwrap() == vec![]);
+    let toks = tokenizer.feed("b'>hi").unwrap();
+    assert!(toks == vec![
+        SpannedTokenHtml { token: TokenHtml::Open { tag_name: "div".to_string(), outer_html: "<div class='a >b'>".to_string() }, span: Span::new(0, 18, 1, 1) },
+    ]);
+    // text may continue in the next chunk, so it only comes out once a tag or the end follows it
+    assert!(tokenizer.finish().unwrap() == vec![
+        SpannedTokenHtml { token: TokenHtml::InnerText { text: "hi".to_string() }, span: Span::new(18, 20, 1, 19) },
+    ]);
+
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    assert!(tokenizer.feed("<script>if (a <").unwrap().len() == 1);
+    assert!(tokenizer.feed(" b) {}</scr").unwrap() == vec![]);
+    let toks = tokenizer.feed("ipt>").unwrap();
+    assert!(toks[0].token == TokenHtml::InnerText { text: "if (a < b) {}".to_string() });
+    assert!(toks[1].token == TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script>".to_string() });
+}
+
+#[test]
+fn test_html_chunk_tokenizer_errors() {
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    tokenizer.feed("<p>\n<script>let a").unwrap();
+    assert!(tokenizer.finish() == Err(RipError::UnclosedRawText { tag_name: "script".to_string(), span: Some(Span::new(4, 12, 2, 1)) }));
+
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    tokenizer.feed("<p>x</p><!-- never").unwrap();
+    assert!(matches!(tokenizer.finish(), Err(RipError::UnclosedComment { .. })));
+
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    assert!(matches!(tokenizer.feed("<hr//>"), Err(RipError::TooManySlashes { .. })));
+    assert!(matches!(tokenizer.feed("<p>"), Err(RipError::TooManySlashes { .. })));
+
+    assert!(html_tokenize("<script>") == Err(RipError::UnclosedRawText { tag_name: "script".to_string(), span: Some(Span::new(0, 8, 1, 1)) }));
+}
+
+#[test]
+fn test_html_chunk_tokenizer_options() {
+    let options = HtmlTokenizerOptions { quote_aware_raw_text: true };
+    let doc = "<script>let a = '</script>'; if (a < b) {}</script>";
+    let expected = HtmlTokenizer::with_options(doc, options).spanned().collect::<Result<Vec<_>, _>>().unwrap();
+    assert!(expected[1].token == TokenHtml::InnerText { text: "let a = '</script>'; if (a < b) {}".to_string() });
+    for (split, _) in doc.char_indices() {
+        let mut tokenizer = HtmlChunkTokenizer::with_options(options);
+        let mut toks = tokenizer.feed(&doc[..split]).unwrap();
+        toks.extend(tokenizer.feed(&doc[split..]).unwrap());
+        toks.extend(tokenizer.finish().unwrap());
+        assert!(toks == expected, "split at {}", split);
+    }
+    // fed one char at a time, every pass before the first '<' outside the quotes has to start over
+    let mut tokenizer = HtmlChunkTokenizer::with_options(options);
+    let mut toks: Vec<SpannedTokenHtml> = vec![];
+    for c in doc.chars() {
+        toks.extend(tokenizer.feed(&c.to_string()).unwrap());
+    }
+    toks.extend(tokenizer.finish().unwrap());
+    assert!(toks == expected);
+}
+
+#[test]
+fn test_html_chunk_tokenizer_long_tokens() {
+    // a body that piles up over many chunks is searched once, and still ends at the right closing tag
+    let mut tokenizer = HtmlChunkTokenizer::new();
+    let mut toks = tokenizer.feed("<script>").unwrap();
+    for _ in 0..1000 {
+        toks.extend(tokenizer.feed("if (a < b) { c('</p>') } ").unwrap());
+    }
+    toks.extend(tokenizer.feed("</scr").unwrap());
+    toks.extend(tokenizer.feed("ipt  ").unwrap());
+    toks.extend(tokenizer.feed(">x").unwrap());
+    toks.extend(tokenizer.finish().unwrap());
+    assert!(toks.len() == 4);
+    assert!(toks[1].token == TokenHtml::InnerText { text: "if (a < b) { c('</p>') } ".repeat(1000) });
+    assert!(toks[2].token == TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script  >".to_string() });
+    assert!(toks[3].span == Span::new(25019, 25020, 1, 25020));
+}
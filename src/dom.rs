@@ -1,34 +1,198 @@
-use crate::tokenizer::{html_tokenize, TokenHtml};
+use crate::error::RipError;
+use crate::entities::{html_decode_attribute, html_decode_text};
+use crate::html_utils::{html_attributes, is_html_raw_text_element, is_html_void_element, HtmlAttribute};
+use crate::span::Span;
+use crate::tokenizer::{HtmlTokenizer, SpannedTokenHtml, TokenHtml};
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DomNodeKind {
+    Root,
+    Element,
+    Text,
+    Comment,
+    Doctype,
+}
+
+// svg and math elements keep their own namespace, everything the tag-balancing builder makes is html
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DomNamespace {
+    #[default]
+    Html,
+    Svg,
+    MathMl,
+}
+
+// with the serde feature a node is an object with every field below, kind and namespace as the variant's name.
+// a whole tree is one nested object, like {"kind": "Element", "tag_name": "p", "namespace": "Html",
+// "attributes": [{"name": "id", "value": "a"}], "outer_html": "<p id=a>", "text": "", "children": [...]}
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DomNode {
-    Children:  Vec<DomNode>
+    pub kind: DomNodeKind,
+    pub tag_name: String,
+    pub namespace: DomNamespace,
+    pub attributes: Vec<HtmlAttribute>,
+    pub outer_html: String,
+    pub text: String,
+    pub children: Vec<DomNode>,
+}
+
+impl Default for DomNode {
+    fn default() -> Self {
+        return DomNode::new();
+    }
 }
 
 impl DomNode {
 
     pub fn new() -> DomNode {
-        let node = DomNode {
-            Children: vec![],
+        return DomNode {
+            kind: DomNodeKind::Root,
+            tag_name: String::new(),
+            namespace: DomNamespace::Html,
+            attributes: vec![],
+            outer_html: String::new(),
+            text: String::new(),
+            children: vec![],
         };
-        node
     }
 
-    pub fn append(&mut self, node: DomNode) {
-        self.Children.push(node);
-    } 
-
-}
+    // the attributes are read out of the opening tag in outer_html, with their values decoded
+    pub fn new_element(tag_name: &str, outer_html: &str) -> DomNode {
+        let attributes = html_attributes(outer_html).unwrap_or_default().into_iter().map(|attr| {
+            return HtmlAttribute { value: html_decode_attribute(&attr.value), name: attr.name };
+        }).collect();
+        return DomNode {
+            kind: DomNodeKind::Element,
+            tag_name: tag_name.to_string(),
+            namespace: DomNamespace::Html,
+            attributes,
+            outer_html: outer_html.to_string(),
+            text: String::new(),
+            children: vec![],
+        };
+    }
 
+    pub fn new_text(text: &str) -> DomNode {
+        return DomNode {
+            kind: DomNodeKind::Text,
+            tag_name: String::new(),
+            namespace: DomNamespace::Html,
+            attributes: vec![],
+            outer_html: text.to_string(),
+            text: text.to_string(),
+            children: vec![],
+        };
+    }
 
-pub fn dom_tree_from_html(html: &str) {
-    let toks = html_tokenize("<p>Hello!</p>").unwrap();
-    let head = DomNode::new();
-    let mut stack: Vec<TokenHtml> = vec![];
+    pub fn new_comment(text: &str, outer_html: &str) -> DomNode {
+        return DomNode {
+            kind: DomNodeKind::Comment,
+            tag_name: String::new(),
+            namespace: DomNamespace::Html,
+            attributes: vec![],
+            outer_html: outer_html.to_string(),
+            text: text.to_string(),
+            children: vec![],
+        };
+    }
 
- 
+    pub fn new_doctype(name: &str, outer_html: &str) -> DomNode {
+        return DomNode {
+            kind: DomNodeKind::Doctype,
+            tag_name: name.to_string(),
+            namespace: DomNamespace::Html,
+            attributes: vec![],
+            outer_html: outer_html.to_string(),
+            text: String::new(),
+            children: vec![],
+        };
+    }
 
-}
+    // appending a child folds its text into the parent's text. outer_html stays the markup the node was
+    // parsed from, to_html writes out the tree as it is after changes
+    pub fn append(&mut self, node: DomNode) {
+        // comments are markup, not text content
+        if node.kind != DomNodeKind::Comment {
+            self.text.push_str(&node.text);
+        }
+        self.children.push(node);
+    }
 
+    // what the builders append with while the node's closing tag is still to come,
+    // the child's markup is part of the node's original markup then
+    pub(crate) fn append_parsed(&mut self, node: DomNode) {
+        self.outer_html.push_str(&node.outer_html);
+        self.append(node);
+    }
 
+}
 
+pub fn dom_tree_from_html(html: &str) -> Result<DomNode, RipError> {
+    // the bottom of the stack is the root, everything above it is an element still waiting on its closing tag
+    let mut stack: Vec<DomNode> = vec![DomNode::new()];
+    // where each element above the root was opened, kept alongside the stack for error reporting
+    let mut open_spans: Vec<Span> = vec![];
+    for tok in HtmlTokenizer::new(html).spanned() {
+        let SpannedTokenHtml { token, span } = tok?;
+        match token {
+            TokenHtml::Open { tag_name, outer_html } | TokenHtml::PreLikeOpen { tag_name, outer_html } => {
+                stack.push(DomNode::new_element(&tag_name, &outer_html));
+                open_spans.push(span);
+            },
+            TokenHtml::SelfClosing { tag_name, outer_html } => {
+                dom_stack_top(&mut stack).append_parsed(DomNode::new_element(&tag_name, &outer_html));
+            },
+            TokenHtml::Close { tag_name, outer_html } | TokenHtml::PreLikeClose { tag_name, outer_html } => {
+                // void elements were never pushed, so a stray '</br>' or '</img>' has nothing to close,
+                // it stays part of the surrounding markup
+                if is_html_void_element(&tag_name) {
+                    dom_stack_top(&mut stack).outer_html.push_str(&outer_html);
+                    continue;
+                }
+                if stack.len() < 2 {
+                    return Err(RipError::UnexpectedCloseTag { tag: outer_html, span: Some(span) });
+                }
+                let mut node = stack.pop().unwrap(); // cannot fail, checked above
+                open_spans.pop();
+                if node.tag_name != tag_name {
+                    return Err(RipError::MismatchedCloseTag { expected: node.tag_name, found: outer_html, span: Some(span) });
+                }
+                node.outer_html.push_str(&outer_html);
+                dom_stack_top(&mut stack).append_parsed(node);
+            },
+            TokenHtml::InnerText { text } | TokenHtml::Whitespace { text } => {
+                let parent = dom_stack_top(&mut stack);
+                // text inside a raw text element is kept exactly as written, everything else gets its references decoded,
+                // textarea and pre included
+                if is_html_raw_text_element(&parent.tag_name) {
+                    parent.append_parsed(DomNode::new_text(&text));
+                    continue;
+                }
+                let mut node = DomNode::new_text(&html_decode_text(&text));
+                node.outer_html = text;
+                parent.append_parsed(node);
+            },
+            TokenHtml::Comment { text } => {
+                let outer_html = &html[span.start..span.end];
+                dom_stack_top(&mut stack).append_parsed(DomNode::new_comment(&text, outer_html));
+            },
+            TokenHtml::Doctype { name, public_id: _, system_id: _, outer_html } => {
+                dom_stack_top(&mut stack).append_parsed(DomNode::new_doctype(&name, &outer_html));
+            },
+        }
+    }
+    if stack.len() > 1 {
+        let unclosed = stack.pop().unwrap(); // cannot fail, checked above
+        return Err(RipError::UnclosedElement { tag_name: unclosed.tag_name, span: open_spans.pop() });
+    }
+    let root = stack.pop().unwrap(); // cannot fail, the root is never popped in the loop
+    return Ok(root);
+}
 
+fn dom_stack_top(stack: &mut [DomNode]) -> &mut DomNode {
+    // the root is never popped off the stack, so there is always a top
+    return stack.last_mut().unwrap();
+}
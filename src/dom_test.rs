@@ -1,8 +1,128 @@
-use crate::dom::dom_tree_from_html;
+use crate::dom::*;
+use crate::error::RipError;
+use crate::span::Span;
 
 #[test]
 fn test_dom_tree_from_html() {
-    dom_tree_from_html("<p>Hello!</p>");
 
+    let root = dom_tree_from_html("<p>Hello!</p>").unwrap();
+    assert!(root.kind == DomNodeKind::Root);
+    assert!(root.outer_html == "<p>Hello!</p>");
+    assert!(root.text == "Hello!");
+    assert!(root.children.len() == 1);
+    let p = &root.children[0];
+    assert!(p.kind == DomNodeKind::Element);
+    assert!(p.tag_name == "p");
+    assert!(p.outer_html == "<p>Hello!</p>");
+    assert!(p.text == "Hello!");
+    assert!(p.children == vec![DomNode::new_text("Hello!")]);
 
-}
\ No newline at end of file
+    let root = dom_tree_from_html("<div class='x'><h1>Title</h1> <p>Hello<br/>World</p></div>").unwrap();
+    let div = &root.children[0];
+    assert!(div.tag_name == "div");
+    assert!(div.outer_html == "<div class='x'><h1>Title</h1> <p>Hello<br/>World</p></div>");
+    assert!(div.text == "Title HelloWorld");
+    assert!(div.children.len() == 3);
+    assert!(div.children[0].tag_name == "h1");
+    assert!(div.children[1].kind == DomNodeKind::Text);
+    assert!(div.children[1].text == " ");
+    let p = &div.children[2];
+    assert!(p.children.len() == 3);
+    assert!(p.children[1].tag_name == "br");
+    assert!(p.children[1].outer_html == "<br/>");
+    assert!(p.children[1].children.is_empty());
+
+    let root = dom_tree_from_html("<h1>Hello</h1><h2>World</h2>").unwrap();
+    assert!(root.children.len() == 2);
+    assert!(root.children[0].tag_name == "h1");
+    assert!(root.children[1].tag_name == "h2");
+
+    let root = dom_tree_from_html("<div><script>let a = '<p>';</script></div>").unwrap();
+    let script = &root.children[0].children[0];
+    assert!(script.tag_name == "script");
+    assert!(script.text == "let a = '<p>';");
+    assert!(script.outer_html == "<script>let a = '<p>';</script>");
+
+    assert!(dom_tree_from_html("<div><p>Hello</div>").is_err());
+    assert!(dom_tree_from_html("<div><p>Hello</p>").is_err());
+    assert!(dom_tree_from_html("</div>").is_err());
+
+}
+
+#[test]
+fn test_dom_node_append() {
+    let mut node = DomNode::new_element("ul", "<ul>");
+    node.append(DomNode::new_text("a"));
+    node.append(DomNode::new_text("b"));
+    assert!(node.children.len() == 2);
+    assert!(node.text == "ab");
+    // outer_html is the markup the node was made from, appending doesn't change it
+    assert!(node.outer_html == "<ul>");
+
+    let mut root = dom_tree_from_html("<ul><li>a</li></ul>").unwrap();
+    root.children[0].append(DomNode::new_text("x"));
+    assert!(root.children[0].outer_html == "<ul><li>a</li></ul>");
+    assert!(root.children[0].text == "ax");
+    assert!(root.to_html() == "<ul><li>a</li>x</ul>");
+}
+
+#[test]
+fn test_dom_tree_from_html_errors() {
+    assert!(dom_tree_from_html("<div><p>Hello</div>") == Err(RipError::MismatchedCloseTag { expected: "p".to_string(), found: "</div>".to_string(), span: Some(Span::new(13, 19, 1, 14)) }));
+    assert!(dom_tree_from_html("<div><p>Hello</p>") == Err(RipError::UnclosedElement { tag_name: "div".to_string(), span: Some(Span::new(0, 5, 1, 1)) }));
+    assert!(dom_tree_from_html("</div>") == Err(RipError::UnexpectedCloseTag { tag: "</div>".to_string(), span: Some(Span::new(0, 6, 1, 1)) }));
+}
+
+#[test]
+fn test_dom_tree_from_html_comments() {
+    let root = dom_tree_from_html("<div><!-- note -->Hello</div>").unwrap();
+    let div = &root.children[0];
+    assert!(div.children.len() == 2);
+    assert!(div.children[0] == DomNode::new_comment(" note ", "<!-- note -->"));
+    assert!(div.children[0].kind == DomNodeKind::Comment);
+    assert!(div.text == "Hello");
+    assert!(div.outer_html == "<div><!-- note -->Hello</div>");
+}
+
+#[test]
+fn test_dom_tree_from_html_doctype() {
+    let root = dom_tree_from_html("<!DOCTYPE html><html><body><p>Hi</p></body></html>").unwrap();
+    assert!(root.children.len() == 2);
+    assert!(root.children[0] == DomNode::new_doctype("html", "<!DOCTYPE html>"));
+    assert!(root.children[1].tag_name == "html");
+    assert!(root.text == "Hi");
+    assert!(root.outer_html == "<!DOCTYPE html><html><body><p>Hi</p></body></html>");
+}
+
+#[test]
+fn test_dom_tree_from_html_void_elements() {
+    let root = dom_tree_from_html("<head><meta charset='utf-8'><link rel=stylesheet href=a.css></head>").unwrap();
+    let head = &root.children[0];
+    assert!(head.children.len() == 2);
+    assert!(head.children[0].tag_name == "meta");
+    assert!(head.children[1].tag_name == "link");
+    assert!(head.children[1].children.is_empty());
+
+    let root = dom_tree_from_html("<p>a<br>b<hr><img src=x></img></p>").unwrap();
+    let p = &root.children[0];
+    assert!(p.children.len() == 5);
+    assert!(p.text == "ab");
+    assert!(p.outer_html == "<p>a<br>b<hr><img src=x></img></p>");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_dom_node_serde() {
+    let root = dom_tree_from_html("<div id=\"a\">x<br></div>").unwrap();
+    let json = serde_json::to_value(&root).unwrap();
+    assert!(json["kind"] == "Root");
+    let div = &json["children"][0];
+    assert!(div["kind"] == "Element");
+    assert!(div["tag_name"] == "div");
+    assert!(div["namespace"] == "Html");
+    assert!(div["attributes"] == serde_json::json!([{ "name": "id", "value": "a" }]));
+    assert!(div["children"][0]["kind"] == "Text");
+    assert!(div["children"][0]["text"] == "x");
+    let back: DomNode = serde_json::from_str(&serde_json::to_string(&root).unwrap()).unwrap();
+    assert!(back == root);
+}
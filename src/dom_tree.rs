@@ -0,0 +1,653 @@
+use crate::dom::{dom_tree_from_html, DomNamespace, DomNode, DomNodeKind};
+use crate::error::RipError;
+use crate::entities::html_decode_text;
+use crate::html_utils::{is_html_raw_text_element, is_html_rcdata_element, is_html_void_element, HtmlAttribute};
+use crate::selector::{css_query, CssNode};
+use crate::html5_parser::{html5_parse_tree, Html5ParserOptions};
+use crate::serializer::{dom_fill_outer_html, html_is_raw_text_parent, html_node_to_html, HtmlNode};
+
+// a handle to a node in a DomTree. it stays valid while the tree changes around it,
+// and only means something to the tree that handed it out
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    // the node's slot in the arena, nodes are numbered in the order they were created
+    pub fn index(&self) -> usize {
+        return self.0;
+    }
+}
+
+// what a node holds on its own. unlike DomNode there is no outer_html or text gathered up from the children,
+// the tree works those out when asked so they can't go stale as it changes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DomTreeNode {
+    pub kind: DomNodeKind,
+    // the element's name, or the doctype's
+    pub tag_name: String,
+    pub namespace: DomNamespace,
+    pub attributes: Vec<HtmlAttribute>,
+    // the decoded text of a text node, the body of a comment, or a doctype's declaration as it was written
+    pub text: String,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    prev_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+impl DomTreeNode {
+
+    fn new(kind: DomNodeKind, tag_name: &str, text: &str) -> DomTreeNode {
+        return DomTreeNode {
+            kind,
+            tag_name: tag_name.to_string(),
+            namespace: DomNamespace::Html,
+            attributes: vec![],
+            text: text.to_string(),
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        };
+    }
+
+}
+
+// a dom kept in one arena, every node links to its parent, its first and last child and its siblings,
+// so moving from a node to any of its neighbours takes one step. nodes taken out of the tree stay in the
+// arena with no parent until they are put back somewhere
+#[derive(Debug, Clone)]
+pub struct DomTree {
+    nodes: Vec<DomTreeNode>,
+}
+
+// two trees are equal when their roots hold the same nodes in the same places. where the nodes sit in
+// the arena, and any detached nodes, don't count
+impl PartialEq for DomTree {
+    fn eq(&self, other: &DomTree) -> bool {
+        return self.same_subtree(self.root(), other, other.root());
+    }
+}
+
+impl Eq for DomTree {}
+
+// with the serde feature a tree is the same nested object its root's DomNode is. the arena and its links
+// stay out of it, so they are free to change and a tree read back always has every node where it belongs.
+// detached nodes aren't part of the tree and are left out
+#[cfg(feature = "serde")]
+impl serde::Serialize for DomTree {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serde::Serialize::serialize(&self.to_node(self.root()), serializer);
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DomTree {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let node: DomNode = serde::Deserialize::deserialize(deserializer)?;
+        return Ok(DomTree::from_node(&node));
+    }
+}
+
+impl Default for DomTree {
+    fn default() -> Self {
+        return DomTree::new();
+    }
+}
+
+impl DomTree {
+
+    // a tree holding nothing but its root
+    pub fn new() -> DomTree {
+        return DomTree { nodes: vec![DomTreeNode::new(DomNodeKind::Root, "", "")] };
+    }
+
+    // builds the tree html5_parse would, straight into the arena
+    pub fn parse(html: &str) -> DomTree {
+        return html5_parse_tree(html, Html5ParserOptions::default());
+    }
+
+    // copies a DomNode and everything under it into a new tree. a Root node becomes the tree's root,
+    // anything else is appended to an empty root
+    pub fn from_node(node: &DomNode) -> DomTree {
+        let mut tree = DomTree::new();
+        let root = tree.root();
+        if node.kind == DomNodeKind::Root {
+            for child in &node.children {
+                tree.append_dom_node(root, child);
+            }
+        } else {
+            tree.append_dom_node(root, node);
+        }
+        return tree;
+    }
+
+    fn append_dom_node(&mut self, parent: NodeId, node: &DomNode) {
+        let id = match node.kind {
+            DomNodeKind::Root | DomNodeKind::Element => {
+                let id = self.create_element(&node.tag_name);
+                self.nodes[id.0].namespace = node.namespace;
+                self.nodes[id.0].attributes = node.attributes.clone();
+                id
+            },
+            DomNodeKind::Text => self.create_text(&node.text),
+            DomNodeKind::Comment => self.create_comment(&node.text),
+            DomNodeKind::Doctype => self.create_doctype(&node.tag_name, &node.outer_html),
+        };
+        self.append(parent, id);
+        for child in &node.children {
+            self.append_dom_node(id, child);
+        }
+    }
+
+    // builds the DomNode for a node and everything under it, with outer_html and text filled in
+    // the same way html5_parse fills them in
+    pub fn to_node(&self, id: NodeId) -> DomNode {
+        let mut node = self.dom_node(id);
+        dom_fill_outer_html(&mut node, self.in_raw_text(id), false);
+        return node;
+    }
+
+    // text under a raw text element was never decoded and is written back out as it is
+    fn in_raw_text(&self, id: NodeId) -> bool {
+        return self.parent(id).is_some_and(|parent| html_is_raw_text_parent(self.view(parent), false));
+    }
+
+    // outer_html is left for dom_fill_outer_html to write once the whole tree is built
+    fn dom_node(&self, id: NodeId) -> DomNode {
+        let node = &self.nodes[id.0];
+        let mut dom_node = match node.kind {
+            DomNodeKind::Root => DomNode::new(),
+            DomNodeKind::Text => DomNode::new_text(&node.text),
+            DomNodeKind::Comment => DomNode::new_comment(&node.text, ""),
+            DomNodeKind::Doctype => DomNode::new_doctype(&node.tag_name, &node.text),
+            DomNodeKind::Element => {
+                let mut element = DomNode::new_element(&node.tag_name, "");
+                element.namespace = node.namespace;
+                element.attributes = node.attributes.clone();
+                element
+            },
+        };
+        for child in self.children(id) {
+            dom_node.append(self.dom_node(child));
+        }
+        return dom_node;
+    }
+
+    // the markup for a node and everything under it, written straight from the arena
+    pub fn to_html(&self, id: NodeId) -> String {
+        return html_node_to_html(self.view(id), self.in_raw_text(id));
+    }
+
+    fn view(&self, id: NodeId) -> DomTreeNodeView<'_> {
+        return DomTreeNodeView { tree: self, id };
+    }
+
+    fn same_subtree(&self, id: NodeId, other: &DomTree, other_id: NodeId) -> bool {
+        let (a, b) = (&self.nodes[id.0], &other.nodes[other_id.0]);
+        if a.kind != b.kind || a.tag_name != b.tag_name || a.namespace != b.namespace || a.attributes != b.attributes || a.text != b.text {
+            return false;
+        }
+        let mut children = self.children(id);
+        let mut other_children = other.children(other_id);
+        loop {
+            match (children.next(), other_children.next()) {
+                (Some(child), Some(other_child)) => {
+                    if !self.same_subtree(child, other, other_child) {
+                        return false;
+                    }
+                },
+                (None, None) => {
+                    return true;
+                },
+                _ => {
+                    return false;
+                },
+            }
+        }
+    }
+
+    pub fn root(&self) -> NodeId {
+        return NodeId(0);
+    }
+
+    // the number of nodes in the arena. slots are never reused, since that would hand a removed node's id
+    // to another node, so this counts detached and removed nodes along with the ones in the tree
+    pub fn len(&self) -> usize {
+        return self.nodes.len();
+    }
+
+    // a tree always has its root, so it is never empty
+    pub fn is_empty(&self) -> bool {
+        return false;
+    }
+
+    pub fn get(&self, id: NodeId) -> &DomTreeNode {
+        return &self.nodes[id.0];
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> &mut DomTreeNode {
+        return &mut self.nodes[id.0];
+    }
+
+    // creating a node only adds it to the arena, it isn't part of the tree until it is appended somewhere
+
+    pub fn create_element(&mut self, tag_name: &str) -> NodeId {
+        return self.push(DomTreeNode::new(DomNodeKind::Element, tag_name, ""));
+    }
+
+    pub fn create_text(&mut self, text: &str) -> NodeId {
+        return self.push(DomTreeNode::new(DomNodeKind::Text, "", text));
+    }
+
+    pub fn create_comment(&mut self, text: &str) -> NodeId {
+        return self.push(DomTreeNode::new(DomNodeKind::Comment, "", text));
+    }
+
+    pub fn create_doctype(&mut self, name: &str, outer_html: &str) -> NodeId {
+        return self.push(DomTreeNode::new(DomNodeKind::Doctype, name, outer_html));
+    }
+
+    fn push(&mut self, node: DomTreeNode) -> NodeId {
+        self.nodes.push(node);
+        return NodeId(self.nodes.len() - 1);
+    }
+
+    // mutation. every method that puts a node somewhere takes it out of wherever it was first,
+    // and panics when asked to put a node inside itself or next to the root
+
+    // makes a node the last child of parent
+    pub fn append(&mut self, parent: NodeId, id: NodeId) {
+        self.link(parent, None, id);
+    }
+
+    // makes a node the first child of parent
+    pub fn prepend(&mut self, parent: NodeId, id: NodeId) {
+        self.link(parent, self.nodes[parent.0].first_child, id);
+    }
+
+    pub fn insert_before(&mut self, reference: NodeId, id: NodeId) {
+        if reference == id {
+            return;
+        }
+        let parent = self.expect_parent(reference);
+        self.link(parent, Some(reference), id);
+    }
+
+    pub fn insert_after(&mut self, reference: NodeId, id: NodeId) {
+        if reference == id {
+            return;
+        }
+        let parent = self.expect_parent(reference);
+        self.link(parent, self.nodes[reference.0].next_sibling, id);
+    }
+
+    // takes a node and everything under it out of the tree. it stays in the arena and can be put back anywhere
+    pub fn detach(&mut self, id: NodeId) {
+        self.unlink(id);
+    }
+
+    // takes a node out of the tree for good and hands back what it held. its id stays valid,
+    // but it has no children left and nothing links to it
+    pub fn remove(&mut self, id: NodeId) -> DomNode {
+        self.unlink(id);
+        let node = self.to_node(id);
+        self.clear_children(id);
+        return node;
+    }
+
+    // puts another node where this one was, this one is left detached
+    pub fn replace_with(&mut self, id: NodeId, replacement: NodeId) {
+        if id == replacement {
+            return;
+        }
+        self.insert_before(id, replacement);
+        self.unlink(id);
+    }
+
+    // puts wrapper where the node was and the node inside it, after anything wrapper already holds
+    pub fn wrap(&mut self, id: NodeId, wrapper: NodeId) {
+        self.insert_before(id, wrapper);
+        self.append(wrapper, id);
+    }
+
+    // replaces a node with its children, the node itself is left detached and empty
+    pub fn unwrap(&mut self, id: NodeId) {
+        let parent = self.expect_parent(id);
+        while let Some(child) = self.nodes[id.0].first_child {
+            self.link(parent, Some(id), child);
+        }
+        self.unlink(id);
+    }
+
+    // sets the value of the attribute with this name, adding it at the end when there isn't one.
+    // names are matched the way html matches them, without regard to ascii case
+    pub fn set_attribute(&mut self, id: NodeId, name: &str, value: &str) {
+        let attributes = &mut self.nodes[id.0].attributes;
+        match attributes.iter_mut().find(|attr| attr.name.eq_ignore_ascii_case(name)) {
+            Some(attr) => {
+                attr.value = value.to_string();
+            },
+            None => {
+                attributes.push(HtmlAttribute::new(&name.to_ascii_lowercase(), value));
+            },
+        }
+    }
+
+    // removes the attribute with this name and hands back its value, if there was one
+    pub fn remove_attribute(&mut self, id: NodeId, name: &str) -> Option<String> {
+        let attributes = &mut self.nodes[id.0].attributes;
+        let i = attributes.iter().position(|attr| attr.name.eq_ignore_ascii_case(name))?;
+        return Some(attributes.remove(i).value);
+    }
+
+    // a text or comment node gets the new text, anything else has its children replaced by one text node.
+    // a doctype or a void element can't have children, so it is an error to give it text
+    pub fn set_text(&mut self, id: NodeId, text: &str) -> Result<(), RipError> {
+        if matches!(self.nodes[id.0].kind, DomNodeKind::Text | DomNodeKind::Comment) {
+            self.nodes[id.0].text = text.to_string();
+            return Ok(());
+        }
+        self.expect_container(id)?;
+        self.clear_children(id);
+        if !text.is_empty() {
+            let child = self.create_text(text);
+            self.append(id, child);
+        }
+        return Ok(());
+    }
+
+    // replaces a node's children with the nodes parsed out of html. the fragment goes through dom_tree_from_html
+    // and has to be balanced the way it wants it, when it isn't the node is left as it was. inside a raw text
+    // element like <script> the html becomes one text node as it is, inside <textarea> or <title> one text node
+    // with its references decoded, the same as a parser would read them there
+    pub fn set_inner_html(&mut self, id: NodeId, html: &str) -> Result<(), RipError> {
+        self.expect_container(id)?;
+        let node = &self.nodes[id.0];
+        let html_element = node.kind == DomNodeKind::Element && node.namespace == DomNamespace::Html;
+        if html_element && is_html_raw_text_element(&node.tag_name) {
+            return self.set_text(id, html);
+        }
+        if html_element && is_html_rcdata_element(&node.tag_name) {
+            return self.set_text(id, &html_decode_text(html));
+        }
+        let fragment = dom_tree_from_html(html)?;
+        self.clear_children(id);
+        for child in &fragment.children {
+            self.append_dom_node(id, child);
+        }
+        return Ok(());
+    }
+
+    // text, comments, doctypes and void elements have no children to replace
+    fn expect_container(&self, id: NodeId) -> Result<(), RipError> {
+        let node = &self.nodes[id.0];
+        let name = match node.kind {
+            DomNodeKind::Root => {
+                return Ok(());
+            },
+            DomNodeKind::Element if node.namespace == DomNamespace::Html && is_html_void_element(&node.tag_name) => { format!("<{}>", node.tag_name) },
+            DomNodeKind::Element => {
+                return Ok(());
+            },
+            DomNodeKind::Text => { "a text node".to_string() },
+            DomNodeKind::Comment => { "a comment".to_string() },
+            DomNodeKind::Doctype => { "a doctype".to_string() },
+        };
+        return Err(RipError::CannotHoldChildren { node: name, span: None });
+    }
+
+    fn clear_children(&mut self, id: NodeId) {
+        while let Some(child) = self.nodes[id.0].first_child {
+            self.unlink(child);
+        }
+    }
+
+    fn expect_parent(&self, id: NodeId) -> NodeId {
+        match self.nodes[id.0].parent {
+            Some(parent) => {
+                return parent;
+            },
+            None => {
+                panic!("the node has no parent, so nothing can go next to it");
+            },
+        }
+    }
+
+    // puts a node under parent right before next, or after its last child when next is None
+    fn link(&mut self, parent: NodeId, next: Option<NodeId>, id: NodeId) {
+        assert!(id != self.root(), "the root can't be put inside another node");
+        assert!(id != parent && !self.ancestors(parent).any(|ancestor| ancestor == id), "a node can't be put inside itself");
+        if next == Some(id) {
+            return;
+        }
+        self.unlink(id);
+        // the previous sibling is looked up after unlinking, since the node may have been it
+        let prev = match next {
+            Some(next) => self.nodes[next.0].prev_sibling,
+            None => self.nodes[parent.0].last_child,
+        };
+        let node = &mut self.nodes[id.0];
+        node.parent = Some(parent);
+        node.prev_sibling = prev;
+        node.next_sibling = next;
+        match prev {
+            Some(prev) => self.nodes[prev.0].next_sibling = Some(id),
+            None => self.nodes[parent.0].first_child = Some(id),
+        }
+        match next {
+            Some(next) => self.nodes[next.0].prev_sibling = Some(id),
+            None => self.nodes[parent.0].last_child = Some(id),
+        }
+    }
+
+    // takes a node out of its parent's children, it keeps its own children
+    fn unlink(&mut self, id: NodeId) {
+        let DomTreeNode { parent, prev_sibling, next_sibling, .. } = self.nodes[id.0];
+        let parent = match parent {
+            Some(parent) => parent,
+            None => {
+                return;
+            },
+        };
+        match prev_sibling {
+            Some(prev) => self.nodes[prev.0].next_sibling = next_sibling,
+            None => self.nodes[parent.0].first_child = next_sibling,
+        }
+        match next_sibling {
+            Some(next) => self.nodes[next.0].prev_sibling = prev_sibling,
+            None => self.nodes[parent.0].last_child = prev_sibling,
+        }
+        let node = &mut self.nodes[id.0];
+        node.parent = None;
+        node.prev_sibling = None;
+        node.next_sibling = None;
+    }
+
+    // navigation
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        return self.nodes[id.0].parent;
+    }
+
+    pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+        return self.nodes[id.0].first_child;
+    }
+
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        return self.nodes[id.0].last_child;
+    }
+
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        return self.nodes[id.0].next_sibling;
+    }
+
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        return self.nodes[id.0].prev_sibling;
+    }
+
+    pub fn children(&self, id: NodeId) -> DomTreeChildren<'_> {
+        return DomTreeChildren { tree: self, next: self.first_child(id) };
+    }
+
+    // the node's parent, then its parent's parent, up to and including the root
+    pub fn ancestors(&self, id: NodeId) -> DomTreeAncestors<'_> {
+        return DomTreeAncestors { tree: self, next: self.parent(id) };
+    }
+
+    // every node under this one in document order, not counting the node itself
+    pub fn descendants(&self, id: NodeId) -> DomTreeDescendants<'_> {
+        return DomTreeDescendants { tree: self, top: id, next: self.first_child(id) };
+    }
+
+    // queries, matched by the same rules as DomNode::query_selector
+
+    // the first element below a node that matches the selector, in document order
+    pub fn query_selector(&self, id: NodeId, selector: &str) -> Result<Option<NodeId>, RipError> {
+        let found = css_query(self.view(id), selector, true)?;
+        return Ok(found.into_iter().next().map(|node| node.id));
+    }
+
+    // every element below a node that matches the selector, in document order,
+    // combinators can only look as far up as that node
+    pub fn query_selector_all(&self, id: NodeId, selector: &str) -> Result<Vec<NodeId>, RipError> {
+        let found = css_query(self.view(id), selector, false)?;
+        return Ok(found.into_iter().map(|node| node.id).collect());
+    }
+
+}
+
+// a node together with the tree it lives in, the handle the matching and serializing code works through
+#[derive(Debug, Clone, Copy)]
+struct DomTreeNodeView<'a> {
+    tree: &'a DomTree,
+    id: NodeId,
+}
+
+impl CssNode for DomTreeNodeView<'_> {
+
+    fn kind(&self) -> &DomNodeKind {
+        return &self.tree.nodes[self.id.0].kind;
+    }
+
+    fn tag_name(&self) -> &str {
+        return &self.tree.nodes[self.id.0].tag_name;
+    }
+
+    fn text(&self) -> &str {
+        return &self.tree.nodes[self.id.0].text;
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        return self.tree.nodes[self.id.0].attributes.iter().find(|attr| attr.name == name).map(|attr| attr.value.as_str());
+    }
+
+    fn children(&self) -> impl Iterator<Item = Self> {
+        let tree = self.tree;
+        return tree.children(self.id).map(move |id| DomTreeNodeView { tree, id });
+    }
+
+}
+
+impl HtmlNode for DomTreeNodeView<'_> {
+
+    fn kind(&self) -> &DomNodeKind {
+        return &self.tree.nodes[self.id.0].kind;
+    }
+
+    fn tag_name(&self) -> &str {
+        return &self.tree.nodes[self.id.0].tag_name;
+    }
+
+    fn namespace(&self) -> DomNamespace {
+        return self.tree.nodes[self.id.0].namespace;
+    }
+
+    fn attributes(&self) -> &[HtmlAttribute] {
+        return &self.tree.nodes[self.id.0].attributes;
+    }
+
+    fn text(&self) -> &str {
+        return &self.tree.nodes[self.id.0].text;
+    }
+
+    // a tree node keeps the declaration as its text
+    fn doctype(&self) -> &str {
+        return &self.tree.nodes[self.id.0].text;
+    }
+
+    fn children(&self) -> impl Iterator<Item = Self> {
+        let tree = self.tree;
+        return tree.children(self.id).map(move |id| DomTreeNodeView { tree, id });
+    }
+
+}
+
+#[derive(Debug)]
+pub struct DomTreeChildren<'a> {
+    tree: &'a DomTree,
+    next: Option<NodeId>,
+}
+
+impl Iterator for DomTreeChildren<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.tree.next_sibling(id);
+        return Some(id);
+    }
+}
+
+#[derive(Debug)]
+pub struct DomTreeAncestors<'a> {
+    tree: &'a DomTree,
+    next: Option<NodeId>,
+}
+
+impl Iterator for DomTreeAncestors<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.tree.parent(id);
+        return Some(id);
+    }
+}
+
+#[derive(Debug)]
+pub struct DomTreeDescendants<'a> {
+    tree: &'a DomTree,
+    top: NodeId,
+    next: Option<NodeId>,
+}
+
+impl Iterator for DomTreeDescendants<'_> {
+    type Item = NodeId;
+
+    // down into the first child when there is one, otherwise on to the next sibling of the closest node
+    // that has one. each node is climbed out of once, so a whole walk is O(1) per step on average
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        if let Some(child) = self.tree.first_child(id) {
+            self.next = Some(child);
+            return Some(id);
+        }
+        let mut up = id;
+        self.next = loop {
+            if up == self.top {
+                break None;
+            }
+            if let Some(sibling) = self.tree.next_sibling(up) {
+                break Some(sibling);
+            }
+            match self.tree.parent(up) {
+                Some(parent) => up = parent,
+                None => break None,
+            }
+        };
+        return Some(id);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::dom::*;
+use crate::dom_tree::*;
+use crate::error::RipError;
+use crate::html5_parse;
+
+fn dom_tree_find(tree: &DomTree, tag_name: &str) -> NodeId {
+    return tree.descendants(tree.root()).find(|id| tree.get(*id).tag_name == tag_name).unwrap();
+}
+
+#[test]
+fn test_dom_tree_navigation() {
+    let tree = DomTree::from_node(&html5_parse("<ul><li id=a>1</li><li>2<b>!</b></li><li>3</li></ul>"));
+    let b = dom_tree_find(&tree, "b");
+    let names: Vec<&str> = tree.ancestors(b).map(|id| tree.get(id).tag_name.as_str()).collect();
+    assert!(names == vec!["li", "ul", "body", "html", ""]);
+    let li = tree.parent(b).unwrap();
+    let first = tree.prev_sibling(li).unwrap();
+    assert!(tree.get(first).attributes[0].value == "a");
+    assert!(tree.prev_sibling(first).is_none());
+    assert!(tree.next_sibling(tree.next_sibling(li).unwrap()).is_none());
+    assert!(tree.get(tree.first_child(li).unwrap()).text == "2");
+    assert!(tree.last_child(li) == Some(b));
+    assert!(tree.ancestors(tree.root()).count() == 0);
+
+    let ul = tree.parent(li).unwrap();
+    let walked: Vec<String> = tree.descendants(ul).map(|id| {
+        let node = tree.get(id);
+        return if node.kind == DomNodeKind::Text { node.text.clone() } else { node.tag_name.clone() };
+    }).collect();
+    assert!(walked == vec!["li", "1", "li", "2", "b", "!", "li", "3"]);
+    // the walk stays inside the node it started from
+    assert!(tree.descendants(li).count() == 3);
+    assert!(tree.children(ul).count() == 3);
+}
+
+#[test]
+fn test_dom_tree_round_trip() {
+    let html = "<!DOCTYPE html><html><head><title>a &amp; b</title></head><body><!--x--><p class=\"c\">1<br>2</p><script>if (a < b) {}</script></body></html>";
+    let root = html5_parse(html);
+    let tree = DomTree::from_node(&root);
+    assert!(tree.to_node(tree.root()) == root);
+    assert!(tree.to_html(tree.root()) == root.to_html());
+    let p = dom_tree_find(&tree, "p");
+    assert!(tree.to_html(p) == "<p class=\"c\">1<br>2</p>");
+    assert!(tree.to_node(p).text == "12");
+}
+
+#[test]
+fn test_dom_tree_parse() {
+    let html = "<!DOCTYPE html><title>a &amp; b</title><template><p>t</template><svg><style>x</style></svg><script>a<b</script><p>1<br>2";
+    let tree = DomTree::parse(html);
+    assert!(tree == DomTree::from_node(&html5_parse(html)));
+    assert!(tree.to_node(tree.root()) == html5_parse(html));
+    // written from the arena, so it matches the markup of the DomNode it stands for
+    assert!(tree.to_html(tree.root()) == html5_parse(html).to_html());
+    let script = dom_tree_find(&tree, "script");
+    assert!(tree.to_html(tree.first_child(script).unwrap()) == "a<b");
+}
+
+#[test]
+fn test_dom_tree_eq() {
+    let mut tree = DomTree::parse("<p>a</p><p>b</p>");
+    let other = tree.clone();
+    // moving nodes around and back changes where they sit in the arena, not the tree
+    let b = tree.last_child(dom_tree_find(&tree, "body")).unwrap();
+    let a = tree.prev_sibling(b).unwrap();
+    tree.detach(a);
+    tree.insert_after(b, a);
+    assert!(tree != other);
+    tree.insert_before(b, a);
+    let detached = tree.create_element("div");
+    tree.set_text(detached, "x").unwrap();
+    assert!(tree == other);
+    assert!(tree.len() != other.len());
+    tree.set_attribute(a, "class", "x");
+    assert!(tree != other);
+}
+
+#[test]
+fn test_dom_tree_append() {
+    let mut tree = DomTree::new();
+    let root = tree.root();
+    let div = tree.create_element("div");
+    let text = tree.create_text("a < b");
+    tree.append(root, div);
+    tree.append(div, text);
+    assert!(tree.to_html(root) == "<div>a &lt; b</div>");
+    // appending a node that is already in the tree moves it
+    let span = tree.create_element("span");
+    tree.append(root, span);
+    tree.append(span, text);
+    assert!(tree.to_html(root) == "<div></div><span>a &lt; b</span>");
+    assert!(tree.first_child(div).is_none());
+    assert!(tree.parent(text) == Some(span));
+    assert!(tree.len() == 4);
+}
+
+#[test]
+fn test_dom_tree_insert() {
+    let mut tree = DomTree::from_node(&html5_parse("<ul><li>b</li></ul>"));
+    let li = dom_tree_find(&tree, "li");
+    let ul = tree.parent(li).unwrap();
+    let a = tree.create_element("li");
+    let c = tree.create_element("li");
+    let first = tree.create_element("li");
+    tree.insert_before(li, a);
+    tree.insert_after(li, c);
+    tree.prepend(ul, first);
+    tree.set_text(a, "a").unwrap();
+    tree.set_text(c, "c").unwrap();
+    assert!(tree.to_html(ul) == "<ul><li></li><li>a</li><li>b</li><li>c</li></ul>");
+    // moving a node that is already in place, or next to itself, changes nothing
+    tree.insert_after(a, li);
+    tree.insert_before(li, li);
+    tree.prepend(ul, first);
+    assert!(tree.to_html(ul) == "<ul><li></li><li>a</li><li>b</li><li>c</li></ul>");
+    tree.insert_after(c, first);
+    tree.insert_before(a, c);
+    assert!(tree.to_html(ul) == "<ul><li>c</li><li>a</li><li>b</li><li></li></ul>");
+    assert!(tree.prev_sibling(c).is_none());
+    assert!(tree.last_child(ul) == Some(first));
+}
+
+#[test]
+fn test_dom_tree_remove() {
+    let mut tree = DomTree::from_node(&html5_parse("<div><p>1</p><img src=pixel.gif><p>2</p></div>"));
+    let img = dom_tree_find(&tree, "img");
+    let p = tree.prev_sibling(img).unwrap();
+    let div = tree.parent(p).unwrap();
+    let removed = tree.remove(img);
+    assert!(removed.to_html() == "<img src=\"pixel.gif\">");
+    assert!(tree.parent(img).is_none());
+    tree.detach(p);
+    assert!(tree.to_html(div) == "<div><p>2</p></div>");
+    // a detached node keeps its children and can go back in
+    tree.append(div, p);
+    assert!(tree.to_html(div) == "<div><p>2</p><p>1</p></div>");
+    let hr = tree.create_element("hr");
+    tree.replace_with(p, hr);
+    assert!(tree.to_html(div) == "<div><p>2</p><hr></div>");
+    assert!(tree.to_html(p) == "<p>1</p>");
+}
+
+#[test]
+fn test_dom_tree_wrap() {
+    let mut tree = DomTree::from_node(&html5_parse("<p>a <b>b</b> c</p>"));
+    let b = dom_tree_find(&tree, "b");
+    let p = tree.parent(b).unwrap();
+    let link = tree.create_element("a");
+    tree.set_attribute(link, "href", "/x?a=1&b=2");
+    tree.wrap(b, link);
+    assert!(tree.to_html(p) == "<p>a <a href=\"/x?a=1&amp;b=2\"><b>b</b></a> c</p>");
+    tree.unwrap(link);
+    tree.unwrap(b);
+    assert!(tree.to_html(p) == "<p>a b c</p>");
+    assert!(tree.children(p).count() == 3);
+    assert!(tree.parent(link).is_none());
+}
+
+#[test]
+fn test_dom_tree_attributes() {
+    let mut tree = DomTree::from_node(&html5_parse("<a href=/a onclick=track() class=x>a</a>"));
+    let a = dom_tree_find(&tree, "a");
+    tree.set_attribute(a, "HREF", "https://example.com/a");
+    tree.set_attribute(a, "rel", "nofollow");
+    assert!(tree.remove_attribute(a, "onclick") == Some("track()".to_string()));
+    assert!(tree.remove_attribute(a, "onclick").is_none());
+    assert!(tree.to_html(a) == "<a href=\"https://example.com/a\" class=\"x\" rel=\"nofollow\">a</a>");
+}
+
+#[test]
+fn test_dom_tree_set_inner_html() {
+    let mut tree = DomTree::from_node(&html5_parse("<head><title>t</title></head><body><p>old</p></body>"));
+    let head = dom_tree_find(&tree, "head");
+    let title = tree.first_child(head).unwrap();
+    let snippet = "<script>if (a < b) track('&amp;')</script><!-- analytics -->";
+    tree.set_inner_html(head, &format!("<meta charset=utf-8>{}", snippet)).unwrap();
+    assert!(tree.to_html(head) == "<head><meta charset=\"utf-8\"><script>if (a < b) track('&amp;')</script><!-- analytics --></head>");
+    assert!(tree.parent(title).is_none());
+
+    let p = dom_tree_find(&tree, "p");
+    assert!(matches!(tree.set_inner_html(p, "<b>x</i>"), Err(RipError::MismatchedCloseTag { .. })));
+    assert!(matches!(tree.set_inner_html(p, "<b>x"), Err(RipError::UnclosedElement { .. })));
+    assert!(tree.to_html(p) == "<p>old</p>");
+    tree.set_inner_html(p, "a &lt; b<br>c").unwrap();
+    assert!(tree.get(tree.first_child(p).unwrap()).text == "a < b");
+    tree.set_text(p, "<new>").unwrap();
+    assert!(tree.to_html(p) == "<p>&lt;new&gt;</p>");
+
+    // a fragment that doesn't parse adds nothing to the arena, one that does adds only its own nodes
+    let len = tree.len();
+    assert!(tree.set_inner_html(p, "<b><i>x</b>").is_err());
+    assert!(tree.len() == len);
+    tree.set_inner_html(p, "<b>x</b>").unwrap();
+    assert!(tree.len() == len + 2);
+}
+
+#[test]
+fn test_dom_tree_escapes_text() {
+    let mut tree = DomTree::from_node(&html5_parse("<textarea></textarea><pre></pre><script></script>"));
+    let textarea = dom_tree_find(&tree, "textarea");
+    tree.set_text(textarea, "</textarea><script>alert(1)</script>").unwrap();
+    assert!(tree.to_html(textarea) == "<textarea>&lt;/textarea&gt;&lt;script&gt;alert(1)&lt;/script&gt;</textarea>");
+    let pre = dom_tree_find(&tree, "pre");
+    tree.set_inner_html(pre, "&lt;b&gt;").unwrap();
+    assert!(tree.to_html(pre) == "<pre>&lt;b&gt;</pre>");
+    // script text was never decoded, so it goes back out as it is
+    let script = dom_tree_find(&tree, "script");
+    tree.set_text(script, "if (a < b) {}").unwrap();
+    assert!(tree.to_html(script) == "<script>if (a < b) {}</script>");
+    let text = tree.first_child(script).unwrap();
+    assert!(tree.to_node(text).outer_html == "if (a < b) {}");
+    assert!(tree.to_node(tree.root()).outer_html == tree.to_html(tree.root()));
+}
+
+#[test]
+fn test_dom_tree_set_inner_html_targets() {
+    let mut tree = DomTree::from_node(&html5_parse("<!DOCTYPE html><title></title><script></script><p>a<br><!-- c --></p>"));
+    let doctype = tree.first_child(tree.root()).unwrap();
+    let br = dom_tree_find(&tree, "br");
+    let text = tree.prev_sibling(br).unwrap();
+    let comment = tree.next_sibling(br).unwrap();
+    // nodes that can't have children are left alone
+    let len = tree.len();
+    for id in [doctype, br, text, comment] {
+        assert!(matches!(tree.set_inner_html(id, "<b>x</b>"), Err(RipError::CannotHoldChildren { .. })));
+    }
+    assert!(tree.set_text(doctype, "x") == Err(RipError::CannotHoldChildren { node: "a doctype".to_string(), span: None }));
+    assert!(tree.set_text(br, "x") == Err(RipError::CannotHoldChildren { node: "<br>".to_string(), span: None }));
+    assert!(tree.len() == len);
+    assert!(tree.children(br).count() == 0);
+    tree.set_text(comment, " d ").unwrap();
+    assert!(tree.to_html(tree.parent(br).unwrap()) == "<p>a<br><!-- d --></p>");
+
+    // script takes the html as it is and title decodes it, each as a single text node, the same as a parser would
+    let script = dom_tree_find(&tree, "script");
+    tree.set_inner_html(script, "if (a < b) { x = '<p>&amp;'; }").unwrap();
+    assert!(tree.children(script).count() == 1);
+    assert!(tree.to_html(script) == "<script>if (a < b) { x = '<p>&amp;'; }</script>");
+    let title = dom_tree_find(&tree, "title");
+    tree.set_inner_html(title, "<b>a &amp; b</b>").unwrap();
+    assert!(tree.children(title).count() == 1);
+    assert!(tree.get(tree.first_child(title).unwrap()).text == "<b>a & b</b>");
+    assert!(tree.to_html(title) == "<title>&lt;b&gt;a &amp; b&lt;/b&gt;</title>");
+}
+
+#[test]
+#[should_panic(expected = "a node can't be put inside itself")]
+fn test_dom_tree_append_into_itself() {
+    let mut tree = DomTree::from_node(&html5_parse("<div><p>a</p></div>"));
+    let p = dom_tree_find(&tree, "p");
+    let div = tree.parent(p).unwrap();
+    tree.append(p, div);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_dom_tree_serde() {
+    let root = html5_parse("<p id=a>x<br></p>");
+    let mut tree = DomTree::from_node(&root);
+    // the json is the DomNode's, detached nodes and the arena's links don't show up in it
+    let detached = tree.create_element("div");
+    tree.set_text(detached, "gone").unwrap();
+    assert!(serde_json::to_value(&tree).unwrap() == serde_json::to_value(&root).unwrap());
+    let back: DomTree = serde_json::from_str(&serde_json::to_string(&tree).unwrap()).unwrap();
+    assert!(back.to_node(back.root()) == root);
+    assert!(back.len() == tree.len() - 2);
+    // links can't be written in by hand
+    assert!(serde_json::from_str::<DomTree>(r#"{"nodes": [{"kind": "Root", "first_child": 7}]}"#).is_err());
+}
+
+#[test]
+fn test_dom_tree_query_selector() {
+    let html = "<ul class=menu><li id=a>1</li><li class=on>2<b>!</b></li><li>3</li></ul><p>x</p>";
+    let mut tree = DomTree::from_node(&html5_parse(html));
+    let ul = tree.query_selector(tree.root(), "ul.menu").unwrap().unwrap();
+    let found = tree.query_selector_all(ul, "li:nth-child(odd)").unwrap();
+    assert!(found.iter().map(|id| tree.to_html(*id)).collect::<Vec<String>>() == vec!["<li id=\"a\">1</li>", "<li>3</li>"]);
+    assert!(tree.query_selector(ul, "li.on > b").unwrap() == Some(dom_tree_find(&tree, "b")));
+    assert!(tree.query_selector(ul, "#a + li + li:last-child").unwrap() == tree.last_child(ul));
+    // the search starts below the node, so ul itself and p never match
+    assert!(tree.query_selector(ul, "ul, p").unwrap().is_none());
+    assert!(matches!(tree.query_selector(ul, "li["), Err(RipError::InvalidSelector { .. })));
+
+    // changes to the tree show up in the next query, and both kinds of tree agree on what matches
+    let li = tree.create_element("li");
+    tree.prepend(ul, li);
+    assert!(tree.query_selector(ul, "li:first-child:empty").unwrap() == Some(li));
+    let node = tree.to_node(tree.root());
+    let selector = "body li:not(.on):nth-last-of-type(-n+3)";
+    let expected: Vec<String> = node.query_selector_all(selector).unwrap().iter().map(|node| node.to_html()).collect();
+    let found: Vec<String> = tree.query_selector_all(tree.root(), selector).unwrap().iter().map(|id| tree.to_html(*id)).collect();
+    assert!(found == expected && found.len() == 2);
+}
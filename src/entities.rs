@@ -0,0 +1,157 @@
+use crate::entities_table::HTML_ENTITIES;
+
+// the longest name in the named reference table, "CounterClockwiseContourIntegral;"
+pub(crate) const HTML_ENTITY_MAX_LEN: usize = 32;
+
+// numeric references in the 0x80..=0x9F range are read as windows-1252, the way browsers do
+const HTML_C1_REPLACEMENTS: [(u32, char); 27] = [
+    (0x80, '\u{20AC}'), (0x82, '\u{201A}'), (0x83, '\u{0192}'), (0x84, '\u{201E}'),
+    (0x85, '\u{2026}'), (0x86, '\u{2020}'), (0x87, '\u{2021}'), (0x88, '\u{02C6}'),
+    (0x89, '\u{2030}'), (0x8A, '\u{0160}'), (0x8B, '\u{2039}'), (0x8C, '\u{0152}'),
+    (0x8E, '\u{017D}'), (0x91, '\u{2018}'), (0x92, '\u{2019}'), (0x93, '\u{201C}'),
+    (0x94, '\u{201D}'), (0x95, '\u{2022}'), (0x96, '\u{2013}'), (0x97, '\u{2014}'),
+    (0x98, '\u{02DC}'), (0x99, '\u{2122}'), (0x9A, '\u{0161}'), (0x9B, '\u{203A}'),
+    (0x9C, '\u{0153}'), (0x9E, '\u{017E}'), (0x9F, '\u{0178}'),
+];
+
+// looks up a name like "amp;" or "amp" (without the '&') in the named reference table
+pub fn html_named_entity(name: &str) -> Option<&'static str> {
+    return match HTML_ENTITIES.binary_search_by(|(entity, _)| entity.as_bytes().cmp(name.as_bytes())) {
+        Ok(i) => Some(HTML_ENTITIES[i].1),
+        Err(_) => None,
+    };
+}
+
+pub fn html_decode_text(text: &str) -> String {
+    return html_decode(text, false);
+}
+
+// attribute values keep legacy references like '&copy' as-is when they run into an '=' or an alphanumeric,
+// so query strings such as '?a=1&copy=2' survive decoding
+pub fn html_decode_attribute(value: &str) -> String {
+    return html_decode(value, true);
+}
+
+fn html_decode(src: &str, in_attribute: bool) -> String {
+    if !src.contains('&') {
+        return src.to_string();
+    }
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let after = &rest[1..];
+        if let Some(numeric) = after.strip_prefix('#') {
+            match html_decode_numeric(numeric) {
+                Some((c, consumed)) => {
+                    out.push(c);
+                    rest = &rest[2 + consumed..];
+                },
+                None => {
+                    out.push('&');
+                    rest = after;
+                },
+            }
+            continue;
+        }
+        match html_decode_named(after, in_attribute) {
+            Some((value, consumed)) => {
+                out.push_str(value);
+                rest = &after[consumed..];
+            },
+            None => {
+                out.push('&');
+                rest = after;
+            },
+        }
+    }
+    out.push_str(rest);
+    return out;
+}
+
+// src starts right after the '&', returns the replacement and how many bytes of src it used
+fn html_decode_named(src: &str, in_attribute: bool) -> Option<(&'static str, usize)> {
+    let bytes = src.as_bytes();
+    let mut candidate_len = 0;
+    while candidate_len < bytes.len() && candidate_len < HTML_ENTITY_MAX_LEN && bytes[candidate_len].is_ascii_alphanumeric() {
+        candidate_len += 1;
+    }
+    if candidate_len < bytes.len() && candidate_len < HTML_ENTITY_MAX_LEN && bytes[candidate_len] == b';' {
+        candidate_len += 1;
+    }
+    // the longest name in the table that the input starts with wins
+    let mut len = candidate_len;
+    while len > 0 {
+        if let Some(value) = html_named_entity(&src[..len]) {
+            let next = bytes.get(len).copied();
+            let legacy = !src[..len].ends_with(';');
+            if in_attribute && legacy && next.is_some_and(|b| b == b'=' || b.is_ascii_alphanumeric()) {
+                return None;
+            }
+            return Some((value, len));
+        }
+        len -= 1;
+    }
+    return None;
+}
+
+// src starts right after the '&#', returns the char and how many bytes of src it used
+fn html_decode_numeric(src: &str) -> Option<(char, usize)> {
+    let bytes = src.as_bytes();
+    let (radix, prefix_len) = match bytes.first() {
+        Some(b'x') | Some(b'X') => (16, 1),
+        _ => (10, 0),
+    };
+    let mut len = prefix_len;
+    while len < bytes.len() && (bytes[len] as char).is_digit(radix) {
+        len += 1;
+    }
+    if len == prefix_len {
+        return None;
+    }
+    // overly long references saturate past the unicode range and become U+FFFD below
+    let code = u32::from_str_radix(&src[prefix_len..len], radix).unwrap_or(u32::MAX);
+    if bytes.get(len) == Some(&b';') {
+        len += 1;
+    }
+    return Some((html_numeric_char(code), len));
+}
+
+pub(crate) fn html_numeric_char(code: u32) -> char {
+    if code == 0 || code > 0x10FFFF || (0xD800..=0xDFFF).contains(&code) {
+        return '\u{FFFD}';
+    }
+    if let Some((_, c)) = HTML_C1_REPLACEMENTS.iter().find(|(from, _)| *from == code) {
+        return *c;
+    }
+    return char::from_u32(code).unwrap_or('\u{FFFD}');
+}
+
+pub fn html_encode_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\u{A0}' => out.push_str("&nbsp;"),
+            _ => out.push(c),
+        }
+    }
+    return out;
+}
+
+// meant for values that will be wrapped in double quotes
+pub fn html_encode_attribute(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '\u{A0}' => out.push_str("&nbsp;"),
+            _ => out.push(c),
+        }
+    }
+    return out;
+}
@@ -0,0 +1,48 @@
+use crate::entities::*;
+
+#[test]
+fn test_html_named_entity() {
+    assert!(html_named_entity("amp;") == Some("&"));
+    assert!(html_named_entity("amp") == Some("&"));
+    assert!(html_named_entity("CounterClockwiseContourIntegral;") == Some("\u{2233}"));
+    assert!(html_named_entity("NotEqualTilde;") == Some("\u{2242}\u{338}"));
+    assert!(html_named_entity("hellip").is_none());
+    assert!(html_named_entity("nope;").is_none());
+}
+
+#[test]
+fn test_html_decode_text() {
+    assert!(html_decode_text("The &lt;em&gt; tag is used for emphasis.") == "The <em> tag is used for emphasis.");
+    assert!(html_decode_text("no references") == "no references");
+    assert!(html_decode_text("&amp;&quot;&apos;&#39;") == "&\"''");
+    assert!(html_decode_text("&#60;&#x3C;&#X3c;") == "<<<");
+    assert!(html_decode_text("caf&eacute; &hellip; &nbsp;") == "café \u{2026} \u{A0}");
+    // legacy references work without the semicolon
+    assert!(html_decode_text("&copy 2024 &ampx") == "\u{A9} 2024 &x");
+    // the longest match wins, '&notin' is a reference but '&notit' only matches '&not'
+    assert!(html_decode_text("&notin; &notit;") == "\u{2209} \u{AC}it;");
+    assert!(html_decode_text("&#0; &#xD800; &#x110000; &#99999999999999;") == "\u{FFFD} \u{FFFD} \u{FFFD} \u{FFFD}");
+    assert!(html_decode_text("&#x80;&#150;") == "\u{20AC}\u{2013}");
+    assert!(html_decode_text("&#65") == "A");
+    assert!(html_decode_text("& &# &#x &#; &bogus; &") == "& &# &#x &#; &bogus; &");
+    assert!(html_decode_text("é&eacute;é") == "ééé");
+}
+
+#[test]
+fn test_html_decode_attribute() {
+    assert!(html_decode_attribute("a&amp;b") == "a&b");
+    assert!(html_decode_attribute("?a=1&copy=2") == "?a=1&copy=2");
+    assert!(html_decode_attribute("?a=1&copyx") == "?a=1&copyx");
+    assert!(html_decode_attribute("&copy;=2") == "\u{A9}=2");
+    assert!(html_decode_attribute("&copy 2024") == "\u{A9} 2024");
+    assert!(html_decode_attribute("&#x3C;") == "<");
+}
+
+#[test]
+fn test_html_encode() {
+    assert!(html_encode_text("a < b && c > d") == "a &lt; b &amp;&amp; c &gt; d");
+    assert!(html_encode_text("\"quoted\" 'text'\u{A0}") == "\"quoted\" 'text'&nbsp;");
+    assert!(html_encode_attribute("say \"hi\" & <bye>") == "say &quot;hi&quot; &amp; <bye>");
+    assert!(html_decode_text(&html_encode_text("<p>&amp;</p>")) == "<p>&amp;</p>");
+    assert!(html_decode_attribute(&html_encode_attribute("a=\"1\"&b")) == "a=\"1\"&b");
+}
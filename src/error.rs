@@ -0,0 +1,124 @@
+use crate::span::Span;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RipError {
+    UnbalancedQuotes { tag: String, span: Option<Span> },
+    TooManySlashes { tag: String, span: Option<Span> },
+    UnclosedRawText { tag_name: String, span: Option<Span> },
+    TagTooShort { tag: String, span: Option<Span> },
+    MissingAngleBrackets { tag: String, span: Option<Span> },
+    MissingTagName { tag: String, span: Option<Span> },
+    UnexpectedToken { expected: String, found: String, span: Option<Span> },
+    UnexpectedCloseTag { tag: String, span: Option<Span> },
+    MismatchedCloseTag { expected: String, found: String, span: Option<Span> },
+    UnclosedElement { tag_name: String, span: Option<Span> },
+    UnclosedComment { span: Option<Span> },
+    InvalidSelector { selector: String, span: Option<Span> },
+    CannotHoldChildren { node: String, span: Option<Span> },
+}
+
+impl RipError {
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RipError::UnbalancedQuotes { span, .. }
+            | RipError::TooManySlashes { span, .. }
+            | RipError::UnclosedRawText { span, .. }
+            | RipError::TagTooShort { span, .. }
+            | RipError::MissingAngleBrackets { span, .. }
+            | RipError::MissingTagName { span, .. }
+            | RipError::UnexpectedToken { span, .. }
+            | RipError::UnexpectedCloseTag { span, .. }
+            | RipError::MismatchedCloseTag { span, .. }
+            | RipError::UnclosedElement { span, .. }
+            | RipError::UnclosedComment { span }
+            | RipError::InvalidSelector { span, .. }
+            | RipError::CannotHoldChildren { span, .. } => {
+                return *span;
+            },
+        }
+    }
+
+    // errors raised while checking a lone tag don't know where the tag sits in the document,
+    // the tokenizer uses this to attach the position once it does
+    pub fn with_span(mut self, new_span: Span) -> RipError {
+        match &mut self {
+            RipError::UnbalancedQuotes { span, .. }
+            | RipError::TooManySlashes { span, .. }
+            | RipError::UnclosedRawText { span, .. }
+            | RipError::TagTooShort { span, .. }
+            | RipError::MissingAngleBrackets { span, .. }
+            | RipError::MissingTagName { span, .. }
+            | RipError::UnexpectedToken { span, .. }
+            | RipError::UnexpectedCloseTag { span, .. }
+            | RipError::MismatchedCloseTag { span, .. }
+            | RipError::UnclosedElement { span, .. }
+            | RipError::UnclosedComment { span }
+            | RipError::InvalidSelector { span, .. }
+            | RipError::CannotHoldChildren { span, .. } => {
+                *span = Some(new_span);
+            },
+        }
+        return self;
+    }
+
+    fn message(&self) -> String {
+        match self {
+            RipError::UnbalancedQuotes { tag, .. } => {
+                return format!("the following tag makes poor use of quotes and is malformed: {}", tag);
+            },
+            RipError::TooManySlashes { tag, .. } => {
+                return format!("the following tag has more than 1 '/' character outside of quotes: {}", tag);
+            },
+            RipError::UnclosedRawText { tag_name, .. } => {
+                return format!("failed to find a closing tag for <{}>", tag_name);
+            },
+            RipError::TagTooShort { tag, .. } => {
+                return format!("{} is less than 3 chars, valid html tags must be 3 or more chars", tag);
+            },
+            RipError::MissingAngleBrackets { tag, .. } => {
+                return format!("{} does not start with a '<' and end with a '>' which is required for html tags", tag);
+            },
+            RipError::MissingTagName { tag, .. } => {
+                return format!("{} does not contain a tag name", tag);
+            },
+            RipError::UnexpectedToken { expected, found, .. } => {
+                return format!("expected {} but found {}", expected, found);
+            },
+            RipError::UnexpectedCloseTag { tag, .. } => {
+                return format!("found the closing tag {} without a matching opening tag", tag);
+            },
+            RipError::MismatchedCloseTag { expected, found, .. } => {
+                return format!("expected a closing tag for <{}> but found {}", expected, found);
+            },
+            RipError::UnclosedElement { tag_name, .. } => {
+                return format!("the tag <{}> was never closed", tag_name);
+            },
+            RipError::UnclosedComment { .. } => {
+                return "found a comment without a closing '-->'".to_string();
+            },
+            RipError::InvalidSelector { selector, .. } => {
+                return format!("{} is not a valid css selector", selector);
+            },
+            RipError::CannotHoldChildren { node, .. } => {
+                return format!("{} can't hold children", node);
+            },
+        }
+    }
+}
+
+impl fmt::Display for RipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span() {
+            Some(span) => {
+                return write!(f, "{} (line {}, column {})", self.message(), span.line, span.column);
+            },
+            None => {
+                return write!(f, "{}", self.message());
+            },
+        }
+    }
+}
+
+impl std::error::Error for RipError {}
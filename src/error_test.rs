@@ -0,0 +1,23 @@
+use crate::error::RipError;
+use crate::span::Span;
+
+#[test]
+fn test_rip_error_display() {
+    let err = RipError::TooManySlashes { tag: "<hr//>".to_string(), span: None };
+    assert!(err.to_string() == "the following tag has more than 1 '/' character outside of quotes: <hr//>");
+    let err = RipError::UnclosedRawText { tag_name: "script".to_string(), span: None };
+    assert!(err.to_string() == "failed to find a closing tag for <script>");
+    let err = RipError::MismatchedCloseTag { expected: "p".to_string(), found: "</div>".to_string(), span: None };
+    assert!(err.to_string() == "expected a closing tag for <p> but found </div>");
+    let boxed: Box<dyn std::error::Error> = Box::new(RipError::TagTooShort { tag: "<>".to_string(), span: None });
+    assert!(boxed.to_string() == "<> is less than 3 chars, valid html tags must be 3 or more chars");
+}
+
+#[test]
+fn test_rip_error_span() {
+    let err = RipError::TooManySlashes { tag: "<hr//>".to_string(), span: None };
+    assert!(err.span().is_none());
+    let err = err.with_span(Span::new(10, 16, 2, 4));
+    assert!(err.span() == Some(Span::new(10, 16, 2, 4)));
+    assert!(err.to_string() == "the following tag has more than 1 '/' character outside of quotes: <hr//> (line 2, column 4)");
+}
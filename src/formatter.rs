@@ -0,0 +1,391 @@
+use crate::tokenizer::{html_tokenize_lenient, TokenHtml};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HtmlFormatOptions {
+    // spaces per level of nesting, ignored when indenting with tabs
+    pub indent_width: usize,
+    // one tab per level of nesting instead of spaces
+    pub use_tabs: bool,
+    // inline content that would run past this many chars is wrapped at its whitespace, and an opening tag with
+    // attributes that still doesn't fit gets one attribute per line. tabs count as indent_width chars. 0 never wraps
+    pub max_line_width: usize,
+    // squeezes every run of whitespace in text down to a single space, otherwise each line of the text is kept
+    pub collapse_whitespace: bool,
+}
+
+impl Default for HtmlFormatOptions {
+    fn default() -> Self {
+        return HtmlFormatOptions { indent_width: 2, use_tabs: false, max_line_width: 100, collapse_whitespace: true };
+    }
+}
+
+pub fn html_format(html: &str) -> String {
+    return html_format_with_options(html, HtmlFormatOptions::default());
+}
+
+// pretty-prints html with every block-level tag on its own line, indented one level per level of nesting.
+// inline content like text, <b> or <a> is never split where the source had no whitespace, since a line break
+// there would show up as a space: a run of it stays on one line and is only wrapped at whitespace it already had.
+// a block element holding nothing but inline content that fits stays on one line, whitespace between blocks is
+// dropped since the new indentation takes its place, and the bodies of prelike elements like pre and script are
+// copied through byte for byte. malformed markup is formatted the way html_tokenize_lenient reads it
+pub fn html_format_with_options(html: &str, options: HtmlFormatOptions) -> String {
+    let toks: Vec<TokenHtml> = html_tokenize_lenient(html).tokens.into_iter().map(|tok| tok.token).collect();
+    let mut f = HtmlFormatter { options, out: String::new() };
+    // the names of the block elements the current line is nested in
+    let mut open: Vec<String> = vec![];
+    // the inline content waiting to be written out once the next block boundary is reached
+    let mut run = HtmlFormatRun::default();
+    let mut i = 0;
+    while i < toks.len() {
+        match &toks[i] {
+            TokenHtml::Open { tag_name, outer_html } if html_format_is_block(tag_name) => {
+                f.run(open.len(), &mut run);
+                // an element whose end tag may be left out is closed by the next one like it
+                if HTML_FORMAT_IMPLIED_END_TAGS.contains(&tag_name.as_str()) && open.last() == Some(tag_name) {
+                    open.pop();
+                }
+                if let Some(end) = f.single_line(open.len(), &toks, i) {
+                    i = end + 1;
+                    continue;
+                }
+                f.tag(open.len(), outer_html);
+                open.push(tag_name.clone());
+            },
+            TokenHtml::Close { tag_name, outer_html } if html_format_is_block(tag_name) => {
+                f.run(open.len(), &mut run);
+                if let Some(j) = open.iter().rposition(|name| name == tag_name) {
+                    open.truncate(j);
+                }
+                f.line(open.len(), outer_html);
+            },
+            TokenHtml::SelfClosing { tag_name, outer_html } if html_format_is_block(tag_name) => {
+                f.run(open.len(), &mut run);
+                f.tag(open.len(), outer_html);
+            },
+            TokenHtml::PreLikeOpen { tag_name, .. } => {
+                // the body and closing tag follow right after the opening tag, exactly as they were written
+                let (element, end) = html_format_prelike(&toks, i);
+                i = end;
+                if html_format_is_block(tag_name) {
+                    f.run(open.len(), &mut run);
+                    f.line(open.len(), &element);
+                } else {
+                    run.push(&element, false);
+                }
+            },
+            TokenHtml::Doctype { outer_html, .. } | TokenHtml::PreLikeClose { outer_html, .. } => {
+                f.run(open.len(), &mut run);
+                f.line(open.len(), outer_html);
+            },
+            TokenHtml::Comment { text } if run.words.is_empty() => {
+                f.line(open.len(), &format!("<!--{}-->", text));
+            },
+            tok => {
+                run.inline(tok, options.collapse_whitespace);
+            },
+        }
+        i += 1;
+    }
+    f.run(open.len(), &mut run);
+    return f.out;
+}
+
+// a piece of inline content with no whitespace in it, the only places a run of it can go onto a new line
+// are between words
+#[derive(Debug, Default)]
+struct HtmlFormatWord {
+    text: String,
+    // the word is a single tag, so its attributes can be wrapped when it doesn't fit
+    tag: bool,
+    // the source had a newline or a <br> right before the word, so it starts a line of its own
+    newline: bool,
+}
+
+#[derive(Debug, Default)]
+struct HtmlFormatRun {
+    words: Vec<HtmlFormatWord>,
+    // whitespace came after the last word, so whatever comes next starts a new one
+    space: bool,
+    newline: bool,
+}
+
+impl HtmlFormatRun {
+
+    fn push(&mut self, text: &str, tag: bool) {
+        match self.words.last_mut() {
+            Some(word) if !self.space && !self.newline => {
+                word.text.push_str(text);
+                word.tag = false;
+            },
+            _ => {
+                self.words.push(HtmlFormatWord { text: text.to_string(), tag, newline: self.newline });
+            },
+        }
+        self.space = false;
+        self.newline = false;
+    }
+
+    fn inline(&mut self, tok: &TokenHtml, collapse_whitespace: bool) {
+        match tok {
+            TokenHtml::Open { tag_name, outer_html } | TokenHtml::SelfClosing { tag_name, outer_html } => {
+                self.push(outer_html, true);
+                // whatever follows a line break may as well start on a new line
+                if tag_name.eq_ignore_ascii_case("br") {
+                    self.newline = true;
+                }
+            },
+            TokenHtml::Close { outer_html, .. } => {
+                self.push(outer_html, true);
+            },
+            TokenHtml::Comment { text } => {
+                self.push(&format!("<!--{}-->", text), false);
+            },
+            TokenHtml::InnerText { text } | TokenHtml::Whitespace { text } => {
+                self.text(text, collapse_whitespace);
+            },
+            _ => {},
+        }
+    }
+
+    // collapsed text is split into words at every run of whitespace, otherwise only newlines split it
+    // and the spaces inside a line are kept
+    fn text(&mut self, text: &str, collapse_whitespace: bool) {
+        for (n, line) in text.split('\n').enumerate() {
+            if n > 0 {
+                self.space = true;
+                self.newline |= !collapse_whitespace;
+            }
+            if !collapse_whitespace {
+                // the spaces around a line are trimmed once it is written out
+                if !line.is_empty() {
+                    self.push(line, false);
+                }
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                self.space = true;
+            }
+            for (m, word) in line.split_whitespace().enumerate() {
+                if m > 0 {
+                    self.space = true;
+                }
+                self.push(word, false);
+            }
+            if line.ends_with(char::is_whitespace) {
+                self.space = true;
+            }
+        }
+    }
+
+}
+
+struct HtmlFormatter {
+    options: HtmlFormatOptions,
+    out: String,
+}
+
+impl HtmlFormatter {
+
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            if self.options.use_tabs {
+                self.out.push('\t');
+            } else {
+                self.out.push_str(&" ".repeat(self.options.indent_width));
+            }
+        }
+    }
+
+    fn line(&mut self, depth: usize, line: &str) {
+        if line.is_empty() {
+            return;
+        }
+        self.indent(depth);
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    fn too_long(&self, depth: usize, line: &str) -> bool {
+        return self.options.max_line_width > 0 && depth * self.options.indent_width + line.chars().count() > self.options.max_line_width;
+    }
+
+    // an opening tag, with its attributes one per line a level deeper when it doesn't fit
+    fn tag(&mut self, depth: usize, outer_html: &str) {
+        if !self.too_long(depth, outer_html) {
+            self.line(depth, outer_html);
+            return;
+        }
+        let (name, attributes, end) = match html_format_split_tag(outer_html) {
+            Some(parts) if parts.1.len() > 1 => parts,
+            _ => {
+                self.line(depth, outer_html);
+                return;
+            },
+        };
+        self.line(depth, name);
+        for attr in attributes {
+            self.line(depth + 1, attr);
+        }
+        self.line(depth, end);
+    }
+
+    // the lines a run of inline content is written as, each one as many words as fit. a word that doesn't
+    // fit by itself gets a line of its own, and is returned with true if it is a tag that can be wrapped
+    fn run_lines(&self, depth: usize, run: &HtmlFormatRun) -> Vec<(String, bool)> {
+        let mut lines: Vec<(String, bool)> = vec![];
+        for word in &run.words {
+            if let Some((line, tag)) = lines.last_mut() {
+                let joined = format!("{} {}", line, word.text);
+                if !word.newline && !self.too_long(depth, &joined) {
+                    *line = joined;
+                    *tag = false;
+                    continue;
+                }
+            }
+            lines.push((word.text.clone(), word.tag));
+        }
+        return lines.into_iter().map(|(line, tag)| (line.trim().to_string(), tag)).filter(|(line, _)| !line.is_empty()).collect();
+    }
+
+    // writes out the inline content gathered so far and starts a new run
+    fn run(&mut self, depth: usize, run: &mut HtmlFormatRun) {
+        for (line, tag) in self.run_lines(depth, run) {
+            if tag {
+                self.tag(depth, &line);
+            } else {
+                self.line(depth, &line);
+            }
+        }
+        *run = HtmlFormatRun::default();
+    }
+
+    // writes the block element opened at toks[start] on one line when everything in it is inline content that
+    // fits, and returns the index of its closing tag
+    fn single_line(&mut self, depth: usize, toks: &[TokenHtml], start: usize) -> Option<usize> {
+        let (tag_name, outer_html) = match &toks[start] {
+            TokenHtml::Open { tag_name, outer_html } => (tag_name, outer_html),
+            _ => return None,
+        };
+        let mut run = HtmlFormatRun::default();
+        let mut i = start + 1;
+        loop {
+            match toks.get(i)? {
+                TokenHtml::Close { tag_name: close_name, outer_html: close_html } if close_name == tag_name => {
+                    let lines = self.run_lines(0, &run);
+                    if lines.len() > 1 {
+                        return None;
+                    }
+                    let inner = lines.first().map(|(line, _)| line.as_str()).unwrap_or_default();
+                    let line = format!("{}{}{}", outer_html, inner, close_html);
+                    if self.too_long(depth, &line) {
+                        return None;
+                    }
+                    self.line(depth, &line);
+                    return Some(i);
+                },
+                TokenHtml::Open { tag_name, .. } | TokenHtml::Close { tag_name, .. } | TokenHtml::SelfClosing { tag_name, .. } if html_format_is_block(tag_name) => {
+                    return None;
+                },
+                TokenHtml::PreLikeOpen { .. } | TokenHtml::PreLikeClose { .. } | TokenHtml::Doctype { .. } => {
+                    return None;
+                },
+                tok => {
+                    run.inline(tok, self.options.collapse_whitespace);
+                },
+            }
+            i += 1;
+        }
+    }
+
+}
+
+// the prelike element opened at toks[start] as it was written, and the index of its last token
+fn html_format_prelike(toks: &[TokenHtml], start: usize) -> (String, usize) {
+    let mut element = match &toks[start] {
+        TokenHtml::PreLikeOpen { outer_html, .. } => outer_html.clone(),
+        _ => String::new(),
+    };
+    let mut i = start;
+    while let Some(tok) = toks.get(i + 1) {
+        i += 1;
+        match tok {
+            TokenHtml::InnerText { text } | TokenHtml::Whitespace { text } => {
+                element.push_str(text);
+            },
+            TokenHtml::PreLikeClose { outer_html, .. } => {
+                element.push_str(outer_html);
+                break;
+            },
+            _ => {},
+        }
+    }
+    return (element, i);
+}
+
+// splits an opening tag into '<name', each attribute as written and the closing '>' or '/>'
+fn html_format_split_tag(tag: &str) -> Option<(&str, Vec<&str>, &str)> {
+    let inner = tag.strip_prefix('<')?.strip_suffix('>')?;
+    let (inner, end) = match inner.trim_end().strip_suffix('/') {
+        Some(inner) => (inner, "/>"),
+        None => (inner, ">"),
+    };
+    let name_end = inner.find(|c: char| c.is_whitespace()).unwrap_or(inner.len());
+    let name = &tag[..name_end + 1];
+    let bytes = inner.as_bytes();
+    let mut attributes: Vec<&str> = vec![];
+    let mut i = name_end;
+    // quotes and '=' are ascii, so stepping over bytes never splits a char that matters
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'=' {
+            i += 1;
+        }
+        let mut j = i;
+        while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+            j += 1;
+        }
+        if j < bytes.len() && bytes[j] == b'=' {
+            i = j + 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            match bytes.get(i) {
+                Some(quote) if *quote == b'"' || *quote == b'\'' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != *quote {
+                        i += 1;
+                    }
+                    i = (i + 1).min(bytes.len());
+                },
+                _ => {
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                        i += 1;
+                    }
+                },
+            }
+        }
+        attributes.push(&inner[start..i]);
+    }
+    return Some((name, attributes, end));
+}
+
+// elements that are commonly written without their end tag, a second one opening right after the first is its sibling
+const HTML_FORMAT_IMPLIED_END_TAGS: [&str; 8] = ["li", "p", "dt", "dd", "option", "tr", "td", "th"];
+
+// elements laid out as blocks, a line break before or after one never shows up on the page. anything else,
+// including elements this list doesn't know, is treated as inline content
+const HTML_FORMAT_BLOCK_ELEMENTS: [&str; 59] = [
+    "address", "article", "aside", "base", "blockquote", "body", "caption", "col", "colgroup", "dd", "details", "dialog", "div", "dl", "dt",
+    "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup", "hr", "html",
+    "legend", "li", "link", "main", "menu", "meta", "nav", "noscript", "ol", "optgroup", "option", "p", "pre", "script", "section", "style",
+    "summary", "table", "tbody", "td", "template", "tfoot", "th", "thead", "title", "tr", "ul", "xmp",
+];
+
+fn html_format_is_block(tag_name: &str) -> bool {
+    return HTML_FORMAT_BLOCK_ELEMENTS.iter().any(|block| block.eq_ignore_ascii_case(tag_name));
+}
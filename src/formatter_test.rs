@@ -0,0 +1,81 @@
+use crate::*;
+
+#[test]
+fn test_html_format() {
+    let html = "<div class='card'><h1>Title</h1>\n<ul><li>One<li>Two</ul><br><!-- note --></div>";
+    assert!(html_format(html) == [
+        "<div class='card'>",
+        "  <h1>Title</h1>",
+        "  <ul>",
+        "    <li>",
+        "      One",
+        "    <li>",
+        "      Two",
+        "  </ul>",
+        "  <br>",
+        "  <!-- note -->",
+        "</div>",
+        "",
+    ].join("\n"));
+}
+
+#[test]
+fn test_html_format_prelike() {
+    let html = "<div><pre>\n  keep   this\n</pre><script>if (a < b) { go(); }</script></div>";
+    assert!(html_format(html) == "<div>\n  <pre>\n  keep   this\n</pre>\n  <script>if (a < b) { go(); }</script>\n</div>\n");
+}
+
+#[test]
+fn test_html_format_indentation() {
+    let html = "<ul>\n<li>One</li>\n</ul>";
+    let options = HtmlFormatOptions { indent_width: 4, ..HtmlFormatOptions::default() };
+    assert!(html_format_with_options(html, options) == "<ul>\n    <li>One</li>\n</ul>\n");
+    let options = HtmlFormatOptions { use_tabs: true, ..HtmlFormatOptions::default() };
+    assert!(html_format_with_options(html, options) == "<ul>\n\t<li>One</li>\n</ul>\n");
+}
+
+#[test]
+fn test_html_format_wrapping() {
+    let html = "<div><input type=\"text\" name='q' placeholder=\"Search the docs\" required/></div>";
+    let options = HtmlFormatOptions { max_line_width: 40, ..HtmlFormatOptions::default() };
+    assert!(html_format_with_options(html, options) == [
+        "<div>",
+        "  <input",
+        "    type=\"text\"",
+        "    name='q'",
+        "    placeholder=\"Search the docs\"",
+        "    required",
+        "  />",
+        "</div>",
+        "",
+    ].join("\n"));
+    // short tags and tags with a single attribute are never wrapped
+    let options = HtmlFormatOptions { max_line_width: 10, ..HtmlFormatOptions::default() };
+    assert!(html_format_with_options("<p><a href='/a/long/path'>x</a></p>", options) == "<p>\n  <a href='/a/long/path'>x</a>\n</p>\n");
+}
+
+#[test]
+fn test_html_format_inline() {
+    assert!(html_format("<p>Hello <b>world</b>!</p>") == "<p>Hello <b>world</b>!</p>\n");
+    assert!(html_format("<div><span>a</span><span>b</span></div>") == "<div><span>a</span><span>b</span></div>\n");
+    assert!(html_format("<div><h1>Title</h1><span>a</span><span>b</span> <i>c</i></div>") == "<div>\n  <h1>Title</h1>\n  <span>a</span><span>b</span> <i>c</i>\n</div>\n");
+    // a line break is already a place to start a new line
+    assert!(html_format("<p>a<br>b</p>") == "<p>\n  a<br>\n  b\n</p>\n");
+    // long runs are only wrapped where the source had whitespace
+    let options = HtmlFormatOptions { max_line_width: 20, ..HtmlFormatOptions::default() };
+    let html = "<div><p>one two <b>three</b>four five</p></div>";
+    assert!(html_format_with_options(html, options) == "<div>\n  <p>\n    one two\n    <b>three</b>four\n    five\n  </p>\n</div>\n");
+    let options = HtmlFormatOptions { collapse_whitespace: false, ..HtmlFormatOptions::default() };
+    assert!(html_format_with_options("<p>a  <em>b</em>\n  c</p>", options) == "<p>\n  a  <em>b</em>\n  c\n</p>\n");
+}
+
+#[test]
+fn test_html_format_whitespace() {
+    let html = "<p>\n   Some    text\n   over two lines\n</p>";
+    assert!(html_format(html) == "<p>Some text over two lines</p>\n");
+    let options = HtmlFormatOptions { collapse_whitespace: false, ..HtmlFormatOptions::default() };
+    assert!(html_format_with_options(html, options) == "<p>\n  Some    text\n  over two lines\n</p>\n");
+    // prelike bodies are never touched
+    let html = "<textarea>  a\n\n  b  </textarea><style>p  { }</style><xmp> <b> </xmp>";
+    assert!(html_format(html) == "<textarea>  a\n\n  b  </textarea>\n<style>p  { }</style>\n<xmp> <b> </xmp>\n");
+}
@@ -0,0 +1,2586 @@
+use crate::dom::{DomNamespace, DomNode};
+use crate::dom_tree::{DomTree, NodeId};
+use crate::html5_tokenizer::{Html5Event, Html5ParseError, Html5Token, Html5Tokenizer, Html5TokenizerState};
+use crate::html_utils::{is_html_raw_text_element, is_html_void_element, HtmlAttribute};
+use crate::serializer::dom_fill_outer_html;
+use crate::span::Span;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Html5ParserOptions {
+    // parse as a browser with scripting turned on would, which reads the inside of <noscript> as raw text
+    pub scripting: bool,
+}
+
+// the tree is always built, errors only record where the markup broke the rules and how it was repaired
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Html5ParseResult {
+    pub root: DomNode,
+    pub errors: Vec<Html5ParseError>,
+}
+
+// builds the tree a browser would, following the tree construction rules of the HTML Living Standard.
+// missing html, head and body elements are filled in, optional end tags are implied and misnested markup
+// is repaired, so any input gives a document
+pub fn html5_parse(html: &str) -> DomNode {
+    return html5_parse_with_options(html, Html5ParserOptions::default()).root;
+}
+
+pub fn html5_parse_with_options(html: &str, options: Html5ParserOptions) -> Html5ParseResult {
+    let mut builder = Html5TreeBuilder::new(html, options);
+    builder.run();
+    return builder.finish();
+}
+
+// the same document html5_parse builds, put straight into a DomTree's arena
+pub(crate) fn html5_parse_tree(html: &str, options: Html5ParserOptions) -> DomTree {
+    let mut builder = Html5TreeBuilder::new(html, options);
+    builder.run();
+    let mut tree = DomTree::new();
+    let root = tree.root();
+    for child in &builder.nodes[HTML5_DOCUMENT].children {
+        builder.tree_node(*child, &mut tree, root);
+    }
+    return tree;
+}
+
+// parses html as the contents of a context element, the way innerHTML does. the root's children are the
+// nodes that would end up inside the context element
+pub fn html5_parse_fragment(html: &str, context_tag: &str, context_namespace: DomNamespace, options: Html5ParserOptions) -> Html5ParseResult {
+    let mut builder = Html5TreeBuilder::new(html, options);
+    builder.start_fragment(context_tag, context_namespace);
+    builder.run();
+    return builder.finish();
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Html5InsertionMode {
+    Initial,
+    BeforeHtml,
+    BeforeHead,
+    InHead,
+    InHeadNoscript,
+    AfterHead,
+    InBody,
+    Text,
+    InTable,
+    InTableText,
+    InCaption,
+    InColumnGroup,
+    InTableBody,
+    InRow,
+    InCell,
+    InSelect,
+    InSelectInTable,
+    InTemplate,
+    AfterBody,
+    InFrameset,
+    AfterFrameset,
+    AfterAfterBody,
+    AfterAfterFrameset,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Html5Scope {
+    Default,
+    ListItem,
+    Button,
+    Table,
+    Select,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Html5TagToken {
+    name: String,
+    attributes: Vec<HtmlAttribute>,
+    self_closing: bool,
+}
+
+// tokens as the tree builder sees them, runs of characters arrive split so every char in a run is
+// whitespace, every char is NUL or none of them are either
+#[derive(Debug, Clone)]
+enum Html5TreeToken {
+    Doctype { name: Option<String>, public_id: Option<String>, system_id: Option<String>, force_quirks: bool, outer_html: String },
+    StartTag(Html5TagToken),
+    EndTag(String),
+    Characters(String),
+    Comment(String),
+    Eof,
+}
+
+#[derive(Debug)]
+enum Html5NodeData {
+    Document,
+    Doctype { name: String, outer_html: String },
+    Element { tag_name: String, namespace: DomNamespace, attributes: Vec<HtmlAttribute>, html_integration_point: bool, template_content: Option<usize> },
+    // the children of a template element live here rather than on the element itself
+    TemplateContent,
+    Text(String),
+    Comment(String),
+}
+
+#[derive(Debug)]
+struct Html5Node {
+    data: Html5NodeData,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Html5FormattingEntry {
+    Marker,
+    Element(usize, Html5TagToken),
+}
+
+const HTML5_DOCUMENT: usize = 0;
+
+struct Html5TreeBuilder<'a> {
+    source: &'a str,
+    tokenizer: Html5Tokenizer,
+    options: Html5ParserOptions,
+    nodes: Vec<Html5Node>,
+    mode: Html5InsertionMode,
+    original_mode: Html5InsertionMode,
+    template_modes: Vec<Html5InsertionMode>,
+    open_elements: Vec<usize>,
+    active_formatting: Vec<Html5FormattingEntry>,
+    head_element: Option<usize>,
+    form_element: Option<usize>,
+    // the element given to html5_parse_fragment, it never becomes part of the tree
+    context: Option<usize>,
+    quirks_mode: bool,
+    frameset_ok: bool,
+    foster_parenting: bool,
+    // a newline right after <pre>, <listing> or <textarea> is dropped
+    skip_newline: bool,
+    pending_table_text: Vec<String>,
+    errors: Vec<Html5ParseError>,
+    // where the token being processed sits in the source, tree construction errors are reported there
+    span: Span,
+    done: bool,
+}
+
+impl<'a> Html5TreeBuilder<'a> {
+
+    fn new(source: &'a str, options: Html5ParserOptions) -> Html5TreeBuilder<'a> {
+        return Html5TreeBuilder {
+            source,
+            tokenizer: Html5Tokenizer::new(source),
+            options,
+            nodes: vec![Html5Node { data: Html5NodeData::Document, parent: None, children: vec![] }],
+            mode: Html5InsertionMode::Initial,
+            original_mode: Html5InsertionMode::Initial,
+            template_modes: vec![],
+            open_elements: vec![],
+            active_formatting: vec![],
+            head_element: None,
+            form_element: None,
+            context: None,
+            quirks_mode: false,
+            frameset_ok: true,
+            foster_parenting: false,
+            skip_newline: false,
+            pending_table_text: vec![],
+            errors: vec![],
+            span: Span::new(0, 0, 1, 1),
+            done: false,
+        };
+    }
+
+    fn start_fragment(&mut self, context_tag: &str, context_namespace: DomNamespace) {
+        let context_token = Html5TagToken { name: context_tag.to_string(), attributes: vec![], self_closing: false };
+        let context = self.create_element(&context_token, context_namespace);
+        self.context = Some(context);
+        if context_namespace == DomNamespace::Html {
+            let state = match context_tag {
+                "title" | "textarea" => Some(Html5TokenizerState::RcData),
+                "style" | "xmp" | "iframe" | "noembed" | "noframes" => Some(Html5TokenizerState::RawText),
+                "noscript" if self.options.scripting => Some(Html5TokenizerState::RawText),
+                "script" => Some(Html5TokenizerState::ScriptData),
+                "plaintext" => Some(Html5TokenizerState::PlainText),
+                _ => None,
+            };
+            if let Some(state) = state {
+                self.tokenizer.set_state(state);
+            }
+            self.tokenizer.set_last_start_tag(Some(context_tag));
+        }
+        let html_token = Html5TagToken { name: "html".to_string(), attributes: vec![], self_closing: false };
+        let html = self.create_element(&html_token, DomNamespace::Html);
+        self.append_child(HTML5_DOCUMENT, html);
+        self.open_elements.push(html);
+        if context_namespace == DomNamespace::Html && context_tag == "template" {
+            self.template_modes.push(Html5InsertionMode::InTemplate);
+        }
+        self.reset_insertion_mode();
+    }
+
+    fn run(&mut self) {
+        while !self.done {
+            let allow_cdata = match self.adjusted_current_node() {
+                Some(node) => self.namespace(node) != DomNamespace::Html,
+                None => false,
+            };
+            self.tokenizer.set_allow_cdata(allow_cdata);
+            let event = match self.tokenizer.next_event() {
+                Some(event) => event,
+                None => {
+                    self.span = Span::new(self.source.len(), self.source.len(), self.span.line, self.span.column);
+                    self.dispatch(Html5TreeToken::Eof);
+                    break;
+                },
+            };
+            let tok = match event {
+                Html5Event::Error(err) => {
+                    self.errors.push(err);
+                    continue;
+                },
+                Html5Event::Token(tok) => tok,
+            };
+            self.span = tok.span;
+            let skip_newline = std::mem::replace(&mut self.skip_newline, false);
+            match tok.token {
+                Html5Token::Character { text } => {
+                    let text = if skip_newline { text.strip_prefix('\n').unwrap_or(&text).to_string() } else { text };
+                    for run in html5_char_runs(&text) {
+                        self.dispatch(Html5TreeToken::Characters(run.to_string()));
+                    }
+                },
+                Html5Token::StartTag { tag_name, attributes, self_closing } => {
+                    self.dispatch(Html5TreeToken::StartTag(Html5TagToken { name: tag_name, attributes, self_closing }));
+                },
+                Html5Token::EndTag { tag_name } => {
+                    self.dispatch(Html5TreeToken::EndTag(tag_name));
+                },
+                Html5Token::Comment { text } => {
+                    self.dispatch(Html5TreeToken::Comment(text));
+                },
+                Html5Token::Doctype { name, public_id, system_id, force_quirks } => {
+                    let outer_html = self.source[tok.span.start..tok.span.end].to_string();
+                    self.dispatch(Html5TreeToken::Doctype { name, public_id, system_id, force_quirks, outer_html });
+                },
+            }
+        }
+    }
+
+    fn finish(self) -> Html5ParseResult {
+        let mut root = DomNode::new();
+        let (top, raw_text) = match self.context {
+            // a fragment is everything that was parsed into the html element standing in for the context
+            Some(context) => {
+                let raw_text = self.namespace(context) == DomNamespace::Html && html5_is_raw_text_element(self.element_name(context), self.options.scripting);
+                (self.nodes[HTML5_DOCUMENT].children[0], raw_text)
+            },
+            None => (HTML5_DOCUMENT, false),
+        };
+        for child in &self.nodes[top].children {
+            root.append(self.dom_node(*child));
+        }
+        dom_fill_outer_html(&mut root, raw_text, self.options.scripting);
+        return Html5ParseResult { root, errors: self.errors };
+    }
+
+    // outer_html is left for dom_fill_outer_html to write once the whole tree is built
+    fn dom_node(&self, id: usize) -> DomNode {
+        match &self.nodes[id].data {
+            Html5NodeData::Doctype { name, outer_html } => {
+                return DomNode::new_doctype(name, outer_html);
+            },
+            Html5NodeData::Text(text) => {
+                return DomNode::new_text(text);
+            },
+            Html5NodeData::Comment(text) => {
+                return DomNode::new_comment(text, "");
+            },
+            Html5NodeData::Element { tag_name, namespace, attributes, template_content, .. } => {
+                let mut node = DomNode::new_element(tag_name, "");
+                node.namespace = *namespace;
+                node.attributes = attributes.clone();
+                if *namespace == DomNamespace::Html && is_html_void_element(tag_name) {
+                    return node;
+                }
+                let children = match template_content {
+                    Some(content) => &self.nodes[*content].children,
+                    None => &self.nodes[id].children,
+                };
+                for child in children {
+                    node.append(self.dom_node(*child));
+                }
+                return node;
+            },
+            Html5NodeData::Document | Html5NodeData::TemplateContent => {
+                return DomNode::new();
+            },
+        }
+    }
+
+    // the tree counterpart of dom_node, appends the node and everything under it to parent
+    fn tree_node(&self, id: usize, tree: &mut DomTree, parent: NodeId) {
+        let node = match &self.nodes[id].data {
+            Html5NodeData::Doctype { name, outer_html } => tree.create_doctype(name, outer_html),
+            Html5NodeData::Text(text) => tree.create_text(text),
+            Html5NodeData::Comment(text) => tree.create_comment(text),
+            Html5NodeData::Element { tag_name, namespace, attributes, .. } => {
+                let node = tree.create_element(tag_name);
+                tree.get_mut(node).namespace = *namespace;
+                tree.get_mut(node).attributes = attributes.clone();
+                node
+            },
+            Html5NodeData::Document | Html5NodeData::TemplateContent => {
+                return;
+            },
+        };
+        tree.append(parent, node);
+        if let Html5NodeData::Element { tag_name, namespace, template_content, .. } = &self.nodes[id].data {
+            if *namespace == DomNamespace::Html && is_html_void_element(tag_name) {
+                return;
+            }
+            let children = match template_content {
+                Some(content) => &self.nodes[*content].children,
+                None => &self.nodes[id].children,
+            };
+            for child in children {
+                self.tree_node(*child, tree, node);
+            }
+        }
+    }
+
+    fn error(&mut self, code: &'static str) {
+        self.errors.push(Html5ParseError { code, span: self.span });
+    }
+
+    // nodes
+
+    fn element_name(&self, id: usize) -> &str {
+        return match &self.nodes[id].data {
+            Html5NodeData::Element { tag_name, .. } => tag_name,
+            _ => "",
+        };
+    }
+
+    fn namespace(&self, id: usize) -> DomNamespace {
+        return match &self.nodes[id].data {
+            Html5NodeData::Element { namespace, .. } => *namespace,
+            _ => DomNamespace::Html,
+        };
+    }
+
+    fn is_html(&self, id: usize, name: &str) -> bool {
+        return matches!(&self.nodes[id].data, Html5NodeData::Element { tag_name, namespace: DomNamespace::Html, .. } if tag_name == name);
+    }
+
+    fn is_html_one_of(&self, id: usize, names: &[&str]) -> bool {
+        return matches!(&self.nodes[id].data, Html5NodeData::Element { tag_name, namespace: DomNamespace::Html, .. } if names.contains(&tag_name.as_str()));
+    }
+
+    fn is_special(&self, id: usize) -> bool {
+        let name = self.element_name(id);
+        return match self.namespace(id) {
+            DomNamespace::Html => HTML5_SPECIAL_ELEMENTS.contains(&name),
+            DomNamespace::MathMl => matches!(name, "mi" | "mo" | "mn" | "ms" | "mtext" | "annotation-xml"),
+            DomNamespace::Svg => matches!(name, "foreignObject" | "desc" | "title"),
+        };
+    }
+
+    fn is_mathml_text_integration_point(&self, id: usize) -> bool {
+        return self.namespace(id) == DomNamespace::MathMl && matches!(self.element_name(id), "mi" | "mo" | "mn" | "ms" | "mtext");
+    }
+
+    fn is_html_integration_point(&self, id: usize) -> bool {
+        return match &self.nodes[id].data {
+            Html5NodeData::Element { html_integration_point, .. } => *html_integration_point,
+            _ => false,
+        };
+    }
+
+    fn create_element(&mut self, tag: &Html5TagToken, namespace: DomNamespace) -> usize {
+        let html_integration_point = match namespace {
+            DomNamespace::Svg => matches!(tag.name.as_str(), "foreignObject" | "desc" | "title"),
+            DomNamespace::MathMl => tag.name == "annotation-xml" && tag.attributes.iter().any(|attr| {
+                return attr.name == "encoding" && (attr.value.eq_ignore_ascii_case("text/html") || attr.value.eq_ignore_ascii_case("application/xhtml+xml"));
+            }),
+            DomNamespace::Html => false,
+        };
+        let template_content = if namespace == DomNamespace::Html && tag.name == "template" {
+            self.nodes.push(Html5Node { data: Html5NodeData::TemplateContent, parent: None, children: vec![] });
+            Some(self.nodes.len() - 1)
+        } else {
+            None
+        };
+        let data = Html5NodeData::Element { tag_name: tag.name.clone(), namespace, attributes: tag.attributes.clone(), html_integration_point, template_content };
+        self.nodes.push(Html5Node { data, parent: None, children: vec![] });
+        return self.nodes.len() - 1;
+    }
+
+    fn detach(&mut self, id: usize) {
+        if let Some(parent) = self.nodes[id].parent.take() {
+            self.nodes[parent].children.retain(|child| *child != id);
+        }
+    }
+
+    fn append_child(&mut self, parent: usize, id: usize) {
+        self.detach(id);
+        self.nodes[id].parent = Some(parent);
+        self.nodes[parent].children.push(id);
+    }
+
+    fn insert_at(&mut self, place: (usize, Option<usize>), id: usize) {
+        let (parent, before) = place;
+        self.detach(id);
+        let index = match before {
+            Some(before) => self.nodes[parent].children.iter().position(|child| *child == before).unwrap_or(self.nodes[parent].children.len()),
+            None => self.nodes[parent].children.len(),
+        };
+        self.nodes[id].parent = Some(parent);
+        self.nodes[parent].children.insert(index, id);
+    }
+
+    // the parent to insert into and the child to insert before, foster parenting moves content that
+    // shows up directly inside table markup to just before the table
+    fn appropriate_place(&self, override_target: Option<usize>) -> (usize, Option<usize>) {
+        let target = override_target.unwrap_or_else(|| self.current_node());
+        let place = if self.foster_parenting && self.is_html_one_of(target, &["table", "tbody", "tfoot", "thead", "tr"]) {
+            let last_template = self.open_elements.iter().rposition(|id| self.is_html(*id, "template"));
+            let last_table = self.open_elements.iter().rposition(|id| self.is_html(*id, "table"));
+            match (last_template, last_table) {
+                (Some(template), table) if table.is_none_or(|table| template > table) => (self.open_elements[template], None),
+                (_, None) => (self.open_elements[0], None),
+                (_, Some(table)) => {
+                    let table_id = self.open_elements[table];
+                    match self.nodes[table_id].parent {
+                        Some(parent) => (parent, Some(table_id)),
+                        None => (self.open_elements[table - 1], None),
+                    }
+                },
+            }
+        } else {
+            (target, None)
+        };
+        return match &self.nodes[place.0].data {
+            Html5NodeData::Element { template_content: Some(content), .. } => (*content, place.1),
+            _ => place,
+        };
+    }
+
+    fn insert_characters(&mut self, text: &str) {
+        let (parent, before) = self.appropriate_place(None);
+        if parent == HTML5_DOCUMENT {
+            return;
+        }
+        let siblings = &self.nodes[parent].children;
+        let previous = match before {
+            Some(before) => siblings.iter().position(|child| *child == before).and_then(|i| i.checked_sub(1)).map(|i| siblings[i]),
+            None => siblings.last().copied(),
+        };
+        if let Some(previous) = previous {
+            if let Html5NodeData::Text(existing) = &mut self.nodes[previous].data {
+                existing.push_str(text);
+                return;
+            }
+        }
+        self.nodes.push(Html5Node { data: Html5NodeData::Text(text.to_string()), parent: None, children: vec![] });
+        let id = self.nodes.len() - 1;
+        self.insert_at((parent, before), id);
+    }
+
+    fn insert_comment(&mut self, text: String, place: Option<(usize, Option<usize>)>) {
+        let place = place.unwrap_or_else(|| self.appropriate_place(None));
+        self.nodes.push(Html5Node { data: Html5NodeData::Comment(text), parent: None, children: vec![] });
+        let id = self.nodes.len() - 1;
+        self.insert_at(place, id);
+    }
+
+    fn insert_element(&mut self, tag: &Html5TagToken, namespace: DomNamespace) -> usize {
+        let place = self.appropriate_place(None);
+        let id = self.create_element(tag, namespace);
+        self.insert_at(place, id);
+        self.open_elements.push(id);
+        return id;
+    }
+
+    fn insert_html_element(&mut self, tag: &Html5TagToken) -> usize {
+        return self.insert_element(tag, DomNamespace::Html);
+    }
+
+    // an element the markup left out, like the tbody around a bare tr
+    fn insert_implied_element(&mut self, name: &str) -> usize {
+        return self.insert_html_element(&Html5TagToken { name: name.to_string(), attributes: vec![], self_closing: false });
+    }
+
+    fn insert_foreign_element(&mut self, tag: &Html5TagToken, namespace: DomNamespace) {
+        let mut tag = tag.clone();
+        match namespace {
+            DomNamespace::MathMl => {
+                for attr in &mut tag.attributes {
+                    if attr.name == "definitionurl" {
+                        attr.name = "definitionURL".to_string();
+                    }
+                }
+            },
+            DomNamespace::Svg => {
+                if let Some((_, adjusted)) = HTML5_SVG_TAG_NAMES.iter().find(|(lower, _)| *lower == tag.name) {
+                    tag.name = adjusted.to_string();
+                }
+                for attr in &mut tag.attributes {
+                    if let Some((_, adjusted)) = HTML5_SVG_ATTRIBUTE_NAMES.iter().find(|(lower, _)| *lower == attr.name) {
+                        attr.name = adjusted.to_string();
+                    }
+                }
+            },
+            DomNamespace::Html => {},
+        }
+        self.insert_element(&tag, namespace);
+        if tag.self_closing {
+            self.open_elements.pop();
+        }
+    }
+
+    // the stack of open elements
+
+    fn current_node(&self) -> usize {
+        return *self.open_elements.last().unwrap_or(&HTML5_DOCUMENT);
+    }
+
+    fn adjusted_current_node(&self) -> Option<usize> {
+        if self.open_elements.len() == 1 && self.context.is_some() {
+            return self.context;
+        }
+        return self.open_elements.last().copied();
+    }
+
+    fn is_scope_boundary(&self, id: usize, scope: Html5Scope) -> bool {
+        let name = self.element_name(id);
+        let default = match self.namespace(id) {
+            DomNamespace::Html => matches!(name, "applet" | "caption" | "html" | "table" | "td" | "th" | "marquee" | "object" | "template"),
+            DomNamespace::MathMl => matches!(name, "mi" | "mo" | "mn" | "ms" | "mtext" | "annotation-xml"),
+            DomNamespace::Svg => matches!(name, "foreignObject" | "desc" | "title"),
+        };
+        return match scope {
+            Html5Scope::Default => default,
+            Html5Scope::ListItem => default || self.is_html_one_of(id, &["ol", "ul"]),
+            Html5Scope::Button => default || self.is_html(id, "button"),
+            Html5Scope::Table => self.is_html_one_of(id, &["html", "table", "template"]),
+            Html5Scope::Select => !self.is_html_one_of(id, &["optgroup", "option"]),
+        };
+    }
+
+    fn in_scope_where(&self, scope: Html5Scope, matches: impl Fn(usize) -> bool) -> bool {
+        for id in self.open_elements.iter().rev() {
+            if matches(*id) {
+                return true;
+            }
+            if self.is_scope_boundary(*id, scope) {
+                return false;
+            }
+        }
+        return false;
+    }
+
+    fn in_scope(&self, name: &str, scope: Html5Scope) -> bool {
+        return self.in_scope_where(scope, |id| self.is_html(id, name));
+    }
+
+    fn has_open(&self, name: &str) -> bool {
+        return self.open_elements.iter().any(|id| self.is_html(*id, name));
+    }
+
+    fn pop_until(&mut self, name: &str) {
+        while let Some(id) = self.open_elements.pop() {
+            if self.is_html(id, name) {
+                return;
+            }
+        }
+    }
+
+    fn pop_until_one_of(&mut self, names: &[&str]) {
+        while let Some(id) = self.open_elements.pop() {
+            if self.is_html_one_of(id, names) {
+                return;
+            }
+        }
+    }
+
+    fn remove_open_element(&mut self, id: usize) {
+        self.open_elements.retain(|open| *open != id);
+    }
+
+    fn generate_implied_end_tags(&mut self, except: Option<&str>) {
+        loop {
+            let current = self.current_node();
+            if except.is_some_and(|except| self.is_html(current, except)) {
+                return;
+            }
+            if !self.is_html_one_of(current, &["dd", "dt", "li", "optgroup", "option", "p", "rb", "rp", "rt", "rtc"]) {
+                return;
+            }
+            self.open_elements.pop();
+        }
+    }
+
+    fn generate_implied_end_tags_thoroughly(&mut self) {
+        let names = ["caption", "colgroup", "dd", "dt", "li", "optgroup", "option", "p", "rb", "rp", "rt", "rtc", "tbody", "td", "tfoot", "th", "thead", "tr"];
+        while self.is_html_one_of(self.current_node(), &names) {
+            self.open_elements.pop();
+        }
+    }
+
+    fn close_p_element(&mut self) {
+        self.generate_implied_end_tags(Some("p"));
+        if !self.is_html(self.current_node(), "p") {
+            self.error("unexpected-open-element-before-p-end");
+        }
+        self.pop_until("p");
+    }
+
+    fn close_p_in_button_scope(&mut self) {
+        if self.in_scope("p", Html5Scope::Button) {
+            self.close_p_element();
+        }
+    }
+
+    fn clear_stack_back_to(&mut self, names: &[&str]) {
+        while !self.is_html_one_of(self.current_node(), names) {
+            self.open_elements.pop();
+        }
+    }
+
+    fn reset_insertion_mode(&mut self) {
+        use Html5InsertionMode as M;
+        for i in (0..self.open_elements.len()).rev() {
+            let last = i == 0;
+            let node = match (last, self.context) {
+                (true, Some(context)) => context,
+                _ => self.open_elements[i],
+            };
+            if self.namespace(node) != DomNamespace::Html {
+                if last {
+                    self.mode = M::InBody;
+                    return;
+                }
+                continue;
+            }
+            let mode = match self.element_name(node) {
+                "select" => {
+                    let in_table = !last && self.open_elements[..i].iter().rev().take_while(|id| !self.is_html(**id, "template")).any(|id| self.is_html(*id, "table"));
+                    Some(if in_table { M::InSelectInTable } else { M::InSelect })
+                },
+                "td" | "th" if !last => Some(M::InCell),
+                "tr" => Some(M::InRow),
+                "tbody" | "thead" | "tfoot" => Some(M::InTableBody),
+                "caption" => Some(M::InCaption),
+                "colgroup" => Some(M::InColumnGroup),
+                "table" => Some(M::InTable),
+                "template" => self.template_modes.last().copied(),
+                "head" if !last => Some(M::InHead),
+                "body" => Some(M::InBody),
+                "frameset" => Some(M::InFrameset),
+                "html" => Some(if self.head_element.is_none() { M::BeforeHead } else { M::AfterHead }),
+                _ => None,
+            };
+            if let Some(mode) = mode {
+                self.mode = mode;
+                return;
+            }
+            if last {
+                self.mode = M::InBody;
+                return;
+            }
+        }
+    }
+
+    // the list of active formatting elements
+
+    fn push_formatting(&mut self, id: usize, tag: &Html5TagToken) {
+        // at most three identical elements are remembered after the last marker, the earliest goes first
+        let mut same: Vec<usize> = vec![];
+        for (i, entry) in self.active_formatting.iter().enumerate().rev() {
+            match entry {
+                Html5FormattingEntry::Marker => {
+                    break;
+                },
+                Html5FormattingEntry::Element(_, existing) => {
+                    if existing.name == tag.name && html5_same_attributes(&existing.attributes, &tag.attributes) {
+                        same.push(i);
+                    }
+                },
+            }
+        }
+        if same.len() >= 3 {
+            self.active_formatting.remove(*same.last().unwrap_or(&0));
+        }
+        self.active_formatting.push(Html5FormattingEntry::Element(id, tag.clone()));
+    }
+
+    fn formatting_position(&self, id: usize) -> Option<usize> {
+        return self.active_formatting.iter().position(|entry| matches!(entry, Html5FormattingEntry::Element(existing, _) if *existing == id));
+    }
+
+    // the last formatting element with this name since the last marker
+    fn formatting_element_named(&self, name: &str) -> Option<usize> {
+        for entry in self.active_formatting.iter().rev() {
+            match entry {
+                Html5FormattingEntry::Marker => {
+                    return None;
+                },
+                Html5FormattingEntry::Element(id, tag) => {
+                    if tag.name == name {
+                        return Some(*id);
+                    }
+                },
+            }
+        }
+        return None;
+    }
+
+    fn clear_formatting_to_marker(&mut self) {
+        while let Some(entry) = self.active_formatting.pop() {
+            if matches!(entry, Html5FormattingEntry::Marker) {
+                return;
+            }
+        }
+    }
+
+    // reopens formatting elements that were implicitly closed, so '<b>1<p>2' keeps '2' bold
+    fn reconstruct_formatting(&mut self) {
+        let is_open = |builder: &Self, entry: &Html5FormattingEntry| {
+            return match entry {
+                Html5FormattingEntry::Marker => true,
+                Html5FormattingEntry::Element(id, _) => builder.open_elements.contains(id),
+            };
+        };
+        let mut i = match self.active_formatting.last() {
+            Some(entry) if !is_open(self, entry) => self.active_formatting.len() - 1,
+            _ => {
+                return;
+            },
+        };
+        while i > 0 && !is_open(self, &self.active_formatting[i - 1]) {
+            i -= 1;
+        }
+        while i < self.active_formatting.len() {
+            if let Html5FormattingEntry::Element(_, tag) = self.active_formatting[i].clone() {
+                let id = self.insert_html_element(&tag);
+                self.active_formatting[i] = Html5FormattingEntry::Element(id, tag);
+            }
+            i += 1;
+        }
+    }
+
+    // repairs misnested formatting like '<b><p>1</b>2</p>', returns false when the end tag should
+    // get the generic end tag handling instead
+    fn adoption_agency(&mut self, subject: &str) -> bool {
+        let current = self.current_node();
+        if self.is_html(current, subject) && self.formatting_position(current).is_none() {
+            self.open_elements.pop();
+            return true;
+        }
+        for _ in 0..8 {
+            let formatting = match self.formatting_element_named(subject) {
+                Some(id) => id,
+                None => {
+                    return false;
+                },
+            };
+            let formatting_stack = match self.open_elements.iter().position(|id| *id == formatting) {
+                Some(i) => i,
+                None => {
+                    self.error("adoption-agency-1.2");
+                    if let Some(i) = self.formatting_position(formatting) {
+                        self.active_formatting.remove(i);
+                    }
+                    return true;
+                },
+            };
+            if !self.in_scope_where(Html5Scope::Default, |id| id == formatting) {
+                self.error("adoption-agency-4.4");
+                return true;
+            }
+            if formatting != self.current_node() {
+                self.error("adoption-agency-1.3");
+            }
+            let furthest_block = (formatting_stack + 1..self.open_elements.len()).find(|i| self.is_special(self.open_elements[*i]));
+            let mut furthest_stack = match furthest_block {
+                Some(i) => i,
+                None => {
+                    self.open_elements.truncate(formatting_stack);
+                    if let Some(i) = self.formatting_position(formatting) {
+                        self.active_formatting.remove(i);
+                    }
+                    return true;
+                },
+            };
+            let furthest_block = self.open_elements[furthest_stack];
+            let common_ancestor = self.open_elements[formatting_stack - 1];
+            let mut bookmark = self.formatting_position(formatting).unwrap_or(0);
+            let mut last_node = furthest_block;
+            let mut node_stack = furthest_stack;
+            let mut inner = 0;
+            loop {
+                inner += 1;
+                node_stack -= 1;
+                let node = self.open_elements[node_stack];
+                if node == formatting {
+                    break;
+                }
+                let mut node_formatting = self.formatting_position(node);
+                if inner > 3 {
+                    if let Some(i) = node_formatting {
+                        self.active_formatting.remove(i);
+                        if i < bookmark {
+                            bookmark -= 1;
+                        }
+                        node_formatting = None;
+                    }
+                }
+                let node_formatting = match node_formatting {
+                    Some(i) => i,
+                    None => {
+                        self.open_elements.remove(node_stack);
+                        furthest_stack -= 1;
+                        continue;
+                    },
+                };
+                let tag = match &self.active_formatting[node_formatting] {
+                    Html5FormattingEntry::Element(_, tag) => tag.clone(),
+                    Html5FormattingEntry::Marker => {
+                        break;
+                    },
+                };
+                let replacement = self.create_element(&tag, DomNamespace::Html);
+                self.active_formatting[node_formatting] = Html5FormattingEntry::Element(replacement, tag);
+                self.open_elements[node_stack] = replacement;
+                if last_node == furthest_block {
+                    bookmark = node_formatting + 1;
+                }
+                self.append_child(replacement, last_node);
+                last_node = replacement;
+            }
+            let place = self.appropriate_place(Some(common_ancestor));
+            self.insert_at(place, last_node);
+            let tag = match self.formatting_position(formatting).map(|i| &self.active_formatting[i]) {
+                Some(Html5FormattingEntry::Element(_, tag)) => tag.clone(),
+                _ => {
+                    return true;
+                },
+            };
+            let replacement = self.create_element(&tag, DomNamespace::Html);
+            let children = std::mem::take(&mut self.nodes[furthest_block].children);
+            for child in &children {
+                self.nodes[*child].parent = Some(replacement);
+            }
+            self.nodes[replacement].children = children;
+            self.append_child(furthest_block, replacement);
+            if let Some(i) = self.formatting_position(formatting) {
+                self.active_formatting.remove(i);
+                if i < bookmark {
+                    bookmark -= 1;
+                }
+            }
+            let bookmark = bookmark.min(self.active_formatting.len());
+            self.active_formatting.insert(bookmark, Html5FormattingEntry::Element(replacement, tag));
+            self.remove_open_element(formatting);
+            let furthest_stack = self.open_elements.iter().position(|id| *id == furthest_block).unwrap_or(furthest_stack);
+            self.open_elements.insert(furthest_stack + 1, replacement);
+        }
+        return true;
+    }
+
+    // dispatching
+
+    fn dispatch(&mut self, tok: Html5TreeToken) {
+        if self.use_html_rules(&tok) {
+            self.process(self.mode, tok);
+        } else {
+            self.process_foreign(tok);
+        }
+    }
+
+    fn use_html_rules(&self, tok: &Html5TreeToken) -> bool {
+        let node = match self.adjusted_current_node() {
+            Some(node) => node,
+            None => {
+                return true;
+            },
+        };
+        if self.namespace(node) == DomNamespace::Html {
+            return true;
+        }
+        if self.is_mathml_text_integration_point(node) {
+            match tok {
+                Html5TreeToken::StartTag(tag) if tag.name != "mglyph" && tag.name != "malignmark" => {
+                    return true;
+                },
+                Html5TreeToken::Characters(_) => {
+                    return true;
+                },
+                _ => {},
+            }
+        }
+        if self.namespace(node) == DomNamespace::MathMl && self.element_name(node) == "annotation-xml" {
+            if let Html5TreeToken::StartTag(tag) = tok {
+                if tag.name == "svg" {
+                    return true;
+                }
+            }
+        }
+        if self.is_html_integration_point(node) && matches!(tok, Html5TreeToken::StartTag(_) | Html5TreeToken::Characters(_)) {
+            return true;
+        }
+        return matches!(tok, Html5TreeToken::Eof);
+    }
+
+    fn process(&mut self, mode: Html5InsertionMode, tok: Html5TreeToken) {
+        use Html5InsertionMode as M;
+        match mode {
+            M::Initial => self.initial(tok),
+            M::BeforeHtml => self.before_html(tok),
+            M::BeforeHead => self.before_head(tok),
+            M::InHead => self.in_head(tok),
+            M::InHeadNoscript => self.in_head_noscript(tok),
+            M::AfterHead => self.after_head(tok),
+            M::InBody => self.in_body(tok),
+            M::Text => self.text(tok),
+            M::InTable => self.in_table(tok),
+            M::InTableText => self.in_table_text(tok),
+            M::InCaption => self.in_caption(tok),
+            M::InColumnGroup => self.in_column_group(tok),
+            M::InTableBody => self.in_table_body(tok),
+            M::InRow => self.in_row(tok),
+            M::InCell => self.in_cell(tok),
+            M::InSelect => self.in_select(tok),
+            M::InSelectInTable => self.in_select_in_table(tok),
+            M::InTemplate => self.in_template(tok),
+            M::AfterBody => self.after_body(tok),
+            M::InFrameset => self.in_frameset(tok),
+            M::AfterFrameset => self.after_frameset(tok),
+            M::AfterAfterBody => self.after_after_body(tok),
+            M::AfterAfterFrameset => self.after_after_frameset(tok),
+        }
+    }
+
+    fn reprocess(&mut self, mode: Html5InsertionMode, tok: Html5TreeToken) {
+        self.mode = mode;
+        self.process(mode, tok);
+    }
+
+    // switches the tokenizer for elements whose contents are raw text or escapable raw text
+    fn parse_text_element(&mut self, tag: &Html5TagToken, state: Html5TokenizerState) {
+        self.insert_html_element(tag);
+        self.tokenizer.set_state(state);
+        self.original_mode = self.mode;
+        self.mode = Html5InsertionMode::Text;
+    }
+
+    // insertion modes
+
+    fn initial(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {},
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, Some((HTML5_DOCUMENT, None)));
+            },
+            Html5TreeToken::Doctype { name, public_id, system_id, force_quirks, outer_html } => {
+                if name.as_deref() != Some("html") || public_id.is_some() || system_id.as_deref().is_some_and(|id| id != "about:legacy-compat") {
+                    self.error("unknown-doctype");
+                }
+                self.quirks_mode = html5_is_quirks_doctype(name.as_deref(), public_id.as_deref(), system_id.as_deref(), force_quirks);
+                let data = Html5NodeData::Doctype { name: name.unwrap_or_default(), outer_html };
+                self.nodes.push(Html5Node { data, parent: None, children: vec![] });
+                let id = self.nodes.len() - 1;
+                self.append_child(HTML5_DOCUMENT, id);
+                self.mode = Html5InsertionMode::BeforeHtml;
+            },
+            tok => {
+                self.error("expected-doctype");
+                self.quirks_mode = true;
+                self.reprocess(Html5InsertionMode::BeforeHtml, tok);
+            },
+        }
+    }
+
+    fn before_html(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, Some((HTML5_DOCUMENT, None)));
+            },
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {},
+            Html5TreeToken::StartTag(tag) if tag.name == "html" => {
+                let id = self.create_element(&tag, DomNamespace::Html);
+                self.append_child(HTML5_DOCUMENT, id);
+                self.open_elements.push(id);
+                self.mode = Html5InsertionMode::BeforeHead;
+            },
+            Html5TreeToken::EndTag(name) if !matches!(name.as_str(), "head" | "body" | "html" | "br") => {
+                self.error("unexpected-end-tag");
+            },
+            tok => {
+                let id = self.create_element(&html5_tag("html"), DomNamespace::Html);
+                self.append_child(HTML5_DOCUMENT, id);
+                self.open_elements.push(id);
+                self.reprocess(Html5InsertionMode::BeforeHead, tok);
+            },
+        }
+    }
+
+    fn before_head(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {},
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) if tag.name == "html" => {
+                self.in_body(Html5TreeToken::StartTag(tag));
+            },
+            Html5TreeToken::StartTag(tag) if tag.name == "head" => {
+                self.head_element = Some(self.insert_html_element(&tag));
+                self.mode = Html5InsertionMode::InHead;
+            },
+            Html5TreeToken::EndTag(name) if !matches!(name.as_str(), "head" | "body" | "html" | "br") => {
+                self.error("unexpected-end-tag");
+            },
+            tok => {
+                self.head_element = Some(self.insert_implied_element("head"));
+                self.reprocess(Html5InsertionMode::InHead, tok);
+            },
+        }
+    }
+
+    fn in_head(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) => {
+                match tag.name.as_str() {
+                    "html" => {
+                        self.in_body(Html5TreeToken::StartTag(tag));
+                    },
+                    "base" | "basefont" | "bgsound" | "link" | "meta" => {
+                        self.insert_html_element(&tag);
+                        self.open_elements.pop();
+                    },
+                    "title" => {
+                        self.parse_text_element(&tag, Html5TokenizerState::RcData);
+                    },
+                    "noscript" if self.options.scripting => {
+                        self.parse_text_element(&tag, Html5TokenizerState::RawText);
+                    },
+                    "noframes" | "style" => {
+                        self.parse_text_element(&tag, Html5TokenizerState::RawText);
+                    },
+                    "noscript" => {
+                        self.insert_html_element(&tag);
+                        self.mode = Html5InsertionMode::InHeadNoscript;
+                    },
+                    "script" => {
+                        self.parse_text_element(&tag, Html5TokenizerState::ScriptData);
+                    },
+                    "template" => {
+                        self.insert_html_element(&tag);
+                        self.active_formatting.push(Html5FormattingEntry::Marker);
+                        self.frameset_ok = false;
+                        self.mode = Html5InsertionMode::InTemplate;
+                        self.template_modes.push(Html5InsertionMode::InTemplate);
+                    },
+                    "head" => {
+                        self.error("unexpected-start-tag");
+                    },
+                    _ => {
+                        self.open_elements.pop();
+                        self.reprocess(Html5InsertionMode::AfterHead, Html5TreeToken::StartTag(tag));
+                    },
+                }
+            },
+            Html5TreeToken::EndTag(name) => {
+                match name.as_str() {
+                    "head" => {
+                        self.open_elements.pop();
+                        self.mode = Html5InsertionMode::AfterHead;
+                    },
+                    "body" | "html" | "br" => {
+                        self.open_elements.pop();
+                        self.reprocess(Html5InsertionMode::AfterHead, Html5TreeToken::EndTag(name));
+                    },
+                    "template" => {
+                        if !self.has_open("template") {
+                            self.error("unexpected-end-tag");
+                            return;
+                        }
+                        self.generate_implied_end_tags_thoroughly();
+                        if !self.is_html(self.current_node(), "template") {
+                            self.error("unexpected-end-tag");
+                        }
+                        self.pop_until("template");
+                        self.clear_formatting_to_marker();
+                        self.template_modes.pop();
+                        self.reset_insertion_mode();
+                    },
+                    _ => {
+                        self.error("unexpected-end-tag");
+                    },
+                }
+            },
+            tok => {
+                self.open_elements.pop();
+                self.reprocess(Html5InsertionMode::AfterHead, tok);
+            },
+        }
+    }
+
+    fn in_head_noscript(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) if tag.name == "html" => {
+                self.in_body(Html5TreeToken::StartTag(tag));
+            },
+            Html5TreeToken::EndTag(name) if name == "noscript" => {
+                self.open_elements.pop();
+                self.mode = Html5InsertionMode::InHead;
+            },
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {
+                self.in_head(Html5TreeToken::Characters(text));
+            },
+            Html5TreeToken::Comment(text) => {
+                self.in_head(Html5TreeToken::Comment(text));
+            },
+            Html5TreeToken::StartTag(tag) if matches!(tag.name.as_str(), "basefont" | "bgsound" | "link" | "meta" | "noframes" | "style") => {
+                self.in_head(Html5TreeToken::StartTag(tag));
+            },
+            Html5TreeToken::StartTag(tag) if matches!(tag.name.as_str(), "head" | "noscript") => {
+                self.error("unexpected-start-tag");
+            },
+            Html5TreeToken::EndTag(name) if name != "br" => {
+                self.error("unexpected-end-tag");
+            },
+            tok => {
+                self.error("unexpected-token-in-noscript");
+                self.open_elements.pop();
+                self.reprocess(Html5InsertionMode::InHead, tok);
+            },
+        }
+    }
+
+    fn after_head(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) => {
+                match tag.name.as_str() {
+                    "html" => {
+                        self.in_body(Html5TreeToken::StartTag(tag));
+                    },
+                    "body" => {
+                        self.insert_html_element(&tag);
+                        self.frameset_ok = false;
+                        self.mode = Html5InsertionMode::InBody;
+                    },
+                    "frameset" => {
+                        self.insert_html_element(&tag);
+                        self.mode = Html5InsertionMode::InFrameset;
+                    },
+                    "base" | "basefont" | "bgsound" | "link" | "meta" | "noframes" | "script" | "style" | "template" | "title" => {
+                        self.error("unexpected-start-tag-after-head");
+                        let head = match self.head_element {
+                            Some(head) => head,
+                            None => {
+                                return;
+                            },
+                        };
+                        self.open_elements.push(head);
+                        self.in_head(Html5TreeToken::StartTag(tag));
+                        self.remove_open_element(head);
+                    },
+                    "head" => {
+                        self.error("unexpected-start-tag");
+                    },
+                    _ => {
+                        self.insert_implied_element("body");
+                        self.reprocess(Html5InsertionMode::InBody, Html5TreeToken::StartTag(tag));
+                    },
+                }
+            },
+            Html5TreeToken::EndTag(name) if name == "template" => {
+                self.in_head(Html5TreeToken::EndTag(name));
+            },
+            Html5TreeToken::EndTag(name) if !matches!(name.as_str(), "body" | "html" | "br") => {
+                self.error("unexpected-end-tag");
+            },
+            tok => {
+                self.insert_implied_element("body");
+                self.reprocess(Html5InsertionMode::InBody, tok);
+            },
+        }
+    }
+
+    fn in_body(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) => {
+                if text.starts_with('\0') {
+                    self.error("unexpected-null-character");
+                    return;
+                }
+                self.reconstruct_formatting();
+                self.insert_characters(&text);
+                if !html5_is_whitespace(&text) {
+                    self.frameset_ok = false;
+                }
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) => {
+                self.in_body_start_tag(tag);
+            },
+            Html5TreeToken::EndTag(name) => {
+                self.in_body_end_tag(name);
+            },
+            Html5TreeToken::Eof => {
+                if !self.template_modes.is_empty() {
+                    self.in_template(Html5TreeToken::Eof);
+                    return;
+                }
+                self.done = true;
+            },
+        }
+    }
+
+    fn in_body_start_tag(&mut self, mut tag: Html5TagToken) {
+        match tag.name.as_str() {
+            "html" => {
+                self.error("unexpected-start-tag");
+                if self.has_open("template") {
+                    return;
+                }
+                self.merge_attributes(self.open_elements[0], &tag);
+            },
+            "base" | "basefont" | "bgsound" | "link" | "meta" | "noframes" | "script" | "style" | "template" | "title" => {
+                self.in_head(Html5TreeToken::StartTag(tag));
+            },
+            "body" => {
+                self.error("unexpected-start-tag");
+                if self.open_elements.len() < 2 || !self.is_html(self.open_elements[1], "body") || self.has_open("template") {
+                    return;
+                }
+                self.frameset_ok = false;
+                self.merge_attributes(self.open_elements[1], &tag);
+            },
+            "frameset" => {
+                self.error("unexpected-start-tag");
+                if self.open_elements.len() < 2 || !self.is_html(self.open_elements[1], "body") || !self.frameset_ok {
+                    return;
+                }
+                let body = self.open_elements[1];
+                self.detach(body);
+                self.open_elements.truncate(1);
+                self.insert_html_element(&tag);
+                self.mode = Html5InsertionMode::InFrameset;
+            },
+            "address" | "article" | "aside" | "blockquote" | "center" | "details" | "dialog" | "dir" | "div" | "dl" | "fieldset" | "figcaption" | "figure" | "footer" | "header" | "hgroup" | "main" | "menu" | "nav" | "ol" | "p" | "search" | "section" | "summary" | "ul" => {
+                self.close_p_in_button_scope();
+                self.insert_html_element(&tag);
+            },
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.close_p_in_button_scope();
+                if self.is_html_one_of(self.current_node(), &HTML5_HEADINGS) {
+                    self.error("unexpected-start-tag");
+                    self.open_elements.pop();
+                }
+                self.insert_html_element(&tag);
+            },
+            "pre" | "listing" => {
+                self.close_p_in_button_scope();
+                self.insert_html_element(&tag);
+                self.skip_newline = true;
+                self.frameset_ok = false;
+            },
+            "form" => {
+                let in_template = self.has_open("template");
+                if self.form_element.is_some() && !in_template {
+                    self.error("unexpected-start-tag");
+                    return;
+                }
+                self.close_p_in_button_scope();
+                let form = self.insert_html_element(&tag);
+                if !in_template {
+                    self.form_element = Some(form);
+                }
+            },
+            "li" | "dd" | "dt" => {
+                self.frameset_ok = false;
+                let closes: &[&str] = if tag.name == "li" { &["li"] } else { &["dd", "dt"] };
+                for i in (0..self.open_elements.len()).rev() {
+                    let node = self.open_elements[i];
+                    if self.is_html_one_of(node, closes) {
+                        let name = self.element_name(node).to_string();
+                        self.generate_implied_end_tags(Some(&name));
+                        if !self.is_html(self.current_node(), &name) {
+                            self.error("unexpected-start-tag");
+                        }
+                        self.pop_until(&name);
+                        break;
+                    }
+                    if self.is_special(node) && !self.is_html_one_of(node, &["address", "div", "p"]) {
+                        break;
+                    }
+                }
+                self.close_p_in_button_scope();
+                self.insert_html_element(&tag);
+            },
+            "plaintext" => {
+                self.close_p_in_button_scope();
+                self.insert_html_element(&tag);
+                self.tokenizer.set_state(Html5TokenizerState::PlainText);
+            },
+            "button" => {
+                if self.in_scope("button", Html5Scope::Default) {
+                    self.error("unexpected-start-tag");
+                    self.generate_implied_end_tags(None);
+                    self.pop_until("button");
+                }
+                self.reconstruct_formatting();
+                self.insert_html_element(&tag);
+                self.frameset_ok = false;
+            },
+            "a" => {
+                if let Some(a) = self.formatting_element_named("a") {
+                    self.error("unexpected-start-tag");
+                    self.adoption_agency("a");
+                    if let Some(i) = self.formatting_position(a) {
+                        self.active_formatting.remove(i);
+                    }
+                    self.remove_open_element(a);
+                }
+                self.reconstruct_formatting();
+                let id = self.insert_html_element(&tag);
+                self.push_formatting(id, &tag);
+            },
+            "b" | "big" | "code" | "em" | "font" | "i" | "s" | "small" | "strike" | "strong" | "tt" | "u" => {
+                self.reconstruct_formatting();
+                let id = self.insert_html_element(&tag);
+                self.push_formatting(id, &tag);
+            },
+            "nobr" => {
+                self.reconstruct_formatting();
+                if self.in_scope("nobr", Html5Scope::Default) {
+                    self.error("unexpected-start-tag");
+                    self.adoption_agency("nobr");
+                    self.reconstruct_formatting();
+                }
+                let id = self.insert_html_element(&tag);
+                self.push_formatting(id, &tag);
+            },
+            "applet" | "marquee" | "object" => {
+                self.reconstruct_formatting();
+                self.insert_html_element(&tag);
+                self.active_formatting.push(Html5FormattingEntry::Marker);
+                self.frameset_ok = false;
+            },
+            "table" => {
+                if !self.quirks_mode {
+                    self.close_p_in_button_scope();
+                }
+                self.insert_html_element(&tag);
+                self.frameset_ok = false;
+                self.mode = Html5InsertionMode::InTable;
+            },
+            "area" | "br" | "embed" | "img" | "keygen" | "wbr" => {
+                self.reconstruct_formatting();
+                self.insert_html_element(&tag);
+                self.open_elements.pop();
+                self.frameset_ok = false;
+            },
+            "input" => {
+                self.reconstruct_formatting();
+                self.insert_html_element(&tag);
+                self.open_elements.pop();
+                if !html5_is_hidden_input(&tag) {
+                    self.frameset_ok = false;
+                }
+            },
+            "param" | "source" | "track" => {
+                self.insert_html_element(&tag);
+                self.open_elements.pop();
+            },
+            "hr" => {
+                self.close_p_in_button_scope();
+                self.insert_html_element(&tag);
+                self.open_elements.pop();
+                self.frameset_ok = false;
+            },
+            "image" => {
+                self.error("unexpected-start-tag");
+                tag.name = "img".to_string();
+                self.in_body_start_tag(tag);
+            },
+            "textarea" => {
+                self.insert_html_element(&tag);
+                self.skip_newline = true;
+                self.tokenizer.set_state(Html5TokenizerState::RcData);
+                self.original_mode = self.mode;
+                self.frameset_ok = false;
+                self.mode = Html5InsertionMode::Text;
+            },
+            "xmp" => {
+                self.close_p_in_button_scope();
+                self.reconstruct_formatting();
+                self.frameset_ok = false;
+                self.parse_text_element(&tag, Html5TokenizerState::RawText);
+            },
+            "iframe" => {
+                self.frameset_ok = false;
+                self.parse_text_element(&tag, Html5TokenizerState::RawText);
+            },
+            "noembed" => {
+                self.parse_text_element(&tag, Html5TokenizerState::RawText);
+            },
+            "noscript" if self.options.scripting => {
+                self.parse_text_element(&tag, Html5TokenizerState::RawText);
+            },
+            "select" => {
+                self.reconstruct_formatting();
+                self.insert_html_element(&tag);
+                self.frameset_ok = false;
+                use Html5InsertionMode as M;
+                self.mode = match self.mode {
+                    M::InTable | M::InCaption | M::InTableBody | M::InRow | M::InCell => M::InSelectInTable,
+                    _ => M::InSelect,
+                };
+            },
+            "optgroup" | "option" => {
+                if self.is_html(self.current_node(), "option") {
+                    self.open_elements.pop();
+                }
+                self.reconstruct_formatting();
+                self.insert_html_element(&tag);
+            },
+            "rb" | "rtc" => {
+                if self.in_scope("ruby", Html5Scope::Default) {
+                    self.generate_implied_end_tags(None);
+                    if !self.is_html(self.current_node(), "ruby") {
+                        self.error("unexpected-start-tag");
+                    }
+                }
+                self.insert_html_element(&tag);
+            },
+            "rp" | "rt" => {
+                if self.in_scope("ruby", Html5Scope::Default) {
+                    self.generate_implied_end_tags(Some("rtc"));
+                    if !self.is_html_one_of(self.current_node(), &["rtc", "ruby"]) {
+                        self.error("unexpected-start-tag");
+                    }
+                }
+                self.insert_html_element(&tag);
+            },
+            "math" => {
+                self.reconstruct_formatting();
+                self.insert_foreign_element(&tag, DomNamespace::MathMl);
+            },
+            "svg" => {
+                self.reconstruct_formatting();
+                self.insert_foreign_element(&tag, DomNamespace::Svg);
+            },
+            "caption" | "col" | "colgroup" | "frame" | "head" | "tbody" | "td" | "tfoot" | "th" | "thead" | "tr" => {
+                self.error("unexpected-start-tag");
+            },
+            _ => {
+                self.reconstruct_formatting();
+                self.insert_html_element(&tag);
+            },
+        }
+    }
+
+    fn merge_attributes(&mut self, id: usize, tag: &Html5TagToken) {
+        if let Html5NodeData::Element { attributes, .. } = &mut self.nodes[id].data {
+            for attr in &tag.attributes {
+                if !attributes.iter().any(|existing| existing.name == attr.name) {
+                    attributes.push(attr.clone());
+                }
+            }
+        }
+    }
+
+    fn in_body_end_tag(&mut self, name: String) {
+        match name.as_str() {
+            "template" => {
+                self.in_head(Html5TreeToken::EndTag(name));
+            },
+            "body" | "html" => {
+                if !self.in_scope("body", Html5Scope::Default) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.mode = Html5InsertionMode::AfterBody;
+                if name == "html" {
+                    self.reprocess(Html5InsertionMode::AfterBody, Html5TreeToken::EndTag(name));
+                }
+            },
+            "address" | "article" | "aside" | "blockquote" | "button" | "center" | "details" | "dialog" | "dir" | "div" | "dl" | "fieldset" | "figcaption" | "figure" | "footer" | "header" | "hgroup" | "listing" | "main" | "menu" | "nav" | "ol" | "pre" | "search" | "section" | "summary" | "ul" => {
+                if !self.in_scope(&name, Html5Scope::Default) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.is_html(self.current_node(), &name) {
+                    self.error("end-tag-too-early");
+                }
+                self.pop_until(&name);
+            },
+            "form" => {
+                if self.has_open("template") {
+                    if !self.in_scope("form", Html5Scope::Default) {
+                        self.error("unexpected-end-tag");
+                        return;
+                    }
+                    self.generate_implied_end_tags(None);
+                    if !self.is_html(self.current_node(), "form") {
+                        self.error("end-tag-too-early");
+                    }
+                    self.pop_until("form");
+                    return;
+                }
+                let form = match self.form_element.take() {
+                    Some(form) if self.in_scope_where(Html5Scope::Default, |id| id == form) => form,
+                    _ => {
+                        self.error("unexpected-end-tag");
+                        return;
+                    },
+                };
+                self.generate_implied_end_tags(None);
+                if self.current_node() != form {
+                    self.error("end-tag-too-early");
+                }
+                self.remove_open_element(form);
+            },
+            "p" => {
+                if !self.in_scope("p", Html5Scope::Button) {
+                    self.error("unexpected-end-tag");
+                    self.insert_implied_element("p");
+                }
+                self.close_p_element();
+            },
+            "li" => {
+                if !self.in_scope("li", Html5Scope::ListItem) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.generate_implied_end_tags(Some("li"));
+                if !self.is_html(self.current_node(), "li") {
+                    self.error("end-tag-too-early");
+                }
+                self.pop_until("li");
+            },
+            "dd" | "dt" => {
+                if !self.in_scope(&name, Html5Scope::Default) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.generate_implied_end_tags(Some(&name));
+                if !self.is_html(self.current_node(), &name) {
+                    self.error("end-tag-too-early");
+                }
+                self.pop_until(&name);
+            },
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                if !self.in_scope_where(Html5Scope::Default, |id| self.is_html_one_of(id, &HTML5_HEADINGS)) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.is_html(self.current_node(), &name) {
+                    self.error("end-tag-too-early");
+                }
+                self.pop_until_one_of(&HTML5_HEADINGS);
+            },
+            "a" | "b" | "big" | "code" | "em" | "font" | "i" | "nobr" | "s" | "small" | "strike" | "strong" | "tt" | "u" => {
+                if !self.adoption_agency(&name) {
+                    self.any_other_end_tag(&name);
+                }
+            },
+            "applet" | "marquee" | "object" => {
+                if !self.in_scope(&name, Html5Scope::Default) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.is_html(self.current_node(), &name) {
+                    self.error("end-tag-too-early");
+                }
+                self.pop_until(&name);
+                self.clear_formatting_to_marker();
+            },
+            "br" => {
+                self.error("unexpected-end-tag");
+                self.in_body_start_tag(html5_tag("br"));
+            },
+            _ => {
+                self.any_other_end_tag(&name);
+            },
+        }
+    }
+
+    fn any_other_end_tag(&mut self, name: &str) {
+        for i in (0..self.open_elements.len()).rev() {
+            let node = self.open_elements[i];
+            if self.is_html(node, name) {
+                self.generate_implied_end_tags(Some(name));
+                if self.current_node() != node {
+                    self.error("end-tag-too-early");
+                }
+                self.open_elements.truncate(i);
+                return;
+            }
+            if self.is_special(node) {
+                self.error("unexpected-end-tag");
+                return;
+            }
+        }
+    }
+
+    fn text(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) => {
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Eof => {
+                self.error("eof-in-text");
+                self.open_elements.pop();
+                self.reprocess(self.original_mode, Html5TreeToken::Eof);
+            },
+            _ => {
+                self.open_elements.pop();
+                self.mode = self.original_mode;
+            },
+        }
+    }
+
+    fn in_table(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(_) if self.is_html_one_of(self.current_node(), &["table", "tbody", "template", "tfoot", "thead", "tr"]) => {
+                self.pending_table_text.clear();
+                self.original_mode = self.mode;
+                self.reprocess(Html5InsertionMode::InTableText, tok);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) => {
+                match tag.name.as_str() {
+                    "caption" => {
+                        self.clear_stack_back_to(&["table", "template", "html"]);
+                        self.active_formatting.push(Html5FormattingEntry::Marker);
+                        self.insert_html_element(&tag);
+                        self.mode = Html5InsertionMode::InCaption;
+                    },
+                    "colgroup" => {
+                        self.clear_stack_back_to(&["table", "template", "html"]);
+                        self.insert_html_element(&tag);
+                        self.mode = Html5InsertionMode::InColumnGroup;
+                    },
+                    "col" => {
+                        self.clear_stack_back_to(&["table", "template", "html"]);
+                        self.insert_implied_element("colgroup");
+                        self.reprocess(Html5InsertionMode::InColumnGroup, Html5TreeToken::StartTag(tag));
+                    },
+                    "tbody" | "tfoot" | "thead" => {
+                        self.clear_stack_back_to(&["table", "template", "html"]);
+                        self.insert_html_element(&tag);
+                        self.mode = Html5InsertionMode::InTableBody;
+                    },
+                    "td" | "th" | "tr" => {
+                        self.clear_stack_back_to(&["table", "template", "html"]);
+                        self.insert_implied_element("tbody");
+                        self.reprocess(Html5InsertionMode::InTableBody, Html5TreeToken::StartTag(tag));
+                    },
+                    "table" => {
+                        self.error("unexpected-start-tag");
+                        if !self.in_scope("table", Html5Scope::Table) {
+                            return;
+                        }
+                        self.pop_until("table");
+                        self.reset_insertion_mode();
+                        self.process(self.mode, Html5TreeToken::StartTag(tag));
+                    },
+                    "style" | "script" | "template" => {
+                        self.in_head(Html5TreeToken::StartTag(tag));
+                    },
+                    "input" if html5_is_hidden_input(&tag) => {
+                        self.error("unexpected-hidden-input-in-table");
+                        self.insert_html_element(&tag);
+                        self.open_elements.pop();
+                    },
+                    "form" => {
+                        self.error("unexpected-start-tag");
+                        if self.has_open("template") || self.form_element.is_some() {
+                            return;
+                        }
+                        self.form_element = Some(self.insert_html_element(&tag));
+                        self.open_elements.pop();
+                    },
+                    _ => {
+                        self.in_table_anything_else(Html5TreeToken::StartTag(tag));
+                    },
+                }
+            },
+            Html5TreeToken::EndTag(name) => {
+                match name.as_str() {
+                    "table" => {
+                        if !self.in_scope("table", Html5Scope::Table) {
+                            self.error("unexpected-end-tag");
+                            return;
+                        }
+                        self.pop_until("table");
+                        self.reset_insertion_mode();
+                    },
+                    "body" | "caption" | "col" | "colgroup" | "html" | "tbody" | "td" | "tfoot" | "th" | "thead" | "tr" => {
+                        self.error("unexpected-end-tag");
+                    },
+                    "template" => {
+                        self.in_head(Html5TreeToken::EndTag(name));
+                    },
+                    _ => {
+                        self.in_table_anything_else(Html5TreeToken::EndTag(name));
+                    },
+                }
+            },
+            Html5TreeToken::Eof => {
+                self.in_body(Html5TreeToken::Eof);
+            },
+            tok => {
+                self.in_table_anything_else(tok);
+            },
+        }
+    }
+
+    fn in_table_anything_else(&mut self, tok: Html5TreeToken) {
+        self.error("foster-parenting");
+        self.foster_parenting = true;
+        self.in_body(tok);
+        self.foster_parenting = false;
+    }
+
+    fn in_table_text(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) => {
+                if text.starts_with('\0') {
+                    self.error("unexpected-null-character");
+                    return;
+                }
+                self.pending_table_text.push(text);
+            },
+            tok => {
+                let pending = std::mem::take(&mut self.pending_table_text);
+                if pending.iter().all(|text| html5_is_whitespace(text)) {
+                    for text in pending {
+                        self.insert_characters(&text);
+                    }
+                } else {
+                    for text in pending {
+                        self.in_table_anything_else(Html5TreeToken::Characters(text));
+                    }
+                }
+                self.reprocess(self.original_mode, tok);
+            },
+        }
+    }
+
+    fn close_caption(&mut self) -> bool {
+        if !self.in_scope("caption", Html5Scope::Table) {
+            self.error("unexpected-end-tag");
+            return false;
+        }
+        self.generate_implied_end_tags(None);
+        if !self.is_html(self.current_node(), "caption") {
+            self.error("end-tag-too-early");
+        }
+        self.pop_until("caption");
+        self.clear_formatting_to_marker();
+        self.mode = Html5InsertionMode::InTable;
+        return true;
+    }
+
+    fn in_caption(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::EndTag(name) if name == "caption" => {
+                self.close_caption();
+            },
+            Html5TreeToken::StartTag(ref tag) if matches!(tag.name.as_str(), "caption" | "col" | "colgroup" | "tbody" | "td" | "tfoot" | "th" | "thead" | "tr") => {
+                if self.close_caption() {
+                    self.reprocess(Html5InsertionMode::InTable, tok);
+                }
+            },
+            Html5TreeToken::EndTag(ref name) if name == "table" => {
+                if self.close_caption() {
+                    self.reprocess(Html5InsertionMode::InTable, tok);
+                }
+            },
+            Html5TreeToken::EndTag(name) if matches!(name.as_str(), "body" | "col" | "colgroup" | "html" | "tbody" | "td" | "tfoot" | "th" | "thead" | "tr") => {
+                self.error("unexpected-end-tag");
+            },
+            tok => {
+                self.in_body(tok);
+            },
+        }
+    }
+
+    fn in_column_group(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) if tag.name == "html" => {
+                self.in_body(Html5TreeToken::StartTag(tag));
+            },
+            Html5TreeToken::StartTag(tag) if tag.name == "col" => {
+                self.insert_html_element(&tag);
+                self.open_elements.pop();
+            },
+            Html5TreeToken::EndTag(name) if name == "colgroup" => {
+                if !self.is_html(self.current_node(), "colgroup") {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.open_elements.pop();
+                self.mode = Html5InsertionMode::InTable;
+            },
+            Html5TreeToken::EndTag(name) if name == "col" => {
+                self.error("unexpected-end-tag");
+            },
+            Html5TreeToken::StartTag(tag) if tag.name == "template" => {
+                self.in_head(Html5TreeToken::StartTag(tag));
+            },
+            Html5TreeToken::EndTag(name) if name == "template" => {
+                self.in_head(Html5TreeToken::EndTag(name));
+            },
+            Html5TreeToken::Eof => {
+                self.in_body(Html5TreeToken::Eof);
+            },
+            tok => {
+                if !self.is_html(self.current_node(), "colgroup") {
+                    self.error("unexpected-token-in-column-group");
+                    return;
+                }
+                self.open_elements.pop();
+                self.reprocess(Html5InsertionMode::InTable, tok);
+            },
+        }
+    }
+
+    fn in_table_body(&mut self, tok: Html5TreeToken) {
+        const BODY_CONTEXT: [&str; 5] = ["tbody", "tfoot", "thead", "template", "html"];
+        match tok {
+            Html5TreeToken::StartTag(tag) if tag.name == "tr" => {
+                self.clear_stack_back_to(&BODY_CONTEXT);
+                self.insert_html_element(&tag);
+                self.mode = Html5InsertionMode::InRow;
+            },
+            Html5TreeToken::StartTag(tag) if tag.name == "th" || tag.name == "td" => {
+                self.error("unexpected-cell-in-table-body");
+                self.clear_stack_back_to(&BODY_CONTEXT);
+                self.insert_implied_element("tr");
+                self.reprocess(Html5InsertionMode::InRow, Html5TreeToken::StartTag(tag));
+            },
+            Html5TreeToken::EndTag(name) if matches!(name.as_str(), "tbody" | "tfoot" | "thead") => {
+                if !self.in_scope(&name, Html5Scope::Table) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.clear_stack_back_to(&BODY_CONTEXT);
+                self.open_elements.pop();
+                self.mode = Html5InsertionMode::InTable;
+            },
+            Html5TreeToken::StartTag(ref tag) if matches!(tag.name.as_str(), "caption" | "col" | "colgroup" | "tbody" | "tfoot" | "thead") => {
+                self.leave_table_body(tok);
+            },
+            Html5TreeToken::EndTag(ref name) if name == "table" => {
+                self.leave_table_body(tok);
+            },
+            Html5TreeToken::EndTag(name) if matches!(name.as_str(), "body" | "caption" | "col" | "colgroup" | "html" | "td" | "th" | "tr") => {
+                self.error("unexpected-end-tag");
+            },
+            tok => {
+                self.in_table(tok);
+            },
+        }
+    }
+
+    fn leave_table_body(&mut self, tok: Html5TreeToken) {
+        if !self.in_scope_where(Html5Scope::Table, |id| self.is_html_one_of(id, &["tbody", "thead", "tfoot"])) {
+            self.error("unexpected-token-in-table-body");
+            return;
+        }
+        self.clear_stack_back_to(&["tbody", "tfoot", "thead", "template", "html"]);
+        self.open_elements.pop();
+        self.reprocess(Html5InsertionMode::InTable, tok);
+    }
+
+    fn close_row(&mut self) -> bool {
+        if !self.in_scope("tr", Html5Scope::Table) {
+            self.error("unexpected-end-tag");
+            return false;
+        }
+        self.clear_stack_back_to(&["tr", "template", "html"]);
+        self.open_elements.pop();
+        self.mode = Html5InsertionMode::InTableBody;
+        return true;
+    }
+
+    fn in_row(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::StartTag(tag) if tag.name == "th" || tag.name == "td" => {
+                self.clear_stack_back_to(&["tr", "template", "html"]);
+                self.insert_html_element(&tag);
+                self.mode = Html5InsertionMode::InCell;
+                self.active_formatting.push(Html5FormattingEntry::Marker);
+            },
+            Html5TreeToken::EndTag(name) if name == "tr" => {
+                self.close_row();
+            },
+            Html5TreeToken::StartTag(ref tag) if matches!(tag.name.as_str(), "caption" | "col" | "colgroup" | "tbody" | "tfoot" | "thead" | "tr") => {
+                if self.close_row() {
+                    self.reprocess(Html5InsertionMode::InTableBody, tok);
+                }
+            },
+            Html5TreeToken::EndTag(ref name) if name == "table" => {
+                if self.close_row() {
+                    self.reprocess(Html5InsertionMode::InTableBody, tok);
+                }
+            },
+            Html5TreeToken::EndTag(ref name) if matches!(name.as_str(), "tbody" | "tfoot" | "thead") => {
+                if !self.in_scope(name, Html5Scope::Table) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                if self.close_row() {
+                    self.reprocess(Html5InsertionMode::InTableBody, tok);
+                }
+            },
+            Html5TreeToken::EndTag(name) if matches!(name.as_str(), "body" | "caption" | "col" | "colgroup" | "html" | "td" | "th") => {
+                self.error("unexpected-end-tag");
+            },
+            tok => {
+                self.in_table(tok);
+            },
+        }
+    }
+
+    fn close_cell(&mut self) {
+        self.generate_implied_end_tags(None);
+        if !self.is_html_one_of(self.current_node(), &["td", "th"]) {
+            self.error("end-tag-too-early");
+        }
+        self.pop_until_one_of(&["td", "th"]);
+        self.clear_formatting_to_marker();
+        self.mode = Html5InsertionMode::InRow;
+    }
+
+    fn in_cell(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::EndTag(name) if name == "td" || name == "th" => {
+                if !self.in_scope(&name, Html5Scope::Table) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.generate_implied_end_tags(None);
+                if !self.is_html(self.current_node(), &name) {
+                    self.error("end-tag-too-early");
+                }
+                self.pop_until(&name);
+                self.clear_formatting_to_marker();
+                self.mode = Html5InsertionMode::InRow;
+            },
+            Html5TreeToken::StartTag(ref tag) if matches!(tag.name.as_str(), "caption" | "col" | "colgroup" | "tbody" | "td" | "tfoot" | "th" | "thead" | "tr") => {
+                if !self.in_scope("td", Html5Scope::Table) && !self.in_scope("th", Html5Scope::Table) {
+                    self.error("unexpected-start-tag");
+                    return;
+                }
+                self.close_cell();
+                self.reprocess(Html5InsertionMode::InRow, tok);
+            },
+            Html5TreeToken::EndTag(name) if matches!(name.as_str(), "body" | "caption" | "col" | "colgroup" | "html") => {
+                self.error("unexpected-end-tag");
+            },
+            Html5TreeToken::EndTag(ref name) if matches!(name.as_str(), "table" | "tbody" | "tfoot" | "thead" | "tr") => {
+                if !self.in_scope(name, Html5Scope::Table) {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.close_cell();
+                self.reprocess(Html5InsertionMode::InRow, tok);
+            },
+            tok => {
+                self.in_body(tok);
+            },
+        }
+    }
+
+    fn in_select(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) => {
+                if text.starts_with('\0') {
+                    self.error("unexpected-null-character");
+                    return;
+                }
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) => {
+                match tag.name.as_str() {
+                    "html" => {
+                        self.in_body(Html5TreeToken::StartTag(tag));
+                    },
+                    "option" => {
+                        if self.is_html(self.current_node(), "option") {
+                            self.open_elements.pop();
+                        }
+                        self.insert_html_element(&tag);
+                    },
+                    "optgroup" => {
+                        if self.is_html(self.current_node(), "option") {
+                            self.open_elements.pop();
+                        }
+                        if self.is_html(self.current_node(), "optgroup") {
+                            self.open_elements.pop();
+                        }
+                        self.insert_html_element(&tag);
+                    },
+                    "select" => {
+                        self.error("unexpected-start-tag");
+                        if self.in_scope("select", Html5Scope::Select) {
+                            self.pop_until("select");
+                            self.reset_insertion_mode();
+                        }
+                    },
+                    "input" | "keygen" | "textarea" => {
+                        self.error("unexpected-start-tag");
+                        if !self.in_scope("select", Html5Scope::Select) {
+                            return;
+                        }
+                        self.pop_until("select");
+                        self.reset_insertion_mode();
+                        self.process(self.mode, Html5TreeToken::StartTag(tag));
+                    },
+                    "script" | "template" => {
+                        self.in_head(Html5TreeToken::StartTag(tag));
+                    },
+                    _ => {
+                        self.error("unexpected-start-tag-in-select");
+                    },
+                }
+            },
+            Html5TreeToken::EndTag(name) => {
+                match name.as_str() {
+                    "optgroup" => {
+                        let len = self.open_elements.len();
+                        if self.is_html(self.current_node(), "option") && len > 1 && self.is_html(self.open_elements[len - 2], "optgroup") {
+                            self.open_elements.pop();
+                        }
+                        if self.is_html(self.current_node(), "optgroup") {
+                            self.open_elements.pop();
+                        } else {
+                            self.error("unexpected-end-tag");
+                        }
+                    },
+                    "option" => {
+                        if self.is_html(self.current_node(), "option") {
+                            self.open_elements.pop();
+                        } else {
+                            self.error("unexpected-end-tag");
+                        }
+                    },
+                    "select" => {
+                        if !self.in_scope("select", Html5Scope::Select) {
+                            self.error("unexpected-end-tag");
+                            return;
+                        }
+                        self.pop_until("select");
+                        self.reset_insertion_mode();
+                    },
+                    "template" => {
+                        self.in_head(Html5TreeToken::EndTag(name));
+                    },
+                    _ => {
+                        self.error("unexpected-end-tag-in-select");
+                    },
+                }
+            },
+            Html5TreeToken::Eof => {
+                self.in_body(Html5TreeToken::Eof);
+            },
+        }
+    }
+
+    fn in_select_in_table(&mut self, tok: Html5TreeToken) {
+        const TABLE_TAGS: [&str; 8] = ["caption", "table", "tbody", "tfoot", "thead", "tr", "td", "th"];
+        match tok {
+            Html5TreeToken::StartTag(ref tag) if TABLE_TAGS.contains(&tag.name.as_str()) => {
+                self.error("unexpected-table-element-in-select");
+                self.pop_until("select");
+                self.reset_insertion_mode();
+                self.process(self.mode, tok);
+            },
+            Html5TreeToken::EndTag(ref name) if TABLE_TAGS.contains(&name.as_str()) => {
+                self.error("unexpected-table-element-in-select");
+                if !self.in_scope(name, Html5Scope::Table) {
+                    return;
+                }
+                self.pop_until("select");
+                self.reset_insertion_mode();
+                self.process(self.mode, tok);
+            },
+            tok => {
+                self.in_select(tok);
+            },
+        }
+    }
+
+    fn in_template(&mut self, tok: Html5TreeToken) {
+        use Html5InsertionMode as M;
+        match tok {
+            Html5TreeToken::Characters(_) | Html5TreeToken::Comment(_) | Html5TreeToken::Doctype { .. } => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::StartTag(ref tag) => {
+                let mode = match tag.name.as_str() {
+                    "base" | "basefont" | "bgsound" | "link" | "meta" | "noframes" | "script" | "style" | "template" | "title" => {
+                        self.in_head(tok);
+                        return;
+                    },
+                    "caption" | "colgroup" | "tbody" | "tfoot" | "thead" => M::InTable,
+                    "col" => M::InColumnGroup,
+                    "tr" => M::InTableBody,
+                    "td" | "th" => M::InRow,
+                    _ => M::InBody,
+                };
+                self.template_modes.pop();
+                self.template_modes.push(mode);
+                self.reprocess(mode, tok);
+            },
+            Html5TreeToken::EndTag(ref name) => {
+                if name == "template" {
+                    self.in_head(tok);
+                    return;
+                }
+                self.error("unexpected-end-tag");
+            },
+            Html5TreeToken::Eof => {
+                if !self.has_open("template") {
+                    self.done = true;
+                    return;
+                }
+                self.error("eof-in-template");
+                self.pop_until("template");
+                self.clear_formatting_to_marker();
+                self.template_modes.pop();
+                self.reset_insertion_mode();
+                self.process(self.mode, Html5TreeToken::Eof);
+            },
+        }
+    }
+
+    fn after_body(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(ref text) if html5_is_whitespace(text) => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::Comment(text) => {
+                let html = self.open_elements[0];
+                self.insert_comment(text, Some((html, None)));
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(ref tag) if tag.name == "html" => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::EndTag(ref name) if name == "html" => {
+                if self.context.is_some() {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.mode = Html5InsertionMode::AfterAfterBody;
+            },
+            Html5TreeToken::Eof => {
+                self.done = true;
+            },
+            tok => {
+                self.error("unexpected-token-after-body");
+                self.reprocess(Html5InsertionMode::InBody, tok);
+            },
+        }
+    }
+
+    fn in_frameset(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::StartTag(tag) => {
+                match tag.name.as_str() {
+                    "html" => {
+                        self.in_body(Html5TreeToken::StartTag(tag));
+                    },
+                    "frameset" => {
+                        self.insert_html_element(&tag);
+                    },
+                    "frame" => {
+                        self.insert_html_element(&tag);
+                        self.open_elements.pop();
+                    },
+                    "noframes" => {
+                        self.in_head(Html5TreeToken::StartTag(tag));
+                    },
+                    _ => {
+                        self.error("unexpected-start-tag-in-frameset");
+                    },
+                }
+            },
+            Html5TreeToken::EndTag(name) if name == "frameset" => {
+                if self.open_elements.len() == 1 {
+                    self.error("unexpected-end-tag");
+                    return;
+                }
+                self.open_elements.pop();
+                if self.context.is_none() && !self.is_html(self.current_node(), "frameset") {
+                    self.mode = Html5InsertionMode::AfterFrameset;
+                }
+            },
+            Html5TreeToken::Eof => {
+                if self.open_elements.len() != 1 {
+                    self.error("eof-in-frameset");
+                }
+                self.done = true;
+            },
+            _ => {
+                self.error("unexpected-token-in-frameset");
+            },
+        }
+    }
+
+    fn after_frameset(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) if html5_is_whitespace(&text) => {
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::StartTag(ref tag) if tag.name == "html" => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::EndTag(ref name) if name == "html" => {
+                self.mode = Html5InsertionMode::AfterAfterFrameset;
+            },
+            Html5TreeToken::StartTag(ref tag) if tag.name == "noframes" => {
+                self.in_head(tok);
+            },
+            Html5TreeToken::Eof => {
+                self.done = true;
+            },
+            _ => {
+                self.error("unexpected-token-after-frameset");
+            },
+        }
+    }
+
+    fn after_after_body(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, Some((HTML5_DOCUMENT, None)));
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::Characters(ref text) if html5_is_whitespace(text) => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::StartTag(ref tag) if tag.name == "html" => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::Eof => {
+                self.done = true;
+            },
+            tok => {
+                self.error("unexpected-token-after-body");
+                self.reprocess(Html5InsertionMode::InBody, tok);
+            },
+        }
+    }
+
+    fn after_after_frameset(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, Some((HTML5_DOCUMENT, None)));
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::Characters(ref text) if html5_is_whitespace(text) => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::StartTag(ref tag) if tag.name == "html" => {
+                self.in_body(tok);
+            },
+            Html5TreeToken::StartTag(ref tag) if tag.name == "noframes" => {
+                self.in_head(tok);
+            },
+            Html5TreeToken::Eof => {
+                self.done = true;
+            },
+            _ => {
+                self.error("unexpected-token-after-frameset");
+            },
+        }
+    }
+
+    // svg and math content
+
+    fn process_foreign(&mut self, tok: Html5TreeToken) {
+        match tok {
+            Html5TreeToken::Characters(text) => {
+                if text.starts_with('\0') {
+                    self.error("unexpected-null-character");
+                    self.insert_characters(&"\u{FFFD}".repeat(text.chars().count()));
+                    return;
+                }
+                if !html5_is_whitespace(&text) {
+                    self.frameset_ok = false;
+                }
+                self.insert_characters(&text);
+            },
+            Html5TreeToken::Comment(text) => {
+                self.insert_comment(text, None);
+            },
+            Html5TreeToken::Doctype { .. } => {
+                self.error("unexpected-doctype");
+            },
+            Html5TreeToken::StartTag(tag) => {
+                let breaks_out = HTML5_FOREIGN_BREAKOUT_ELEMENTS.contains(&tag.name.as_str())
+                    || (tag.name == "font" && tag.attributes.iter().any(|attr| matches!(attr.name.as_str(), "color" | "face" | "size")));
+                if breaks_out {
+                    self.error("unexpected-html-element-in-foreign-content");
+                    while !self.open_elements.is_empty() {
+                        let current = self.current_node();
+                        if self.namespace(current) == DomNamespace::Html || self.is_mathml_text_integration_point(current) || self.is_html_integration_point(current) {
+                            break;
+                        }
+                        self.open_elements.pop();
+                    }
+                    self.process(self.mode, Html5TreeToken::StartTag(tag));
+                    return;
+                }
+                let namespace = self.adjusted_current_node().map(|id| self.namespace(id)).unwrap_or(DomNamespace::Html);
+                self.insert_foreign_element(&tag, namespace);
+            },
+            Html5TreeToken::EndTag(name) => {
+                let mut i = self.open_elements.len() - 1;
+                if !self.element_name(self.open_elements[i]).eq_ignore_ascii_case(&name) {
+                    self.error("unexpected-end-tag");
+                }
+                loop {
+                    if i == 0 {
+                        return;
+                    }
+                    let node = self.open_elements[i];
+                    if self.element_name(node).eq_ignore_ascii_case(&name) {
+                        self.open_elements.truncate(i);
+                        return;
+                    }
+                    i -= 1;
+                    if self.namespace(self.open_elements[i]) == DomNamespace::Html {
+                        self.process(self.mode, Html5TreeToken::EndTag(name));
+                        return;
+                    }
+                }
+            },
+            Html5TreeToken::Eof => {
+                self.process(self.mode, Html5TreeToken::Eof);
+            },
+        }
+    }
+
+}
+
+fn html5_tag(name: &str) -> Html5TagToken {
+    return Html5TagToken { name: name.to_string(), attributes: vec![], self_closing: false };
+}
+
+fn html5_is_whitespace(text: &str) -> bool {
+    return text.chars().all(|c| matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' '));
+}
+
+// splits text into runs that are all whitespace, all NUL or neither, the tree construction rules
+// treat every char of a run the same way
+fn html5_char_runs(text: &str) -> Vec<&str> {
+    let class = |c: char| {
+        return match c {
+            '\t' | '\n' | '\x0C' | '\r' | ' ' => 0,
+            '\0' => 1,
+            _ => 2,
+        };
+    };
+    let mut runs: Vec<&str> = vec![];
+    let mut start = 0;
+    let mut last_class = None;
+    for (i, c) in text.char_indices() {
+        let c_class = class(c);
+        if last_class.is_some_and(|last| last != c_class) {
+            runs.push(&text[start..i]);
+            start = i;
+        }
+        last_class = Some(c_class);
+    }
+    if start < text.len() {
+        runs.push(&text[start..]);
+    }
+    return runs;
+}
+
+fn html5_is_hidden_input(tag: &Html5TagToken) -> bool {
+    return tag.attributes.iter().any(|attr| attr.name == "type" && attr.value.eq_ignore_ascii_case("hidden"));
+}
+
+fn html5_same_attributes(a: &[HtmlAttribute], b: &[HtmlAttribute]) -> bool {
+    return a.len() == b.len() && a.iter().all(|attr| b.contains(attr));
+}
+
+fn html5_is_raw_text_element(tag_name: &str, scripting: bool) -> bool {
+    return is_html_raw_text_element(tag_name) || (scripting && tag_name == "noscript");
+}
+
+// a doctype that is missing, misspelled or names an old html version puts the document in quirks mode
+fn html5_is_quirks_doctype(name: Option<&str>, public_id: Option<&str>, system_id: Option<&str>, force_quirks: bool) -> bool {
+    if force_quirks || name != Some("html") {
+        return true;
+    }
+    let public_id = public_id.map(|id| id.to_ascii_lowercase());
+    let system_id = system_id.map(|id| id.to_ascii_lowercase());
+    if let Some(public_id) = &public_id {
+        if matches!(public_id.as_str(), "-//w3o//dtd w3 html strict 3.0//en//" | "-/w3c/dtd html 4.0 transitional/en" | "html") {
+            return true;
+        }
+        if HTML5_QUIRKS_PUBLIC_ID_PREFIXES.iter().any(|prefix| public_id.starts_with(prefix)) {
+            return true;
+        }
+        let html4 = public_id.starts_with("-//w3c//dtd html 4.01 frameset//") || public_id.starts_with("-//w3c//dtd html 4.01 transitional//");
+        if html4 && system_id.is_none() {
+            return true;
+        }
+    }
+    return system_id.as_deref() == Some("http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd");
+}
+
+const HTML5_HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+const HTML5_SPECIAL_ELEMENTS: [&str; 83] = [
+    "address", "applet", "area", "article", "aside", "base", "basefont", "bgsound", "blockquote", "body", "br", "button",
+    "caption", "center", "col", "colgroup", "dd", "details", "dir", "div", "dl", "dt", "embed", "fieldset", "figcaption",
+    "figure", "footer", "form", "frame", "frameset", "h1", "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup", "hr",
+    "html", "iframe", "img", "input", "keygen", "li", "link", "listing", "main", "marquee", "menu", "meta", "nav",
+    "noembed", "noframes", "noscript", "object", "ol", "p", "param", "plaintext", "pre", "script", "search", "section",
+    "select", "source", "style", "summary", "table", "tbody", "td", "template", "textarea", "tfoot", "th", "thead",
+    "title", "tr", "track", "ul", "wbr", "xmp",
+];
+
+// start tags that end svg or math content and go back to html
+const HTML5_FOREIGN_BREAKOUT_ELEMENTS: [&str; 44] = [
+    "b", "big", "blockquote", "body", "br", "center", "code", "dd", "div", "dl", "dt", "em", "embed", "h1", "h2", "h3",
+    "h4", "h5", "h6", "head", "hr", "i", "img", "li", "listing", "menu", "meta", "nobr", "ol", "p", "pre", "ruby", "s",
+    "small", "span", "strong", "strike", "sub", "sup", "table", "tt", "u", "ul", "var",
+];
+
+const HTML5_SVG_TAG_NAMES: [(&str, &str); 37] = [
+    ("altglyph", "altGlyph"), ("altglyphdef", "altGlyphDef"), ("altglyphitem", "altGlyphItem"), ("animatecolor", "animateColor"),
+    ("animatemotion", "animateMotion"), ("animatetransform", "animateTransform"), ("clippath", "clipPath"), ("feblend", "feBlend"),
+    ("fecolormatrix", "feColorMatrix"), ("fecomponenttransfer", "feComponentTransfer"), ("fecomposite", "feComposite"),
+    ("feconvolvematrix", "feConvolveMatrix"), ("fediffuselighting", "feDiffuseLighting"), ("fedisplacementmap", "feDisplacementMap"),
+    ("fedistantlight", "feDistantLight"), ("fedropshadow", "feDropShadow"), ("feflood", "feFlood"), ("fefunca", "feFuncA"),
+    ("fefuncb", "feFuncB"), ("fefuncg", "feFuncG"), ("fefuncr", "feFuncR"), ("fegaussianblur", "feGaussianBlur"),
+    ("feimage", "feImage"), ("femerge", "feMerge"), ("femergenode", "feMergeNode"), ("femorphology", "feMorphology"),
+    ("feoffset", "feOffset"), ("fepointlight", "fePointLight"), ("fespecularlighting", "feSpecularLighting"),
+    ("fespotlight", "feSpotLight"), ("fetile", "feTile"), ("feturbulence", "feTurbulence"), ("foreignobject", "foreignObject"),
+    ("glyphref", "glyphRef"), ("lineargradient", "linearGradient"), ("radialgradient", "radialGradient"), ("textpath", "textPath"),
+];
+
+const HTML5_SVG_ATTRIBUTE_NAMES: [(&str, &str); 58] = [
+    ("attributename", "attributeName"), ("attributetype", "attributeType"), ("basefrequency", "baseFrequency"),
+    ("baseprofile", "baseProfile"), ("calcmode", "calcMode"), ("clippathunits", "clipPathUnits"),
+    ("diffuseconstant", "diffuseConstant"), ("edgemode", "edgeMode"), ("filterunits", "filterUnits"), ("glyphref", "glyphRef"),
+    ("gradienttransform", "gradientTransform"), ("gradientunits", "gradientUnits"), ("kernelmatrix", "kernelMatrix"),
+    ("kernelunitlength", "kernelUnitLength"), ("keypoints", "keyPoints"), ("keysplines", "keySplines"), ("keytimes", "keyTimes"),
+    ("lengthadjust", "lengthAdjust"), ("limitingconeangle", "limitingConeAngle"), ("markerheight", "markerHeight"),
+    ("markerunits", "markerUnits"), ("markerwidth", "markerWidth"), ("maskcontentunits", "maskContentUnits"),
+    ("maskunits", "maskUnits"), ("numoctaves", "numOctaves"), ("pathlength", "pathLength"),
+    ("patterncontentunits", "patternContentUnits"), ("patterntransform", "patternTransform"), ("patternunits", "patternUnits"),
+    ("pointsatx", "pointsAtX"), ("pointsaty", "pointsAtY"), ("pointsatz", "pointsAtZ"), ("preservealpha", "preserveAlpha"),
+    ("preserveaspectratio", "preserveAspectRatio"), ("primitiveunits", "primitiveUnits"), ("refx", "refX"), ("refy", "refY"),
+    ("repeatcount", "repeatCount"), ("repeatdur", "repeatDur"), ("requiredextensions", "requiredExtensions"),
+    ("requiredfeatures", "requiredFeatures"), ("specularconstant", "specularConstant"), ("specularexponent", "specularExponent"),
+    ("spreadmethod", "spreadMethod"), ("startoffset", "startOffset"), ("stddeviation", "stdDeviation"),
+    ("stitchtiles", "stitchTiles"), ("surfacescale", "surfaceScale"), ("systemlanguage", "systemLanguage"),
+    ("tablevalues", "tableValues"), ("targetx", "targetX"), ("targety", "targetY"), ("textlength", "textLength"),
+    ("viewbox", "viewBox"), ("viewtarget", "viewTarget"), ("xchannelselector", "xChannelSelector"),
+    ("ychannelselector", "yChannelSelector"), ("zoomandpan", "zoomAndPan"),
+];
+
+const HTML5_QUIRKS_PUBLIC_ID_PREFIXES: [&str; 55] = [
+    "+//silmaril//dtd html pro v0r11 19970101//", "-//as//dtd html 3.0 aswedit + extensions//",
+    "-//advasoft ltd//dtd html 3.0 aswedit + extensions//", "-//ietf//dtd html 2.0 level 1//", "-//ietf//dtd html 2.0 level 2//",
+    "-//ietf//dtd html 2.0 strict level 1//", "-//ietf//dtd html 2.0 strict level 2//", "-//ietf//dtd html 2.0 strict//",
+    "-//ietf//dtd html 2.0//", "-//ietf//dtd html 2.1e//", "-//ietf//dtd html 3.0//", "-//ietf//dtd html 3.2 final//",
+    "-//ietf//dtd html 3.2//", "-//ietf//dtd html 3//", "-//ietf//dtd html level 0//", "-//ietf//dtd html level 1//",
+    "-//ietf//dtd html level 2//", "-//ietf//dtd html level 3//", "-//ietf//dtd html strict level 0//",
+    "-//ietf//dtd html strict level 1//", "-//ietf//dtd html strict level 2//", "-//ietf//dtd html strict level 3//",
+    "-//ietf//dtd html strict//", "-//ietf//dtd html//", "-//metrius//dtd metrius presentational//",
+    "-//microsoft//dtd internet explorer 2.0 html strict//", "-//microsoft//dtd internet explorer 2.0 html//",
+    "-//microsoft//dtd internet explorer 2.0 tables//", "-//microsoft//dtd internet explorer 3.0 html strict//",
+    "-//microsoft//dtd internet explorer 3.0 html//", "-//microsoft//dtd internet explorer 3.0 tables//",
+    "-//netscape comm. corp.//dtd html//", "-//netscape comm. corp.//dtd strict html//", "-//o'reilly and associates//dtd html 2.0//",
+    "-//o'reilly and associates//dtd html extended 1.0//", "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+    "-//sq//dtd html 2.0 hotmetal + extensions//", "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+    "-//softquad//dtd hotmetal pro 4.0::19971010::extensions to html 4.0//", "-//spyglass//dtd html 2.0 extended//",
+    "-//sun microsystems corp.//dtd hotjava html//", "-//sun microsystems corp.//dtd hotjava strict html//",
+    "-//w3c//dtd html 3 1995-03-24//", "-//w3c//dtd html 3.2 draft//", "-//w3c//dtd html 3.2 final//", "-//w3c//dtd html 3.2//",
+    "-//w3c//dtd html 3.2s draft//", "-//w3c//dtd html 4.0 frameset//", "-//w3c//dtd html 4.0 transitional//",
+    "-//w3c//dtd html experimental 19960712//", "-//w3c//dtd html experimental 970421//", "-//w3c//dtd w3 html//",
+    "-//w3o//dtd w3 html 3.0//", "-//webtechs//dtd mozilla html 2.0//", "-//webtechs//dtd mozilla html//",
+];
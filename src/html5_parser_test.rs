@@ -0,0 +1,238 @@
+use crate::*;
+use crate::dom::{DomNamespace, DomNode, DomNodeKind};
+
+#[derive(Debug, Default)]
+struct Html5libTreeTest {
+    file: String,
+    data: String,
+    fragment: Option<String>,
+    scripting: Option<bool>,
+    document: String,
+}
+
+// the tree construction fixtures from html5lib-tests, vendored under fixtures/html5lib-tests
+fn html5lib_tree_fixtures() -> Vec<Html5libTreeTest> {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/html5lib-tests/tree-construction");
+    let mut paths: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    paths.sort();
+    let mut tests: Vec<Html5libTreeTest> = vec![];
+    for path in paths {
+        if path.extension().is_none_or(|ext| ext != "dat") {
+            continue;
+        }
+        let file = path.file_name().unwrap().to_string_lossy().into_owned();
+        let source = std::fs::read_to_string(&path).unwrap();
+        let mut section = String::new();
+        let mut lines: Vec<&str> = vec![];
+        let mut test = Html5libTreeTest::default();
+        // a section runs until the next '#' header, tests are separated by a blank line before '#data'
+        let finish_section = |test: &mut Html5libTreeTest, section: &str, lines: &mut Vec<&str>| {
+            let text = lines.join("\n");
+            match section {
+                "#data" => test.data = text,
+                "#document-fragment" => test.fragment = Some(text),
+                "#document" => test.document = text.strip_suffix('\n').unwrap_or(&text).to_string(),
+                _ => {},
+            }
+            lines.clear();
+        };
+        for line in source.split('\n') {
+            let header = matches!(line, "#data" | "#errors" | "#new-errors" | "#document-fragment" | "#script-off" | "#script-on" | "#document");
+            if !header || (section == "#document" && line != "#data") {
+                lines.push(line);
+                continue;
+            }
+            finish_section(&mut test, &section, &mut lines);
+            if line == "#data" && !section.is_empty() {
+                tests.push(std::mem::take(&mut test));
+            }
+            match line {
+                "#script-off" => test.scripting = Some(false),
+                "#script-on" => test.scripting = Some(true),
+                _ => {},
+            }
+            test.file = file.clone();
+            section = line.to_string();
+        }
+        finish_section(&mut test, &section, &mut lines);
+        tests.push(test);
+    }
+    return tests;
+}
+
+// foreign elements spell namespaced attributes like xlink:href with a prefix, the dump separates it by a space
+fn html5lib_attribute_name(node: &DomNode, name: &str) -> String {
+    if node.namespace == DomNamespace::Html {
+        return name.to_string();
+    }
+    return match name {
+        "xlink:actuate" | "xlink:arcrole" | "xlink:href" | "xlink:role" | "xlink:show" | "xlink:title" | "xlink:type" | "xml:lang" | "xml:space" | "xmlns:xlink" => name.replacen(':', " ", 1),
+        "xmlns" => "xmlns xmlns".to_string(),
+        _ => name.to_string(),
+    };
+}
+
+fn html5lib_dump(node: &DomNode, depth: usize, out: &mut Vec<String>) {
+    let indent = format!("| {}", "  ".repeat(depth));
+    match node.kind {
+        DomNodeKind::Doctype => {
+            let (public_id, system_id) = match html5_tokenize(&node.outer_html).into_iter().next() {
+                Some(Html5Token::Doctype { public_id, system_id, .. }) => (public_id.unwrap_or_default(), system_id.unwrap_or_default()),
+                _ => (String::new(), String::new()),
+            };
+            if public_id.is_empty() && system_id.is_empty() {
+                out.push(format!("{}<!DOCTYPE {}>", indent, node.tag_name));
+            } else {
+                out.push(format!("{}<!DOCTYPE {} \"{}\" \"{}\">", indent, node.tag_name, public_id, system_id));
+            }
+        },
+        DomNodeKind::Text => out.push(format!("{}\"{}\"", indent, node.text)),
+        DomNodeKind::Comment => out.push(format!("{}<!-- {} -->", indent, node.text)),
+        DomNodeKind::Element => {
+            let prefix = match node.namespace {
+                DomNamespace::Html => "",
+                DomNamespace::Svg => "svg ",
+                DomNamespace::MathMl => "math ",
+            };
+            out.push(format!("{}<{}{}>", indent, prefix, node.tag_name));
+            let mut attributes: Vec<(String, &str)> = node.attributes.iter().map(|attr| (html5lib_attribute_name(node, &attr.name), attr.value.as_str())).collect();
+            attributes.sort();
+            for (name, value) in attributes {
+                out.push(format!("| {}{}=\"{}\"", "  ".repeat(depth + 1), name, value));
+            }
+            let mut child_depth = depth + 1;
+            if node.namespace == DomNamespace::Html && node.tag_name == "template" {
+                out.push(format!("| {}content", "  ".repeat(depth + 1)));
+                child_depth += 1;
+            }
+            for child in &node.children {
+                html5lib_dump(child, child_depth, out);
+            }
+        },
+        DomNodeKind::Root => {
+            for child in &node.children {
+                html5lib_dump(child, depth, out);
+            }
+        },
+    }
+}
+
+fn html5lib_tree(root: &DomNode) -> String {
+    let mut out: Vec<String> = vec![];
+    html5lib_dump(root, 0, &mut out);
+    return out.join("\n");
+}
+
+// fixtures written against older revisions of the spec, this tree follows the current rules. html start tags
+// that break out of svg or math content in a fragment used to stay foreign when nothing was left to pop
+const HTML5LIB_OLDER_TREE_RULES: [(&str, &str); 6] = [
+    ("svg path", "<nobr>X"),
+    ("svg path", "<font color></font>X"),
+    ("math annotation-xml", "<div></div>"),
+    ("math math", "<div></div>"),
+    ("svg svg", "<div><h1>X</h1></div>"),
+    ("svg svg", "<div></div>"),
+];
+
+#[test]
+fn test_html5_parser_html5lib_fixtures() {
+    let mut failures: Vec<String> = vec![];
+    let mut ran = 0;
+    for test in html5lib_tree_fixtures() {
+        if HTML5LIB_OLDER_TREE_RULES.contains(&(test.fragment.as_deref().unwrap_or_default(), test.data.as_str())) {
+            continue;
+        }
+        let modes = match test.scripting {
+            Some(scripting) => vec![scripting],
+            None => vec![false, true],
+        };
+        for scripting in modes {
+            let options = Html5ParserOptions { scripting };
+            let result = match &test.fragment {
+                Some(context) => {
+                    let (namespace, tag) = match context.split_once(' ') {
+                        Some(("svg", tag)) => (DomNamespace::Svg, tag),
+                        Some(("math", tag)) => (DomNamespace::MathMl, tag),
+                        _ => (DomNamespace::Html, context.as_str()),
+                    };
+                    html5_parse_fragment(&test.data, tag, namespace, options)
+                },
+                None => html5_parse_with_options(&test.data, options),
+            };
+            let actual = html5lib_tree(&result.root);
+            ran += 1;
+            if actual != test.document {
+                failures.push(format!("{} (scripting {}): {:?}\nexpected:\n{}\nactual:\n{}", test.file, scripting, test.data, test.document, actual));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{} of {} failed\n\n{}", failures.len(), ran, failures.join("\n\n"));
+    assert!(ran > 2500);
+}
+
+#[test]
+fn test_html5_parse_implied_elements() {
+    let root = html5_parse("<title>Hi</title><p>One<p>Two");
+    assert!(html5lib_tree(&root) == [
+        "| <html>",
+        "|   <head>",
+        "|     <title>",
+        "|       \"Hi\"",
+        "|   <body>",
+        "|     <p>",
+        "|       \"One\"",
+        "|     <p>",
+        "|       \"Two\"",
+    ].join("\n"));
+    assert!(root.outer_html == "<html><head><title>Hi</title></head><body><p>One</p><p>Two</p></body></html>");
+    assert!(root.text == "HiOneTwo");
+}
+
+#[test]
+fn test_html5_parse_tables() {
+    let root = html5_parse("<table><tr><td>1<td>2</table>");
+    let body = &root.children[0].children[1];
+    assert!(body.outer_html == "<body><table><tbody><tr><td>1</td><td>2</td></tr></tbody></table></body>");
+    // text directly inside a table is moved in front of it
+    let root = html5_parse("<table>oops<tr><td>1</table>");
+    let body = &root.children[0].children[1];
+    assert!(body.outer_html == "<body>oops<table><tbody><tr><td>1</td></tr></tbody></table></body>");
+}
+
+#[test]
+fn test_html5_parse_misnested_formatting() {
+    let root = html5_parse("<b>1<i>2</b>3</i>");
+    let body = &root.children[0].children[1];
+    assert!(body.outer_html == "<body><b>1<i>2</i></b><i>3</i></body>");
+    let root = html5_parse("<b>1<p>2</b>3</p>");
+    let body = &root.children[0].children[1];
+    assert!(body.outer_html == "<body><b>1</b><p><b>2</b>3</p></body>");
+}
+
+#[test]
+fn test_html5_parse_errors_and_fragments() {
+    let result = html5_parse_with_options("<!DOCTYPE html><p>a</span>", Html5ParserOptions::default());
+    assert!(result.errors.len() == 1);
+    assert!(result.errors[0].code == "unexpected-end-tag");
+    assert!(result.errors[0].span.start == 19);
+    let result = html5_parse_fragment("<td>1<td>2", "tr", DomNamespace::Html, Html5ParserOptions::default());
+    assert!(result.root.outer_html == "<td>1</td><td>2</td>");
+    let result = html5_parse_fragment("a < b &amp; c", "script", DomNamespace::Html, Html5ParserOptions::default());
+    assert!(result.root.outer_html == "a < b &amp; c");
+}
+
+#[test]
+fn test_html5_parse_noscript() {
+    // with scripting on the inside of <noscript> is raw text and comes back as written
+    let html = "<noscript>a &amp; <b></noscript>";
+    let result = html5_parse_with_options(html, Html5ParserOptions { scripting: true });
+    let noscript = &result.root.children[0].children[0].children[0];
+    assert!(noscript.children[0].text == "a &amp; <b>");
+    assert!(noscript.outer_html == html);
+    let result = html5_parse_fragment("a &amp; <b>", "noscript", DomNamespace::Html, Html5ParserOptions { scripting: true });
+    assert!(result.root.outer_html == "a &amp; <b>");
+    // with it off the markup is parsed, and the text is escaped again
+    let result = html5_parse_with_options("<body><noscript>a &amp; <b></noscript>", Html5ParserOptions { scripting: false });
+    let body = &result.root.children[0].children[1];
+    assert!(body.outer_html == "<body><noscript>a &amp; <b></b></noscript></body>");
+}
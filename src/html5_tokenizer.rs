@@ -0,0 +1,1750 @@
+use crate::entities::{html_named_entity, html_numeric_char, HTML_ENTITY_MAX_LEN};
+use crate::html_utils::HtmlAttribute;
+use crate::span::{Span, SpanMap};
+use std::collections::VecDeque;
+
+// the states of the tokenizer from the HTML Living Standard, named the way the standard names them.
+// a tree builder switches the tokenizer into RcData, RawText, ScriptData or PlainText after the start tags that need it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Html5TokenizerState {
+    Data,
+    RcData,
+    RawText,
+    ScriptData,
+    PlainText,
+    TagOpen,
+    EndTagOpen,
+    TagName,
+    RcDataLessThanSign,
+    RcDataEndTagOpen,
+    RcDataEndTagName,
+    RawTextLessThanSign,
+    RawTextEndTagOpen,
+    RawTextEndTagName,
+    ScriptDataLessThanSign,
+    ScriptDataEndTagOpen,
+    ScriptDataEndTagName,
+    ScriptDataEscapeStart,
+    ScriptDataEscapeStartDash,
+    ScriptDataEscaped,
+    ScriptDataEscapedDash,
+    ScriptDataEscapedDashDash,
+    ScriptDataEscapedLessThanSign,
+    ScriptDataEscapedEndTagOpen,
+    ScriptDataEscapedEndTagName,
+    ScriptDataDoubleEscapeStart,
+    ScriptDataDoubleEscaped,
+    ScriptDataDoubleEscapedDash,
+    ScriptDataDoubleEscapedDashDash,
+    ScriptDataDoubleEscapedLessThanSign,
+    ScriptDataDoubleEscapeEnd,
+    BeforeAttributeName,
+    AttributeName,
+    AfterAttributeName,
+    BeforeAttributeValue,
+    AttributeValueDoubleQuoted,
+    AttributeValueSingleQuoted,
+    AttributeValueUnquoted,
+    AfterAttributeValueQuoted,
+    SelfClosingStartTag,
+    BogusComment,
+    MarkupDeclarationOpen,
+    CommentStart,
+    CommentStartDash,
+    Comment,
+    CommentLessThanSign,
+    CommentLessThanSignBang,
+    CommentLessThanSignBangDash,
+    CommentLessThanSignBangDashDash,
+    CommentEndDash,
+    CommentEnd,
+    CommentEndBang,
+    Doctype,
+    BeforeDoctypeName,
+    DoctypeName,
+    AfterDoctypeName,
+    AfterDoctypePublicKeyword,
+    BeforeDoctypePublicIdentifier,
+    DoctypePublicIdentifierDoubleQuoted,
+    DoctypePublicIdentifierSingleQuoted,
+    AfterDoctypePublicIdentifier,
+    BetweenDoctypePublicAndSystemIdentifiers,
+    AfterDoctypeSystemKeyword,
+    BeforeDoctypeSystemIdentifier,
+    DoctypeSystemIdentifierDoubleQuoted,
+    DoctypeSystemIdentifierSingleQuoted,
+    AfterDoctypeSystemIdentifier,
+    BogusDoctype,
+    CdataSection,
+    CdataSectionBracket,
+    CdataSectionEnd,
+    CharacterReference,
+    NamedCharacterReference,
+    AmbiguousAmpersand,
+    NumericCharacterReference,
+    HexadecimalCharacterReferenceStart,
+    DecimalCharacterReferenceStart,
+    HexadecimalCharacterReference,
+    DecimalCharacterReference,
+    NumericCharacterReferenceEnd,
+}
+
+// tokens as the standard defines them: names are lowercased, character references are decoded
+// and attribute values are final. adjacent characters come out together as one Character token
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Html5Token {
+    Doctype { name: Option<String>, public_id: Option<String>, system_id: Option<String>, force_quirks: bool },
+    StartTag { tag_name: String, attributes: Vec<HtmlAttribute>, self_closing: bool },
+    EndTag { tag_name: String },
+    Comment { text: String },
+    Character { text: String },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpannedHtml5Token {
+    pub token: Html5Token,
+    pub span: Span,
+}
+
+// a parse error never stops tokenizing, code is the name the standard gives the error, like 'eof-in-tag'
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Html5ParseError {
+    pub code: &'static str,
+    pub span: Span,
+}
+
+// tokens and parse errors in the order the standard emits them
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Html5Event {
+    Token(SpannedHtml5Token),
+    Error(Html5ParseError),
+}
+
+// the tag being built, attributes are only kept once their name is complete and not a duplicate
+#[derive(Debug, Default)]
+struct Html5TagBuilder {
+    is_end: bool,
+    tag_name: String,
+    attributes: Vec<HtmlAttribute>,
+    self_closing: bool,
+    attr_name: String,
+    attr_value: String,
+    attr_duplicate: bool,
+    in_attr: bool,
+}
+
+#[derive(Debug, Default)]
+struct Html5DoctypeBuilder {
+    name: Option<String>,
+    public_id: Option<String>,
+    system_id: Option<String>,
+    force_quirks: bool,
+}
+
+// the tokenizer from the HTML Living Standard. it never fails, anything out of place becomes a parse error
+// and tokenizing carries on the way a browser would
+#[derive(Debug)]
+pub struct Html5Tokenizer {
+    chars: Vec<char>,
+    map: SpanMap,
+    pos: usize,
+    // how many chars the last consume stepped over, a '\r\n' pair is read as a single '\n'
+    last_width: usize,
+    // chars before this position were already checked for control and noncharacter code points
+    checked: usize,
+    state: Html5TokenizerState,
+    return_state: Html5TokenizerState,
+    tag: Html5TagBuilder,
+    doctype: Html5DoctypeBuilder,
+    comment: String,
+    temp_buffer: String,
+    char_ref_code: u32,
+    last_start_tag: Option<String>,
+    // set by a tree builder while the adjusted current node is not an html element, so '<![CDATA[' opens a section
+    allow_cdata: bool,
+    // where the token being built started
+    token_start: usize,
+    // where the current run of data started, chars emitted while in it are reported from here
+    construct_start: usize,
+    pending_chars: String,
+    pending_start: usize,
+    pending_end: usize,
+    events: VecDeque<Html5Event>,
+    errors: Vec<Html5ParseError>,
+    done: bool,
+}
+
+pub fn html5_tokenize(source: &str) -> Vec<Html5Token> {
+    return Html5Tokenizer::new(source).map(|tok| tok.token).collect();
+}
+
+impl Html5Tokenizer {
+
+    pub fn new(source: &str) -> Html5Tokenizer {
+        return Html5Tokenizer {
+            chars: source.chars().collect(),
+            map: SpanMap::with_base(source, Span::new(0, 0, 1, 1)),
+            pos: 0,
+            last_width: 0,
+            checked: 0,
+            state: Html5TokenizerState::Data,
+            return_state: Html5TokenizerState::Data,
+            tag: Html5TagBuilder::default(),
+            doctype: Html5DoctypeBuilder::default(),
+            comment: String::new(),
+            temp_buffer: String::new(),
+            char_ref_code: 0,
+            last_start_tag: None,
+            allow_cdata: false,
+            token_start: 0,
+            construct_start: 0,
+            pending_chars: String::new(),
+            pending_start: 0,
+            pending_end: 0,
+            events: VecDeque::new(),
+            errors: vec![],
+            done: false,
+        };
+    }
+
+    pub fn state(&self) -> Html5TokenizerState {
+        return self.state;
+    }
+
+    pub fn set_state(&mut self, state: Html5TokenizerState) {
+        self.state = state;
+    }
+
+    // the name an end tag has to have to close an RcData, RawText or ScriptData body
+    pub fn set_last_start_tag(&mut self, tag_name: Option<&str>) {
+        self.last_start_tag = tag_name.map(|name| name.to_string());
+    }
+
+    pub fn set_allow_cdata(&mut self, allow_cdata: bool) {
+        self.allow_cdata = allow_cdata;
+    }
+
+    // the parse errors seen so far by the token iterator, next_event hands them out in line with the tokens instead
+    pub fn errors(&self) -> &[Html5ParseError] {
+        return &self.errors;
+    }
+
+    pub fn next_event(&mut self) -> Option<Html5Event> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+            if self.done {
+                return None;
+            }
+            self.step();
+        }
+    }
+
+    // consuming
+
+    fn consume(&mut self) -> Option<char> {
+        self.last_width = 0;
+        let c = *self.chars.get(self.pos)?;
+        let mut width = 1;
+        let c = if c == '\r' {
+            if self.chars.get(self.pos + 1) == Some(&'\n') {
+                width = 2;
+            }
+            '\n'
+        } else {
+            c
+        };
+        if self.pos >= self.checked {
+            self.check_input_char(c);
+            self.checked = self.pos + width;
+        }
+        self.pos += width;
+        self.last_width = width;
+        return Some(c);
+    }
+
+    fn reconsume(&mut self) {
+        self.pos -= self.last_width;
+        self.last_width = 0;
+    }
+
+    // the next chars match s, ascii letters compared without regard to case when ignore_case is set
+    fn next_chars_are(&self, s: &str, ignore_case: bool) -> bool {
+        let mut i = self.pos;
+        for expected in s.chars() {
+            match self.chars.get(i) {
+                Some(c) if *c == expected || (ignore_case && c.eq_ignore_ascii_case(&expected)) => {
+                    i += 1;
+                },
+                _ => {
+                    return false;
+                },
+            }
+        }
+        return true;
+    }
+
+    fn skip(&mut self, count: usize) {
+        for _ in 0..count {
+            self.consume();
+        }
+    }
+
+    fn check_input_char(&mut self, c: char) {
+        let code = c as u32;
+        let noncharacter = (0xFDD0..=0xFDEF).contains(&code) || (code & 0xFFFE) == 0xFFFE;
+        if noncharacter {
+            self.error_at("noncharacter-in-input-stream", self.pos);
+            return;
+        }
+        let control = (code < 0x20 && !matches!(c, '\t' | '\n' | '\x0C' | '\r' | '\0')) || (0x7F..=0x9F).contains(&code);
+        if control {
+            self.error_at("control-character-in-input-stream", self.pos);
+        }
+    }
+
+    // emitting
+
+    // reported at the char just consumed, or where the input ended
+    fn error(&mut self, code: &'static str) {
+        let at = self.pos - self.last_width;
+        self.error_at(code, at);
+    }
+
+    fn error_at(&mut self, code: &'static str, at: usize) {
+        self.flush_chars();
+        let err = Html5ParseError { code, span: self.map.span(at, at + 1) };
+        self.errors.push(err.clone());
+        self.events.push_back(Html5Event::Error(err));
+    }
+
+    fn emit_char(&mut self, c: char) {
+        if self.pending_chars.is_empty() {
+            self.pending_start = self.construct_start;
+        }
+        self.pending_chars.push(c);
+        self.pending_end = self.pos.max(self.pending_start);
+    }
+
+    fn emit_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.emit_char(c);
+        }
+    }
+
+    fn flush_chars(&mut self) {
+        if self.pending_chars.is_empty() {
+            return;
+        }
+        let text = std::mem::take(&mut self.pending_chars);
+        let span = self.map.span(self.pending_start, self.pending_end);
+        self.events.push_back(Html5Event::Token(SpannedHtml5Token { token: Html5Token::Character { text }, span }));
+    }
+
+    fn emit_token(&mut self, token: Html5Token) {
+        self.flush_chars();
+        let span = self.map.span(self.token_start, self.pos);
+        self.events.push_back(Html5Event::Token(SpannedHtml5Token { token, span }));
+    }
+
+    fn emit_eof(&mut self) {
+        self.flush_chars();
+        self.done = true;
+    }
+
+    // tags
+
+    fn new_tag(&mut self, is_end: bool) {
+        self.tag = Html5TagBuilder { is_end, ..Html5TagBuilder::default() };
+    }
+
+    fn start_attribute(&mut self) {
+        self.finish_attribute();
+        self.tag.in_attr = true;
+    }
+
+    // called once the attribute's name can't grow any more, a repeated name is dropped along with its value
+    fn check_attribute_name(&mut self) {
+        if self.tag.in_attr && self.tag.attributes.iter().any(|attr| attr.name == self.tag.attr_name) {
+            self.tag.attr_duplicate = true;
+            self.error("duplicate-attribute");
+        }
+    }
+
+    fn finish_attribute(&mut self) {
+        if !self.tag.in_attr {
+            return;
+        }
+        let name = std::mem::take(&mut self.tag.attr_name);
+        let value = std::mem::take(&mut self.tag.attr_value);
+        if !self.tag.attr_duplicate {
+            self.tag.attributes.push(HtmlAttribute { name, value });
+        }
+        self.tag.attr_duplicate = false;
+        self.tag.in_attr = false;
+    }
+
+    fn emit_tag(&mut self) {
+        self.finish_attribute();
+        let tag = std::mem::take(&mut self.tag);
+        if tag.is_end {
+            if !tag.attributes.is_empty() {
+                self.error("end-tag-with-attributes");
+            }
+            if tag.self_closing {
+                self.error("end-tag-with-trailing-solidus");
+            }
+            self.emit_token(Html5Token::EndTag { tag_name: tag.tag_name });
+            return;
+        }
+        self.last_start_tag = Some(tag.tag_name.clone());
+        self.emit_token(Html5Token::StartTag { tag_name: tag.tag_name, attributes: tag.attributes, self_closing: tag.self_closing });
+    }
+
+    fn is_appropriate_end_tag(&self) -> bool {
+        return self.tag.is_end && self.last_start_tag.as_deref() == Some(self.tag.tag_name.as_str());
+    }
+
+    fn emit_comment(&mut self) {
+        let text = std::mem::take(&mut self.comment);
+        self.emit_token(Html5Token::Comment { text });
+    }
+
+    fn emit_doctype(&mut self) {
+        let doctype = std::mem::take(&mut self.doctype);
+        self.emit_token(Html5Token::Doctype { name: doctype.name, public_id: doctype.public_id, system_id: doctype.system_id, force_quirks: doctype.force_quirks });
+    }
+
+    // character references
+
+    fn char_ref_in_attribute(&self) -> bool {
+        return matches!(self.return_state, Html5TokenizerState::AttributeValueDoubleQuoted | Html5TokenizerState::AttributeValueSingleQuoted | Html5TokenizerState::AttributeValueUnquoted);
+    }
+
+    fn flush_char_ref(&mut self) {
+        let buffer = std::mem::take(&mut self.temp_buffer);
+        if self.char_ref_in_attribute() {
+            self.tag.attr_value.push_str(&buffer);
+        } else {
+            self.emit_str(&buffer);
+        }
+    }
+
+    // the longest name in the named reference table that the upcoming chars spell out
+    fn match_named_char_ref(&self) -> Option<(usize, &'static str)> {
+        let mut candidate = String::new();
+        let mut i = self.pos;
+        while let Some(c) = self.chars.get(i) {
+            if candidate.len() >= HTML_ENTITY_MAX_LEN || !(c.is_ascii_alphanumeric() || *c == ';') {
+                break;
+            }
+            candidate.push(*c);
+            i += 1;
+            if *c == ';' {
+                break;
+            }
+        }
+        let mut len = candidate.len();
+        while len > 0 {
+            if let Some(value) = html_named_entity(&candidate[..len]) {
+                return Some((len, value));
+            }
+            len -= 1;
+        }
+        return None;
+    }
+
+    fn step(&mut self) {
+        use Html5TokenizerState as S;
+        match self.state {
+            S::Data => {
+                self.construct_start = self.pos;
+                match self.consume() {
+                    Some('&') => {
+                        self.return_state = S::Data;
+                        self.state = S::CharacterReference;
+                    },
+                    Some('<') => {
+                        self.token_start = self.pos - 1;
+                        self.state = S::TagOpen;
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.emit_char('\0');
+                    },
+                    Some(c) => {
+                        self.emit_char(c);
+                    },
+                    None => {
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::RcData => {
+                self.construct_start = self.pos;
+                match self.consume() {
+                    Some('&') => {
+                        self.return_state = S::RcData;
+                        self.state = S::CharacterReference;
+                    },
+                    Some('<') => {
+                        self.token_start = self.pos - 1;
+                        self.state = S::RcDataLessThanSign;
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.emit_char('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        self.emit_char(c);
+                    },
+                    None => {
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::RawText | S::ScriptData | S::PlainText => {
+                self.construct_start = self.pos;
+                let less_than_state = match self.state {
+                    S::RawText => Some(S::RawTextLessThanSign),
+                    S::ScriptData => Some(S::ScriptDataLessThanSign),
+                    _ => None,
+                };
+                match (self.consume(), less_than_state) {
+                    (Some('<'), Some(less_than_state)) => {
+                        self.token_start = self.pos - 1;
+                        self.state = less_than_state;
+                    },
+                    (Some('\0'), _) => {
+                        self.error("unexpected-null-character");
+                        self.emit_char('\u{FFFD}');
+                    },
+                    (Some(c), _) => {
+                        self.emit_char(c);
+                    },
+                    (None, _) => {
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::TagOpen => {
+                match self.consume() {
+                    Some('!') => {
+                        self.state = S::MarkupDeclarationOpen;
+                    },
+                    Some('/') => {
+                        self.state = S::EndTagOpen;
+                    },
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.new_tag(false);
+                        self.reconsume();
+                        self.state = S::TagName;
+                    },
+                    Some('?') => {
+                        self.error("unexpected-question-mark-instead-of-tag-name");
+                        self.comment.clear();
+                        self.reconsume();
+                        self.state = S::BogusComment;
+                    },
+                    Some(_) => {
+                        self.error("invalid-first-character-of-tag-name");
+                        self.reconsume();
+                        self.emit_char('<');
+                        self.state = S::Data;
+                    },
+                    None => {
+                        self.error("eof-before-tag-name");
+                        self.emit_char('<');
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::EndTagOpen => {
+                match self.consume() {
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.new_tag(true);
+                        self.reconsume();
+                        self.state = S::TagName;
+                    },
+                    Some('>') => {
+                        self.error("missing-end-tag-name");
+                        self.state = S::Data;
+                    },
+                    Some(_) => {
+                        self.error("invalid-first-character-of-tag-name");
+                        self.comment.clear();
+                        self.reconsume();
+                        self.state = S::BogusComment;
+                    },
+                    None => {
+                        self.error("eof-before-tag-name");
+                        self.emit_str("</");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::TagName => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        self.state = S::BeforeAttributeName;
+                    },
+                    Some('/') => {
+                        self.state = S::SelfClosingStartTag;
+                    },
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_tag();
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.tag.tag_name.push('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        self.tag.tag_name.push(c.to_ascii_lowercase());
+                    },
+                    None => {
+                        self.error("eof-in-tag");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::RcDataLessThanSign | S::RawTextLessThanSign | S::ScriptDataLessThanSign => {
+                let (text_state, end_tag_open_state) = match self.state {
+                    S::RcDataLessThanSign => (S::RcData, S::RcDataEndTagOpen),
+                    S::RawTextLessThanSign => (S::RawText, S::RawTextEndTagOpen),
+                    _ => (S::ScriptData, S::ScriptDataEndTagOpen),
+                };
+                match self.consume() {
+                    Some('/') => {
+                        self.temp_buffer.clear();
+                        self.state = end_tag_open_state;
+                    },
+                    Some('!') if text_state == S::ScriptData => {
+                        self.emit_str("<!");
+                        self.state = S::ScriptDataEscapeStart;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.emit_char('<');
+                        self.state = text_state;
+                    },
+                    None => {
+                        self.emit_char('<');
+                        self.state = text_state;
+                    },
+                }
+            },
+            S::RcDataEndTagOpen | S::RawTextEndTagOpen | S::ScriptDataEndTagOpen | S::ScriptDataEscapedEndTagOpen => {
+                let (text_state, end_tag_name_state) = match self.state {
+                    S::RcDataEndTagOpen => (S::RcData, S::RcDataEndTagName),
+                    S::RawTextEndTagOpen => (S::RawText, S::RawTextEndTagName),
+                    S::ScriptDataEndTagOpen => (S::ScriptData, S::ScriptDataEndTagName),
+                    _ => (S::ScriptDataEscaped, S::ScriptDataEscapedEndTagName),
+                };
+                match self.consume() {
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.new_tag(true);
+                        self.reconsume();
+                        self.state = end_tag_name_state;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.emit_str("</");
+                        self.state = text_state;
+                    },
+                    None => {
+                        self.emit_str("</");
+                        self.state = text_state;
+                    },
+                }
+            },
+            S::RcDataEndTagName | S::RawTextEndTagName | S::ScriptDataEndTagName | S::ScriptDataEscapedEndTagName => {
+                let text_state = match self.state {
+                    S::RcDataEndTagName => S::RcData,
+                    S::RawTextEndTagName => S::RawText,
+                    S::ScriptDataEndTagName => S::ScriptData,
+                    _ => S::ScriptDataEscaped,
+                };
+                let c = self.consume();
+                match c {
+                    Some('\t' | '\n' | '\x0C' | ' ') if self.is_appropriate_end_tag() => {
+                        self.state = S::BeforeAttributeName;
+                    },
+                    Some('/') if self.is_appropriate_end_tag() => {
+                        self.state = S::SelfClosingStartTag;
+                    },
+                    Some('>') if self.is_appropriate_end_tag() => {
+                        self.state = S::Data;
+                        self.emit_tag();
+                    },
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.tag.tag_name.push(c.to_ascii_lowercase());
+                        self.temp_buffer.push(c);
+                    },
+                    _ => {
+                        if c.is_some() {
+                            self.reconsume();
+                        }
+                        let buffer = std::mem::take(&mut self.temp_buffer);
+                        self.emit_str("</");
+                        self.emit_str(&buffer);
+                        self.state = text_state;
+                    },
+                }
+            },
+            S::ScriptDataEscapeStart | S::ScriptDataEscapeStartDash => {
+                let next = if self.state == S::ScriptDataEscapeStart { S::ScriptDataEscapeStartDash } else { S::ScriptDataEscapedDashDash };
+                match self.consume() {
+                    Some('-') => {
+                        self.emit_char('-');
+                        self.state = next;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = S::ScriptData;
+                    },
+                    None => {
+                        self.state = S::ScriptData;
+                    },
+                }
+            },
+            S::ScriptDataEscaped | S::ScriptDataEscapedDash | S::ScriptDataEscapedDashDash => {
+                self.construct_start = self.pos;
+                let state = self.state;
+                match self.consume() {
+                    Some('-') => {
+                        self.emit_char('-');
+                        self.state = match state {
+                            S::ScriptDataEscaped => S::ScriptDataEscapedDash,
+                            _ => S::ScriptDataEscapedDashDash,
+                        };
+                    },
+                    Some('<') => {
+                        self.token_start = self.pos - 1;
+                        self.state = S::ScriptDataEscapedLessThanSign;
+                    },
+                    Some('>') if state == S::ScriptDataEscapedDashDash => {
+                        self.emit_char('>');
+                        self.state = S::ScriptData;
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.emit_char('\u{FFFD}');
+                        self.state = S::ScriptDataEscaped;
+                    },
+                    Some(c) => {
+                        self.emit_char(c);
+                        self.state = S::ScriptDataEscaped;
+                    },
+                    None => {
+                        self.error("eof-in-script-html-comment-like-text");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::ScriptDataEscapedLessThanSign => {
+                match self.consume() {
+                    Some('/') => {
+                        self.temp_buffer.clear();
+                        self.state = S::ScriptDataEscapedEndTagOpen;
+                    },
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.temp_buffer.clear();
+                        self.emit_char('<');
+                        self.reconsume();
+                        self.state = S::ScriptDataDoubleEscapeStart;
+                    },
+                    Some(_) => {
+                        self.emit_char('<');
+                        self.reconsume();
+                        self.state = S::ScriptDataEscaped;
+                    },
+                    None => {
+                        self.emit_char('<');
+                        self.state = S::ScriptDataEscaped;
+                    },
+                }
+            },
+            S::ScriptDataDoubleEscapeStart | S::ScriptDataDoubleEscapeEnd => {
+                let (matched, otherwise) = if self.state == S::ScriptDataDoubleEscapeStart {
+                    (S::ScriptDataDoubleEscaped, S::ScriptDataEscaped)
+                } else {
+                    (S::ScriptDataEscaped, S::ScriptDataDoubleEscaped)
+                };
+                match self.consume() {
+                    Some(c @ ('\t' | '\n' | '\x0C' | ' ' | '/' | '>')) => {
+                        self.state = if self.temp_buffer == "script" { matched } else { otherwise };
+                        self.emit_char(c);
+                    },
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.temp_buffer.push(c.to_ascii_lowercase());
+                        self.emit_char(c);
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = otherwise;
+                    },
+                    None => {
+                        self.state = otherwise;
+                    },
+                }
+            },
+            S::ScriptDataDoubleEscaped | S::ScriptDataDoubleEscapedDash | S::ScriptDataDoubleEscapedDashDash => {
+                self.construct_start = self.pos;
+                let state = self.state;
+                match self.consume() {
+                    Some('-') => {
+                        self.emit_char('-');
+                        self.state = match state {
+                            S::ScriptDataDoubleEscaped => S::ScriptDataDoubleEscapedDash,
+                            _ => S::ScriptDataDoubleEscapedDashDash,
+                        };
+                    },
+                    Some('<') => {
+                        self.emit_char('<');
+                        self.state = S::ScriptDataDoubleEscapedLessThanSign;
+                    },
+                    Some('>') if state == S::ScriptDataDoubleEscapedDashDash => {
+                        self.emit_char('>');
+                        self.state = S::ScriptData;
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.emit_char('\u{FFFD}');
+                        self.state = S::ScriptDataDoubleEscaped;
+                    },
+                    Some(c) => {
+                        self.emit_char(c);
+                        self.state = S::ScriptDataDoubleEscaped;
+                    },
+                    None => {
+                        self.error("eof-in-script-html-comment-like-text");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::ScriptDataDoubleEscapedLessThanSign => {
+                match self.consume() {
+                    Some('/') => {
+                        self.temp_buffer.clear();
+                        self.emit_char('/');
+                        self.state = S::ScriptDataDoubleEscapeEnd;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = S::ScriptDataDoubleEscaped;
+                    },
+                    None => {
+                        self.state = S::ScriptDataDoubleEscaped;
+                    },
+                }
+            },
+            S::BeforeAttributeName => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {},
+                    Some('/' | '>') | None => {
+                        self.reconsume_or_eof();
+                        self.state = S::AfterAttributeName;
+                    },
+                    Some('=') => {
+                        self.error("unexpected-equals-sign-before-attribute-name");
+                        self.start_attribute();
+                        self.tag.attr_name.push('=');
+                        self.state = S::AttributeName;
+                    },
+                    Some(_) => {
+                        self.start_attribute();
+                        self.reconsume();
+                        self.state = S::AttributeName;
+                    },
+                }
+            },
+            S::AttributeName => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ' | '/' | '>') | None => {
+                        self.reconsume_or_eof();
+                        self.check_attribute_name();
+                        self.state = S::AfterAttributeName;
+                    },
+                    Some('=') => {
+                        self.check_attribute_name();
+                        self.state = S::BeforeAttributeValue;
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.tag.attr_name.push('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        if matches!(c, '"' | '\'' | '<') {
+                            self.error("unexpected-character-in-attribute-name");
+                        }
+                        self.tag.attr_name.push(c.to_ascii_lowercase());
+                    },
+                }
+            },
+            S::AfterAttributeName => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {},
+                    Some('/') => {
+                        self.state = S::SelfClosingStartTag;
+                    },
+                    Some('=') => {
+                        self.state = S::BeforeAttributeValue;
+                    },
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_tag();
+                    },
+                    Some(_) => {
+                        self.start_attribute();
+                        self.reconsume();
+                        self.state = S::AttributeName;
+                    },
+                    None => {
+                        self.error("eof-in-tag");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::BeforeAttributeValue => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {},
+                    Some('"') => {
+                        self.state = S::AttributeValueDoubleQuoted;
+                    },
+                    Some('\'') => {
+                        self.state = S::AttributeValueSingleQuoted;
+                    },
+                    Some('>') => {
+                        self.error("missing-attribute-value");
+                        self.state = S::Data;
+                        self.emit_tag();
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = S::AttributeValueUnquoted;
+                    },
+                    None => {
+                        self.state = S::AttributeValueUnquoted;
+                    },
+                }
+            },
+            S::AttributeValueDoubleQuoted | S::AttributeValueSingleQuoted => {
+                let quote = if self.state == S::AttributeValueDoubleQuoted { '"' } else { '\'' };
+                match self.consume() {
+                    Some(c) if c == quote => {
+                        self.state = S::AfterAttributeValueQuoted;
+                    },
+                    Some('&') => {
+                        self.return_state = self.state;
+                        self.state = S::CharacterReference;
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.tag.attr_value.push('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        self.tag.attr_value.push(c);
+                    },
+                    None => {
+                        self.error("eof-in-tag");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::AttributeValueUnquoted => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        self.state = S::BeforeAttributeName;
+                    },
+                    Some('&') => {
+                        self.return_state = S::AttributeValueUnquoted;
+                        self.state = S::CharacterReference;
+                    },
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_tag();
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.tag.attr_value.push('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        if matches!(c, '"' | '\'' | '<' | '=' | '`') {
+                            self.error("unexpected-character-in-unquoted-attribute-value");
+                        }
+                        self.tag.attr_value.push(c);
+                    },
+                    None => {
+                        self.error("eof-in-tag");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::AfterAttributeValueQuoted => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        self.state = S::BeforeAttributeName;
+                    },
+                    Some('/') => {
+                        self.state = S::SelfClosingStartTag;
+                    },
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_tag();
+                    },
+                    Some(_) => {
+                        self.error("missing-whitespace-between-attributes");
+                        self.reconsume();
+                        self.state = S::BeforeAttributeName;
+                    },
+                    None => {
+                        self.error("eof-in-tag");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::SelfClosingStartTag => {
+                match self.consume() {
+                    Some('>') => {
+                        self.tag.self_closing = true;
+                        self.state = S::Data;
+                        self.emit_tag();
+                    },
+                    Some(_) => {
+                        self.error("unexpected-solidus-in-tag");
+                        self.reconsume();
+                        self.state = S::BeforeAttributeName;
+                    },
+                    None => {
+                        self.error("eof-in-tag");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::BogusComment => {
+                match self.consume() {
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_comment();
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.comment.push('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        self.comment.push(c);
+                    },
+                    None => {
+                        self.emit_comment();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::MarkupDeclarationOpen => {
+                if self.next_chars_are("--", false) {
+                    self.skip(2);
+                    self.comment.clear();
+                    self.state = S::CommentStart;
+                } else if self.next_chars_are("DOCTYPE", true) {
+                    self.skip(7);
+                    self.state = S::Doctype;
+                } else if self.next_chars_are("[CDATA[", false) {
+                    self.skip(7);
+                    if self.allow_cdata {
+                        self.construct_start = self.pos;
+                        self.state = S::CdataSection;
+                    } else {
+                        self.error("cdata-in-html-content");
+                        self.comment = "[CDATA[".to_string();
+                        self.state = S::BogusComment;
+                    }
+                } else {
+                    self.error_at("incorrectly-opened-comment", self.pos);
+                    self.comment.clear();
+                    self.state = S::BogusComment;
+                }
+            },
+            S::CommentStart => {
+                match self.consume() {
+                    Some('-') => {
+                        self.state = S::CommentStartDash;
+                    },
+                    Some('>') => {
+                        self.error("abrupt-closing-of-empty-comment");
+                        self.state = S::Data;
+                        self.emit_comment();
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = S::Comment;
+                    },
+                    None => {
+                        self.state = S::Comment;
+                    },
+                }
+            },
+            S::CommentStartDash => {
+                match self.consume() {
+                    Some('-') => {
+                        self.state = S::CommentEnd;
+                    },
+                    Some('>') => {
+                        self.error("abrupt-closing-of-empty-comment");
+                        self.state = S::Data;
+                        self.emit_comment();
+                    },
+                    Some(_) => {
+                        self.comment.push('-');
+                        self.reconsume();
+                        self.state = S::Comment;
+                    },
+                    None => {
+                        self.error("eof-in-comment");
+                        self.emit_comment();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::Comment => {
+                match self.consume() {
+                    Some('<') => {
+                        self.comment.push('<');
+                        self.state = S::CommentLessThanSign;
+                    },
+                    Some('-') => {
+                        self.state = S::CommentEndDash;
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.comment.push('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        self.comment.push(c);
+                    },
+                    None => {
+                        self.error("eof-in-comment");
+                        self.emit_comment();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::CommentLessThanSign => {
+                match self.consume() {
+                    Some('!') => {
+                        self.comment.push('!');
+                        self.state = S::CommentLessThanSignBang;
+                    },
+                    Some('<') => {
+                        self.comment.push('<');
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = S::Comment;
+                    },
+                    None => {
+                        self.state = S::Comment;
+                    },
+                }
+            },
+            S::CommentLessThanSignBang | S::CommentLessThanSignBangDash => {
+                let next = if self.state == S::CommentLessThanSignBang { S::CommentLessThanSignBangDash } else { S::CommentLessThanSignBangDashDash };
+                let otherwise = if self.state == S::CommentLessThanSignBang { S::Comment } else { S::CommentEndDash };
+                match self.consume() {
+                    Some('-') => {
+                        self.state = next;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = otherwise;
+                    },
+                    None => {
+                        self.state = otherwise;
+                    },
+                }
+            },
+            S::CommentLessThanSignBangDashDash => {
+                match self.consume() {
+                    Some('>') => {
+                        self.reconsume();
+                        self.state = S::CommentEnd;
+                    },
+                    Some(_) => {
+                        self.error("nested-comment");
+                        self.reconsume();
+                        self.state = S::CommentEnd;
+                    },
+                    None => {
+                        self.state = S::CommentEnd;
+                    },
+                }
+            },
+            S::CommentEndDash => {
+                match self.consume() {
+                    Some('-') => {
+                        self.state = S::CommentEnd;
+                    },
+                    Some(_) => {
+                        self.comment.push('-');
+                        self.reconsume();
+                        self.state = S::Comment;
+                    },
+                    None => {
+                        self.error("eof-in-comment");
+                        self.emit_comment();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::CommentEnd => {
+                match self.consume() {
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_comment();
+                    },
+                    Some('!') => {
+                        self.state = S::CommentEndBang;
+                    },
+                    Some('-') => {
+                        self.comment.push('-');
+                    },
+                    Some(_) => {
+                        self.comment.push_str("--");
+                        self.reconsume();
+                        self.state = S::Comment;
+                    },
+                    None => {
+                        self.error("eof-in-comment");
+                        self.emit_comment();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::CommentEndBang => {
+                match self.consume() {
+                    Some('-') => {
+                        self.comment.push_str("--!");
+                        self.state = S::CommentEndDash;
+                    },
+                    Some('>') => {
+                        self.error("incorrectly-closed-comment");
+                        self.state = S::Data;
+                        self.emit_comment();
+                    },
+                    Some(_) => {
+                        self.comment.push_str("--!");
+                        self.reconsume();
+                        self.state = S::Comment;
+                    },
+                    None => {
+                        self.error("eof-in-comment");
+                        self.emit_comment();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::Doctype => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        self.state = S::BeforeDoctypeName;
+                    },
+                    Some('>') => {
+                        self.reconsume();
+                        self.state = S::BeforeDoctypeName;
+                    },
+                    Some(_) => {
+                        self.error("missing-whitespace-before-doctype-name");
+                        self.reconsume();
+                        self.state = S::BeforeDoctypeName;
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype = Html5DoctypeBuilder { force_quirks: true, ..Html5DoctypeBuilder::default() };
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::BeforeDoctypeName => {
+                self.doctype = Html5DoctypeBuilder::default();
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {},
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.doctype.name = Some('\u{FFFD}'.to_string());
+                        self.state = S::DoctypeName;
+                    },
+                    Some('>') => {
+                        self.error("missing-doctype-name");
+                        self.doctype.force_quirks = true;
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some(c) => {
+                        self.doctype.name = Some(c.to_ascii_lowercase().to_string());
+                        self.state = S::DoctypeName;
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::DoctypeName => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        self.state = S::AfterDoctypeName;
+                    },
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.doctype.name.get_or_insert_with(String::new).push('\u{FFFD}');
+                    },
+                    Some(c) => {
+                        self.doctype.name.get_or_insert_with(String::new).push(c.to_ascii_lowercase());
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::AfterDoctypeName => {
+                if self.pos < self.chars.len() && !matches!(self.chars[self.pos], '\t' | '\n' | '\x0C' | ' ' | '>') {
+                    if self.next_chars_are("PUBLIC", true) {
+                        self.skip(6);
+                        self.state = S::AfterDoctypePublicKeyword;
+                        return;
+                    }
+                    if self.next_chars_are("SYSTEM", true) {
+                        self.skip(6);
+                        self.state = S::AfterDoctypeSystemKeyword;
+                        return;
+                    }
+                }
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {},
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some(_) => {
+                        self.error("invalid-character-sequence-after-doctype-name");
+                        self.doctype.force_quirks = true;
+                        self.reconsume();
+                        self.state = S::BogusDoctype;
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::AfterDoctypePublicKeyword | S::AfterDoctypeSystemKeyword => {
+                let public = self.state == S::AfterDoctypePublicKeyword;
+                let before = if public { S::BeforeDoctypePublicIdentifier } else { S::BeforeDoctypeSystemIdentifier };
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        self.state = before;
+                    },
+                    Some(q @ ('"' | '\'')) => {
+                        self.error(if public { "missing-whitespace-after-doctype-public-keyword" } else { "missing-whitespace-after-doctype-system-keyword" });
+                        self.start_doctype_identifier(public, q);
+                    },
+                    Some('>') => {
+                        self.error(if public { "missing-doctype-public-identifier" } else { "missing-doctype-system-identifier" });
+                        self.doctype.force_quirks = true;
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some(_) => {
+                        self.error(if public { "missing-quote-before-doctype-public-identifier" } else { "missing-quote-before-doctype-system-identifier" });
+                        self.doctype.force_quirks = true;
+                        self.reconsume();
+                        self.state = S::BogusDoctype;
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::BeforeDoctypePublicIdentifier | S::BeforeDoctypeSystemIdentifier => {
+                let public = self.state == S::BeforeDoctypePublicIdentifier;
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {},
+                    Some(q @ ('"' | '\'')) => {
+                        self.start_doctype_identifier(public, q);
+                    },
+                    Some('>') => {
+                        self.error(if public { "missing-doctype-public-identifier" } else { "missing-doctype-system-identifier" });
+                        self.doctype.force_quirks = true;
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some(_) => {
+                        self.error(if public { "missing-quote-before-doctype-public-identifier" } else { "missing-quote-before-doctype-system-identifier" });
+                        self.doctype.force_quirks = true;
+                        self.reconsume();
+                        self.state = S::BogusDoctype;
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::DoctypePublicIdentifierDoubleQuoted | S::DoctypePublicIdentifierSingleQuoted | S::DoctypeSystemIdentifierDoubleQuoted | S::DoctypeSystemIdentifierSingleQuoted => {
+                let public = matches!(self.state, S::DoctypePublicIdentifierDoubleQuoted | S::DoctypePublicIdentifierSingleQuoted);
+                let quote = if matches!(self.state, S::DoctypePublicIdentifierDoubleQuoted | S::DoctypeSystemIdentifierDoubleQuoted) { '"' } else { '\'' };
+                match self.consume() {
+                    Some(c) if c == quote => {
+                        self.state = if public { S::AfterDoctypePublicIdentifier } else { S::AfterDoctypeSystemIdentifier };
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                        self.push_doctype_identifier(public, '\u{FFFD}');
+                    },
+                    Some('>') => {
+                        self.error(if public { "abrupt-doctype-public-identifier" } else { "abrupt-doctype-system-identifier" });
+                        self.doctype.force_quirks = true;
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some(c) => {
+                        self.push_doctype_identifier(public, c);
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::AfterDoctypePublicIdentifier | S::BetweenDoctypePublicAndSystemIdentifiers => {
+                let after = self.state == S::AfterDoctypePublicIdentifier;
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        self.state = S::BetweenDoctypePublicAndSystemIdentifiers;
+                    },
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some(q @ ('"' | '\'')) => {
+                        if after {
+                            self.error("missing-whitespace-between-doctype-public-and-system-identifiers");
+                        }
+                        self.start_doctype_identifier(false, q);
+                    },
+                    Some(_) => {
+                        self.error("missing-quote-before-doctype-system-identifier");
+                        self.doctype.force_quirks = true;
+                        self.reconsume();
+                        self.state = S::BogusDoctype;
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::AfterDoctypeSystemIdentifier => {
+                match self.consume() {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {},
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some(_) => {
+                        self.error("unexpected-character-after-doctype-system-identifier");
+                        self.reconsume();
+                        self.state = S::BogusDoctype;
+                    },
+                    None => {
+                        self.error("eof-in-doctype");
+                        self.doctype.force_quirks = true;
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::BogusDoctype => {
+                match self.consume() {
+                    Some('>') => {
+                        self.state = S::Data;
+                        self.emit_doctype();
+                    },
+                    Some('\0') => {
+                        self.error("unexpected-null-character");
+                    },
+                    Some(_) => {},
+                    None => {
+                        self.emit_doctype();
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::CdataSection => {
+                match self.consume() {
+                    Some(']') => {
+                        self.state = S::CdataSectionBracket;
+                    },
+                    Some(c) => {
+                        self.emit_char(c);
+                    },
+                    None => {
+                        self.error("eof-in-cdata");
+                        self.emit_eof();
+                    },
+                }
+            },
+            S::CdataSectionBracket => {
+                match self.consume() {
+                    Some(']') => {
+                        self.state = S::CdataSectionEnd;
+                    },
+                    Some(_) => {
+                        self.emit_char(']');
+                        self.reconsume();
+                        self.state = S::CdataSection;
+                    },
+                    None => {
+                        self.emit_char(']');
+                        self.state = S::CdataSection;
+                    },
+                }
+            },
+            S::CdataSectionEnd => {
+                match self.consume() {
+                    Some(']') => {
+                        self.emit_char(']');
+                    },
+                    Some('>') => {
+                        self.state = S::Data;
+                    },
+                    Some(_) => {
+                        self.emit_str("]]");
+                        self.reconsume();
+                        self.state = S::CdataSection;
+                    },
+                    None => {
+                        self.emit_str("]]");
+                        self.state = S::CdataSection;
+                    },
+                }
+            },
+            S::CharacterReference => {
+                self.temp_buffer.clear();
+                self.temp_buffer.push('&');
+                match self.consume() {
+                    Some(c) if c.is_ascii_alphanumeric() => {
+                        self.reconsume();
+                        self.state = S::NamedCharacterReference;
+                    },
+                    Some('#') => {
+                        self.temp_buffer.push('#');
+                        self.state = S::NumericCharacterReference;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.flush_char_ref();
+                        self.state = self.return_state;
+                    },
+                    None => {
+                        self.flush_char_ref();
+                        self.state = self.return_state;
+                    },
+                }
+            },
+            S::NamedCharacterReference => {
+                match self.match_named_char_ref() {
+                    Some((len, value)) => {
+                        let name: String = self.chars[self.pos..self.pos + len].iter().collect();
+                        self.skip(len);
+                        self.temp_buffer.push_str(&name);
+                        let next = self.chars.get(self.pos).copied();
+                        let historical = self.char_ref_in_attribute() && !name.ends_with(';') && next.is_some_and(|c| c == '=' || c.is_ascii_alphanumeric());
+                        if !historical {
+                            if !name.ends_with(';') {
+                                self.error("missing-semicolon-after-character-reference");
+                            }
+                            self.temp_buffer = value.to_string();
+                        }
+                        self.flush_char_ref();
+                        self.state = self.return_state;
+                    },
+                    None => {
+                        self.flush_char_ref();
+                        self.state = S::AmbiguousAmpersand;
+                    },
+                }
+            },
+            S::AmbiguousAmpersand => {
+                match self.consume() {
+                    Some(c) if c.is_ascii_alphanumeric() => {
+                        if self.char_ref_in_attribute() {
+                            self.tag.attr_value.push(c);
+                        } else {
+                            self.emit_char(c);
+                        }
+                    },
+                    Some(';') => {
+                        self.error("unknown-named-character-reference");
+                        self.reconsume();
+                        self.state = self.return_state;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = self.return_state;
+                    },
+                    None => {
+                        self.state = self.return_state;
+                    },
+                }
+            },
+            S::NumericCharacterReference => {
+                self.char_ref_code = 0;
+                match self.consume() {
+                    Some(c @ ('x' | 'X')) => {
+                        self.temp_buffer.push(c);
+                        self.state = S::HexadecimalCharacterReferenceStart;
+                    },
+                    Some(_) => {
+                        self.reconsume();
+                        self.state = S::DecimalCharacterReferenceStart;
+                    },
+                    None => {
+                        self.state = S::DecimalCharacterReferenceStart;
+                    },
+                }
+            },
+            S::HexadecimalCharacterReferenceStart | S::DecimalCharacterReferenceStart => {
+                let (radix, next) = if self.state == S::HexadecimalCharacterReferenceStart {
+                    (16, S::HexadecimalCharacterReference)
+                } else {
+                    (10, S::DecimalCharacterReference)
+                };
+                let c = self.consume();
+                match c {
+                    Some(c) if c.is_digit(radix) => {
+                        self.reconsume();
+                        self.state = next;
+                    },
+                    _ => {
+                        if c.is_some() {
+                            self.reconsume();
+                        }
+                        self.error_at("absence-of-digits-in-numeric-character-reference", self.pos);
+                        self.flush_char_ref();
+                        self.state = self.return_state;
+                    },
+                }
+            },
+            S::HexadecimalCharacterReference | S::DecimalCharacterReference => {
+                let radix = if self.state == S::HexadecimalCharacterReference { 16 } else { 10 };
+                let c = self.consume();
+                match c {
+                    Some(c) if c.is_digit(radix) => {
+                        let digit = c.to_digit(radix).unwrap_or(0);
+                        // anything past the unicode range is an error either way, so the code stops growing there
+                        self.char_ref_code = self.char_ref_code.saturating_mul(radix).saturating_add(digit).min(0x110000);
+                    },
+                    Some(';') => {
+                        self.state = S::NumericCharacterReferenceEnd;
+                    },
+                    _ => {
+                        if c.is_some() {
+                            self.reconsume();
+                        }
+                        self.error_at("missing-semicolon-after-character-reference", self.pos);
+                        self.state = S::NumericCharacterReferenceEnd;
+                    },
+                }
+            },
+            S::NumericCharacterReferenceEnd => {
+                let code = self.char_ref_code;
+                let at = self.pos;
+                let c = if code == 0 {
+                    self.error_at("null-character-reference", at);
+                    '\u{FFFD}'
+                } else if code > 0x10FFFF {
+                    self.error_at("character-reference-outside-unicode-range", at);
+                    '\u{FFFD}'
+                } else if (0xD800..=0xDFFF).contains(&code) {
+                    self.error_at("surrogate-character-reference", at);
+                    '\u{FFFD}'
+                } else {
+                    if (0xFDD0..=0xFDEF).contains(&code) || (code & 0xFFFE) == 0xFFFE {
+                        self.error_at("noncharacter-character-reference", at);
+                    }
+                    let c0_control = code < 0x20 && !matches!(code, 0x09 | 0x0A | 0x0C | 0x20);
+                    if code == 0x0D || c0_control || (0x7F..=0x9F).contains(&code) {
+                        self.error_at("control-character-reference", at);
+                    }
+                    html_numeric_char(code)
+                };
+                self.temp_buffer = c.to_string();
+                self.flush_char_ref();
+                self.state = self.return_state;
+            },
+        }
+    }
+
+    fn start_doctype_identifier(&mut self, public: bool, quote: char) {
+        use Html5TokenizerState as S;
+        if public {
+            self.doctype.public_id = Some(String::new());
+            self.state = if quote == '"' { S::DoctypePublicIdentifierDoubleQuoted } else { S::DoctypePublicIdentifierSingleQuoted };
+        } else {
+            self.doctype.system_id = Some(String::new());
+            self.state = if quote == '"' { S::DoctypeSystemIdentifierDoubleQuoted } else { S::DoctypeSystemIdentifierSingleQuoted };
+        }
+    }
+
+    fn push_doctype_identifier(&mut self, public: bool, c: char) {
+        let id = if public { &mut self.doctype.public_id } else { &mut self.doctype.system_id };
+        id.get_or_insert_with(String::new).push(c);
+    }
+
+    // steps back over the char just consumed, unless the input had already run out
+    fn reconsume_or_eof(&mut self) {
+        if self.last_width > 0 {
+            self.reconsume();
+        }
+    }
+
+}
+
+impl Iterator for Html5Tokenizer {
+    type Item = SpannedHtml5Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // the errors were already recorded in self.errors when they were emitted
+            match self.next_event()? {
+                Html5Event::Token(tok) => {
+                    return Some(tok);
+                },
+                Html5Event::Error(_) => {},
+            }
+        }
+    }
+}
@@ -1,133 +1,310 @@
-use rlex::{self, Rlex, DefaultState, DefaultToken};
+use crate::error::RipError;
+use std::borrow::Cow;
 
-pub fn validate_token_html_backslash_count(tag_str: &str) -> Result<(), String> {
-    let mut r: Rlex<DefaultState, DefaultToken> = Rlex::new(tag_str, DefaultState::Default);
+// elements that can never have children, so they never get a closing tag
+pub const HTML_VOID_ELEMENTS: [&str; 13] = ["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr"];
+
+pub fn is_html_void_element(tag_name: &str) -> bool {
+    return HTML_VOID_ELEMENTS.iter().any(|void| void.eq_ignore_ascii_case(tag_name));
+}
+
+// elements whose contents are taken as-is instead of being parsed as html
+pub const HTML_PRE_LIKE_ELEMENTS: [&str; 5] = ["script", "style", "textarea", "xmp", "pre"];
+
+pub fn is_html_pre_like_element(tag_name: &str) -> bool {
+    return HTML_PRE_LIKE_ELEMENTS.iter().any(|pre_like| pre_like.eq_ignore_ascii_case(tag_name));
+}
+
+// elements whose text is never decoded, so it is written back out without escaping. textarea and pre
+// are prelike but their text holds decoded references like any other element's
+pub const HTML_RAW_TEXT_ELEMENTS: [&str; 7] = ["script", "style", "xmp", "iframe", "noembed", "noframes", "plaintext"];
+
+pub fn is_html_raw_text_element(tag_name: &str) -> bool {
+    return HTML_RAW_TEXT_ELEMENTS.iter().any(|raw_text| raw_text.eq_ignore_ascii_case(tag_name));
+}
+
+// elements whose contents are read as text with references decoded, any markup in them is just text
+pub const HTML_RCDATA_ELEMENTS: [&str; 2] = ["textarea", "title"];
+
+pub fn is_html_rcdata_element(tag_name: &str) -> bool {
+    return HTML_RCDATA_ELEMENTS.iter().any(|rcdata| rcdata.eq_ignore_ascii_case(tag_name));
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HtmlAttribute {
+    pub name: String,
+    pub value: String,
+}
+
+impl HtmlAttribute {
+    pub fn new(name: &str, value: &str) -> HtmlAttribute {
+        return HtmlAttribute { name: name.to_string(), value: value.to_string() };
+    }
+}
+
+// counts the '/' chars outside of attribute values, so the ones in a value like href=/a/b or
+// href="/a/b" don't count. an unquoted value runs from its '=' to the next whitespace or '>'
+pub fn validate_token_html_backslash_count(tag_str: &str) -> Result<(), RipError> {
+    let bytes = tag_str.as_bytes();
     let mut count = 0;
-    while !r.at_end() {
-        if r.char() == '/' {
-            if !r.is_in_quote() {
-                count += 1;
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if let Some(q) = quote {
+            if b == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        if b == b'"' || b == b'\'' {
+            quote = Some(b);
+        } else if b == b'/' {
+            count += 1;
+        } else if b == b'=' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < bytes.len() && bytes[i] != b'"' && bytes[i] != b'\'' {
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
             }
+            continue;
         }
-        r.next();
+        i += 1;
     }
     if count > 1 {
-        return Err(format!("ERR_HTML_FORMAT: the following tag has more than 1 '/' character outside of quotes: {}", tag_str));
+        return Err(RipError::TooManySlashes { tag: tag_str.to_string(), span: None });
     }
     return Ok(());
 }
 
-pub fn validate_token_html_quotes(tag_str: &str) -> Result<(), String> {
-    let mut r: Rlex<DefaultState, DefaultToken> = Rlex::new(tag_str, DefaultState::Default);
-    r.trace_on();
-    let mut collect: Vec<String> = vec![];
-    while !r.at_end() {
-        let start_char = r.char();
-        if start_char == '\'' {
-            let start = r.pos();
-            r.next();
-            r.next_until('\'');
-            let pos = r.pos();
-            let str = r.str_from_rng(start, pos);
-            collect.push(str.to_string());
-        }
-        if start_char == '"' {
-            let start = r.pos();
-            r.next();
-            r.next_until('"');
-            let pos = r.pos();
-            let str = r.str_from_rng(start, pos);
-            collect.push(str.to_string());
-        }
-        r.next();
-    }
-    if collect.contains(&"''".to_string()) || collect.contains(&"\"\"".to_string()) {
-        let mut single_count = 0;
-        let mut double_count = 0;
-        for s in &collect {
-            if s == "''" {
-                single_count+=1;
-            }
-            if s == "\"\"" {
-                double_count+=1;
-            }
+// every quote has to be closed by the same kind of quote, and a quoted value can't start right where the
+// last one ended, like class="a""". empty values like alt="" are fine
+pub fn validate_token_html_quotes(tag_str: &str) -> Result<(), RipError> {
+    // quotes are ascii, so walking bytes never lands inside a multi-byte char that matters
+    let bytes = tag_str.as_bytes();
+    // where the last quoted value ended, just past its closing quote
+    let mut value_end: Option<usize> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let quote = bytes[i];
+        if quote != b'"' && quote != b'\'' {
+            i += 1;
+            continue;
         }
-        if double_count > 1 || single_count > 1 {
-            return Err(format!("ERR_HTML_FORMAT: quotation mark error -> {}", tag_str));
+        if value_end == Some(i) {
+            return Err(RipError::UnbalancedQuotes { tag: tag_str.to_string(), span: None });
         }
-    }
-    let mut src = r.src().to_string();
-    for s in collect {
-        // let squeezed = s.replace(" ", "");
-        // if squeezed == "\"\"" || squeezed == "''" {
-        //     return Err(format!("ERR_HTML_FORMAT: the following tag makes poor use of quotes has extract quotes: {}", tag_str));
-        // }
-        let mut chars = s.chars();
-        let first_char = match chars.nth(0) {
-            Some(c) => {c},
-            None => {
-                continue;
-            }
-        };
-        let last_char = match chars.last() {
-            Some(c) => {c},
+        let close = match bytes[i + 1..].iter().position(|b| *b == quote) {
+            Some(offset) => { i + 1 + offset },
             None => {
-                continue;
+                return Err(RipError::UnbalancedQuotes { tag: tag_str.to_string(), span: None });
             }
         };
-        if first_char != last_char {
-            return Err(format!("ERR_HTML_FORMAT: the following tag makes poor use of quotes and is missing a closing quote: {}", tag_str));
-        }
-        src = src.replace(&s, "");
-    }
-    if src.contains("'") || src.contains("\"") {
-        return Err(format!("ERR_HTML_FORMAT: the following tag makes poor use of quotes and is malformed: {}", tag_str));
+        value_end = Some(close + 1);
+        i = close + 1;
     }
     return Ok(());
 }
 
 
-pub fn html_tag_name(tag: &str) -> Result<String, String> {
+pub fn html_tag_name(tag: &str) -> Result<String, RipError> {
+    return html_tag_name_borrowed(tag).map(|name| name.into_owned());
+}
+
+// the same as html_tag_name, but the name is a slice of tag unless it has to be lowercased or cleaned up
+pub fn html_tag_name_borrowed(tag: &str) -> Result<Cow<'_, str>, RipError> {
     // trimming whitespace and ensuring we have an input longer than 3 chars
     let tag = tag.trim();
     if tag.len() < 3 {
-        return Err(format!("{} is less than 3 chars, valid html tags must be 3 or more chars", tag))
+        return Err(RipError::TagTooShort { tag: tag.to_string(), span: None })
     }
     // checking the first and last characters to ensure they are '<' and '>'
-    let mut chars = tag.chars();
-    let first_char = chars.next().unwrap(); // cannot fail
-    let last_char = chars.last().unwrap(); // cannot fail
-    if first_char != '<' || last_char != '>' {
-        return Err(format!("{} does not start with a '<' and end with a '>' which is required for html tags", tag))
+    if !tag.starts_with('<') || !tag.ends_with('>') {
+        return Err(RipError::MissingAngleBrackets { tag: tag.to_string(), span: None })
     }
-    // removing the outer '<' and '>' and splitting by whitespace and getting our iterator
-    let cleaned = tag.replace('<', "").replace('>', "");
-    let parts = cleaned.split_whitespace();
-    let mut parts_iter = parts.into_iter();
-    // if the first part is a '/' then we are dealing with a closing tag
-    let first_part = match parts_iter.next() {
-        Some(part) => { part },
+    // dropping the outer '<' and '>', any stray ones inside have to be removed before splitting
+    let inner = &tag[1..tag.len()-1];
+    let name = if inner.contains(['<', '>']) {
+        let cleaned = inner.replace(['<', '>'], "");
+        html_tag_name_part(&cleaned).map(|name| Cow::Owned(name.into_owned()))
+    } else {
+        html_tag_name_part(inner)
+    };
+    let name = match name {
+        Some(name) => { name },
         None => {
-            return Err(format!("{} failed to split this by whitespace and access the first 'part'", tag))
+            return Err(RipError::MissingTagName { tag: tag.to_string(), span: None })
         },
     };
+    if name.chars().any(|c| c.is_uppercase()) {
+        return Ok(Cow::Owned(name.to_lowercase()));
+    }
+    return Ok(name);
+}
+
+// picks the name out of everything between the '<' and '>' of a tag
+fn html_tag_name_part(inner: &str) -> Option<Cow<'_, str>> {
+    let mut parts_iter = inner.split_whitespace();
+    // if the first part is a '/' then we are dealing with a closing tag
+    let first_part = parts_iter.next()?;
     // this will represent the 'part' of the whitespace split we are returning
-    let target_part: &str;
-    if first_part == "/" {
-        let second_part = match parts_iter.next() {
-            Some(part) => { part },
-            None => {
-                return Err(format!("{} failed to split this by whitespace and access the second 'part'", tag))
-            },
-        };
-        target_part = second_part;
+    let target_part: &str = if first_part == "/" {
+        parts_iter.next()?
     } else {
-        target_part = first_part;
-    }
+        first_part
+    };
     // trimming off the '/' from the target_part if needed
     if target_part.starts_with('/') || target_part.ends_with('/') {
-        let target_part = &target_part.replace('/', "");
-        return Ok(target_part.to_string().to_lowercase());
+        let trimmed = target_part.trim_matches('/');
+        if trimmed.contains('/') {
+            return Some(Cow::Owned(target_part.replace('/', "")));
+        }
+        return Some(Cow::Borrowed(trimmed));
+    }
+    return Some(Cow::Borrowed(target_part));
+}
+
+pub fn html_attributes(tag: &str) -> Result<Vec<HtmlAttribute>, RipError> {
+    validate_token_html_quotes(tag)?;
+    // validating the tag the same way html_tag_name does and grabbing the name so we can skip past it
+    let tag_name = html_tag_name(tag)?;
+    let tag = tag.trim();
+    let chars: Vec<char> = tag[1..tag.len()-1].chars().collect();
+    let mut i = 0;
+    // skipping the optional '/' and whitespace that may lead the tag name
+    while i < chars.len() && (chars[i].is_whitespace() || chars[i] == '/') {
+        i += 1;
+    }
+    i += tag_name.chars().count();
+    let mut attrs: Vec<HtmlAttribute> = vec![];
+    while i < chars.len() {
+        // whitespace and stray '/' characters separate attributes
+        if chars[i].is_whitespace() || chars[i] == '/' {
+            i += 1;
+            continue;
+        }
+        let name_start = i;
+        while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '=' && chars[i] != '/' {
+            i += 1;
+        }
+        let name: String = chars[name_start..i].iter().collect::<String>().to_lowercase();
+        // looking past any whitespace for an '=', if there isn't one we have a boolean attribute
+        let mut j = i;
+        while j < chars.len() && chars[j].is_whitespace() {
+            j += 1;
+        }
+        if j >= chars.len() || chars[j] != '=' {
+            attrs.push(HtmlAttribute { name, value: String::new() });
+            continue;
+        }
+        i = j + 1;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        if i >= chars.len() {
+            attrs.push(HtmlAttribute { name, value: String::new() });
+            break;
+        }
+        let quote = chars[i];
+        if quote == '"' || quote == '\'' {
+            i += 1;
+            let value_start = i;
+            while i < chars.len() && chars[i] != quote {
+                i += 1;
+            }
+            let value: String = chars[value_start..i].iter().collect();
+            attrs.push(HtmlAttribute { name, value });
+            // stepping off the closing quote
+            i += 1;
+            continue;
+        }
+        // unquoted values run until the next whitespace, a trailing '/' belongs to the tag and not the value
+        let value_start = i;
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        let mut value_end = i;
+        if value_end == chars.len() && value_end > value_start && chars[value_end-1] == '/' {
+            value_end -= 1;
+        }
+        let value: String = chars[value_start..value_end].iter().collect();
+        attrs.push(HtmlAttribute { name, value });
     }
-    return Ok(target_part.to_string().to_lowercase());
-}
\ No newline at end of file
+    return Ok(attrs);
+}
+
+
+pub fn is_html_doctype(tag: &str) -> bool {
+    let tag = tag.trim_start();
+    return tag.len() >= 9 && tag.is_char_boundary(9) && tag[..9].eq_ignore_ascii_case("<!doctype");
+}
+
+// pulls the name and the optional public and system identifiers out of a '<!DOCTYPE ...>' declaration
+pub fn html_doctype(tag: &str) -> Result<(String, Option<String>, Option<String>), RipError> {
+    validate_token_html_quotes(tag)?;
+    let trimmed = tag.trim();
+    if !is_html_doctype(trimmed) || !trimmed.ends_with('>') {
+        return Err(RipError::MissingAngleBrackets { tag: tag.to_string(), span: None });
+    }
+    let chars: Vec<char> = trimmed[9..trimmed.len()-1].chars().collect();
+    let mut i = 0;
+    let mut words: Vec<String> = vec![];
+    // splitting the declaration into bare words and quoted identifiers, quoted ones keep their quote char up front
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '"' || c == '\'' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            words.push(format!("{}{}", c, word));
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '"' && chars[i] != '\'' {
+            i += 1;
+        }
+        words.push(chars[start..i].iter().collect());
+    }
+    let mut words = words.into_iter();
+    let name = match words.next() {
+        Some(word) => { word.to_lowercase() },
+        None => { String::new() },
+    };
+    let mut public_id: Option<String> = None;
+    let mut system_id: Option<String> = None;
+    let quoted = |word: Option<String>| -> Option<String> {
+        match word {
+            Some(word) if word.starts_with('"') || word.starts_with('\'') => {
+                return Some(word[1..].to_string());
+            },
+            _ => {
+                return None;
+            }
+        }
+    };
+    match words.next() {
+        Some(keyword) if keyword.eq_ignore_ascii_case("public") => {
+            public_id = quoted(words.next());
+            system_id = quoted(words.next());
+        },
+        Some(keyword) if keyword.eq_ignore_ascii_case("system") => {
+            system_id = quoted(words.next());
+        },
+        _ => {},
+    }
+    return Ok((name, public_id, system_id));
+}
@@ -1,4 +1,5 @@
 use crate::*;
+use std::borrow::Cow;
 
 #[test]
 fn test_new_token_html_tag() {
@@ -28,6 +29,9 @@ fn test_validate_token_backslash_count() {
     assert!(validate_token_html_backslash_count("<h1//>").is_err());
     assert!(validate_token_html_backslash_count("<////h1//>").is_err());
     assert!(validate_token_html_backslash_count("<h1 attr='//////////'/>").is_ok());
+    assert!(validate_token_html_backslash_count("<a href=/path/to/x>").is_ok());
+    assert!(validate_token_html_backslash_count("<a href = /path/to/x/>").is_ok());
+    assert!(validate_token_html_backslash_count("<a href=/x // >").is_err());
 }
 
 #[test]
@@ -55,6 +59,9 @@ fn test_validate_token_html_quotes_more() {
     assert!(validate_token_html_quotes(r#"<div attr='""'>"#).is_ok());
     assert!(validate_token_html_quotes(r#"<div attr=''>"#).is_ok());
     assert!(validate_token_html_quotes(r#"<div attr='>"#).is_err());
+    assert!(validate_token_html_quotes(r#"<img src="" alt="">"#).is_ok());
+    assert!(validate_token_html_quotes(r#"<input value='' placeholder='' title="">"#).is_ok());
+    assert!(validate_token_html_quotes(r#"<div attr=""''>"#).is_err());
     assert!(validate_token_html_quotes(r#"<div attr='''''''>"#).is_err());
 }
 
@@ -90,4 +97,118 @@ fn test_html_tag_name() {
     assert!(html_tag_name("<    /h1>").unwrap() == "h1");
     assert!(html_tag_name("<    /   h1   >").unwrap() == "h1");
     assert!(html_tag_name("</   h1>").unwrap() == "h1");
-}
\ No newline at end of file
+}
+#[test]
+fn test_html_tag_name_borrowed() {
+    assert!(html_tag_name_borrowed("<h1 class='x'>").unwrap() == Cow::Borrowed("h1"));
+    assert!(html_tag_name_borrowed("<  /  input/>").unwrap() == Cow::Borrowed("input"));
+    assert!(matches!(html_tag_name_borrowed("<DIV>").unwrap(), Cow::Owned(name) if name == "div"));
+    assert!(matches!(html_tag_name_borrowed("<a/b/>").unwrap(), Cow::Owned(name) if name == "ab"));
+    assert!(matches!(html_tag_name_borrowed("<a<b>").unwrap(), Cow::Owned(name) if name == "ab"));
+    assert!(html_tag_name_borrowed("</>").is_err());
+}
+#[test]
+fn test_html_attributes() {
+    assert!(html_attributes("<div>").unwrap() == vec![]);
+    assert!(html_attributes("</div>").unwrap() == vec![]);
+    assert!(html_attributes(r#"<div class="foo bar">"#).unwrap() == vec![HtmlAttribute::new("class", "foo bar")]);
+    assert!(html_attributes("<div class='foo bar'>").unwrap() == vec![HtmlAttribute::new("class", "foo bar")]);
+    assert!(html_attributes("<div class=foo>").unwrap() == vec![HtmlAttribute::new("class", "foo")]);
+    assert!(html_attributes("<input disabled>").unwrap() == vec![HtmlAttribute::new("disabled", "")]);
+    assert!(html_attributes("<input disabled/>").unwrap() == vec![HtmlAttribute::new("disabled", "")]);
+    assert!(html_attributes("<input type='checkbox' checked />").unwrap() == vec![
+        HtmlAttribute::new("type", "checkbox"),
+        HtmlAttribute::new("checked", ""),
+    ]);
+    assert!(html_attributes(r#"<a href="/home" id=main data-x='1' hidden>"#).unwrap() == vec![
+        HtmlAttribute::new("href", "/home"),
+        HtmlAttribute::new("id", "main"),
+        HtmlAttribute::new("data-x", "1"),
+        HtmlAttribute::new("hidden", ""),
+    ]);
+    assert!(html_attributes("<img src=foo.png/>").unwrap() == vec![HtmlAttribute::new("src", "foo.png")]);
+    assert!(html_attributes("<div    class  =  'x'    >").unwrap() == vec![HtmlAttribute::new("class", "x")]);
+    assert!(html_attributes("<DIV CLASS='X'>").unwrap() == vec![HtmlAttribute::new("class", "X")]);
+    assert!(html_attributes(r#"<img alt='a "quoted" alt' title="it's">"#).unwrap() == vec![
+        HtmlAttribute::new("alt", r#"a "quoted" alt"#),
+        HtmlAttribute::new("title", "it's"),
+    ]);
+    assert!(html_attributes("<div attr=''>").unwrap() == vec![HtmlAttribute::new("attr", "")]);
+    // slashes in unquoted values and several empty values are ordinary markup
+    assert!(html_attributes("<a href=/path/to/x>").unwrap() == vec![HtmlAttribute::new("href", "/path/to/x")]);
+    assert!(html_attributes(r#"<img src="" alt="">"#).unwrap() == vec![HtmlAttribute::new("src", ""), HtmlAttribute::new("alt", "")]);
+    assert!(new_token_html_from_tag("<a href=/path/to/x>").unwrap() == TokenHtml::Open { tag_name: "a".to_string(), outer_html: "<a href=/path/to/x>".to_string() });
+    assert!(new_token_html_from_tag(r#"<img src="" alt="">"#).is_ok());
+    assert!(html_attributes(r#"<path d="M10 10 H90 V90 H10 Z"/>"#).unwrap() == vec![HtmlAttribute::new("d", "M10 10 H90 V90 H10 Z")]);
+    assert!(html_attributes(r#"<div class="missing end>"#).is_err());
+    assert!(html_attributes("<>").is_err());
+}
+
+#[test]
+fn test_token_html_attributes() {
+    let tok = new_token_html_from_tag("<a href='#' class=link>").unwrap();
+    assert!(tok.attributes() == vec![HtmlAttribute::new("href", "#"), HtmlAttribute::new("class", "link")]);
+    let tok = new_token_html_from_tag("<input type='text'/>").unwrap();
+    assert!(tok.attributes() == vec![HtmlAttribute::new("type", "text")]);
+    let tok = new_token_html_from_tag("<script src='app.js'>").unwrap();
+    assert!(tok.attributes() == vec![HtmlAttribute::new("src", "app.js")]);
+    let tok = new_token_html_from_tag("</a>").unwrap();
+    assert!(tok.attributes() == vec![]);
+    let tok = TokenHtml::InnerText { text: "a=b".to_string() };
+    assert!(tok.attributes() == vec![]);
+}
+
+#[test]
+fn test_html_utils_error_kinds() {
+    assert!(validate_token_html_backslash_count("<h1//>") == Err(RipError::TooManySlashes { tag: "<h1//>".to_string(), span: None }));
+    assert!(validate_token_html_quotes("<div attr='>") == Err(RipError::UnbalancedQuotes { tag: "<div attr='>".to_string(), span: None }));
+    assert!(html_tag_name("<>") == Err(RipError::TagTooShort { tag: "<>".to_string(), span: None }));
+    assert!(html_tag_name("h1>") == Err(RipError::MissingAngleBrackets { tag: "h1>".to_string(), span: None }));
+    assert!(html_tag_name("<     >") == Err(RipError::MissingTagName { tag: "<     >".to_string(), span: None }));
+    assert!(matches!(new_token_html_from_tag("<hr//>"), Err(RipError::TooManySlashes { .. })));
+    assert!(matches!(html_attributes(r#"<div class="missing end>"#), Err(RipError::UnbalancedQuotes { .. })));
+}
+
+#[test]
+fn test_html_doctype() {
+    assert!(is_html_doctype("<!DOCTYPE html>"));
+    assert!(is_html_doctype("<!doctype html>"));
+    assert!(!is_html_doctype("<!-- doctype -->"));
+    assert!(!is_html_doctype("<div>"));
+    assert!(html_doctype("<!DOCTYPE html>").unwrap() == ("html".to_string(), None, None));
+    assert!(html_doctype("<!DOCTYPE HTML>").unwrap() == ("html".to_string(), None, None));
+    assert!(html_doctype(r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN" "http://www.w3.org/TR/html4/strict.dtd">"#).unwrap() == (
+        "html".to_string(),
+        Some("-//W3C//DTD HTML 4.01//EN".to_string()),
+        Some("http://www.w3.org/TR/html4/strict.dtd".to_string()),
+    ));
+    assert!(html_doctype("<!DOCTYPE html PUBLIC '-//W3C//DTD XHTML 1.0 Strict//EN'>").unwrap() == (
+        "html".to_string(),
+        Some("-//W3C//DTD XHTML 1.0 Strict//EN".to_string()),
+        None,
+    ));
+    assert!(html_doctype(r#"<!DOCTYPE html SYSTEM "about:legacy-compat">"#).unwrap() == ("html".to_string(), None, Some("about:legacy-compat".to_string())));
+    assert!(html_doctype("<!DOCTYPE>").unwrap() == ("".to_string(), None, None));
+    assert!(html_doctype(r#"<!DOCTYPE html PUBLIC "unclosed>"#).is_err());
+    let tok = new_token_html_from_tag(r#"<!DOCTYPE html SYSTEM "about:legacy-compat">"#).unwrap();
+    assert!(tok == TokenHtml::Doctype {
+        name: "html".to_string(),
+        public_id: None,
+        system_id: Some("about:legacy-compat".to_string()),
+        outer_html: r#"<!DOCTYPE html SYSTEM "about:legacy-compat">"#.to_string(),
+    });
+}
+
+#[test]
+fn test_html_void_elements() {
+    for tag_name in HTML_VOID_ELEMENTS {
+        assert!(is_html_void_element(tag_name));
+    }
+    assert!(is_html_void_element("BR"));
+    assert!(!is_html_void_element("div"));
+    assert!(!is_html_void_element("p"));
+    assert!(new_token_html_from_tag("<br>").unwrap() == TokenHtml::SelfClosing { tag_name: "br".to_string(), outer_html: "<br>".to_string() });
+    assert!(new_token_html_from_tag("<img src=x>").unwrap() == TokenHtml::SelfClosing { tag_name: "img".to_string(), outer_html: "<img src=x>".to_string() });
+    assert!(new_token_html_from_tag("<META charset='utf-8'>").unwrap() == TokenHtml::SelfClosing { tag_name: "meta".to_string(), outer_html: "<META charset='utf-8'>".to_string() });
+    assert!(new_token_html_from_tag("</br>").unwrap() == TokenHtml::Close { tag_name: "br".to_string(), outer_html: "</br>".to_string() });
+}
@@ -1,10 +1,60 @@
+#![allow(clippy::needless_return)]
 
+pub mod chunked;
+#[cfg(test)]
+mod chunked_test;
+pub mod entities;
+#[cfg(test)]
+mod entities_test;
+mod entities_table;
+pub mod error;
+#[cfg(test)]
+mod error_test;
+pub mod formatter;
+#[cfg(test)]
+mod formatter_test;
+pub mod html5_parser;
+#[cfg(test)]
+mod html5_parser_test;
+pub mod html5_tokenizer;
+#[cfg(test)]
+mod html5_tokenizer_test;
 pub mod html_utils;
-pub mod html_utils_test;
+#[cfg(test)]
+mod html_utils_test;
+pub mod lint;
+#[cfg(test)]
+mod lint_test;
+pub mod minifier;
+#[cfg(test)]
+mod minifier_test;
+pub mod selector;
+#[cfg(test)]
+mod selector_test;
+pub mod serializer;
+#[cfg(test)]
+mod serializer_test;
+pub mod span;
+#[cfg(test)]
+mod span_test;
 pub mod tokenizer;
-pub mod tokenizer_test;
+#[cfg(test)]
+mod tokenizer_test;
 pub mod dom;
-pub mod dom_test;
+#[cfg(test)]
+mod dom_test;
+pub mod dom_tree;
+#[cfg(test)]
+mod dom_tree_test;
 
-use crate::tokenizer::*;
-use crate::html_utils::*;
\ No newline at end of file
+pub use crate::chunked::*;
+pub use crate::entities::*;
+pub use crate::error::*;
+pub use crate::formatter::*;
+pub use crate::span::Span;
+pub use crate::tokenizer::*;
+pub use crate::html_utils::*;
+pub use crate::html5_parser::*;
+pub use crate::html5_tokenizer::*;
+pub use crate::lint::*;
+pub use crate::minifier::*;
@@ -0,0 +1,290 @@
+use crate::entities::html_decode_attribute;
+use crate::error::RipError;
+use crate::html_utils::html_attributes;
+use crate::span::Span;
+use crate::tokenizer::{html_tokenize_lenient, TokenHtml};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum LintSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LintRule {
+    // a tag the tokenizer had to repair, like '<hr//>' or a quote that is never closed
+    MalformedTag,
+    DuplicateAttribute,
+    DuplicateId,
+    UnclosedElement,
+    StrayEndTag,
+    DeprecatedElement,
+    MissingAlt,
+    UppercaseTag,
+    MismatchedNesting,
+}
+
+impl LintRule {
+
+    // the name the rule goes by in reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::MalformedTag => {
+                return "malformed-tag";
+            },
+            LintRule::DuplicateAttribute => {
+                return "duplicate-attribute";
+            },
+            LintRule::DuplicateId => {
+                return "duplicate-id";
+            },
+            LintRule::UnclosedElement => {
+                return "unclosed-element";
+            },
+            LintRule::StrayEndTag => {
+                return "stray-end-tag";
+            },
+            LintRule::DeprecatedElement => {
+                return "deprecated-element";
+            },
+            LintRule::MissingAlt => {
+                return "missing-alt";
+            },
+            LintRule::UppercaseTag => {
+                return "uppercase-tag";
+            },
+            LintRule::MismatchedNesting => {
+                return "mismatched-nesting";
+            },
+        }
+    }
+
+}
+
+// how seriously each rule is taken, None turns a rule off
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LintOptions {
+    pub malformed_tag: Option<LintSeverity>,
+    pub duplicate_attribute: Option<LintSeverity>,
+    pub duplicate_id: Option<LintSeverity>,
+    pub unclosed_element: Option<LintSeverity>,
+    pub stray_end_tag: Option<LintSeverity>,
+    pub deprecated_element: Option<LintSeverity>,
+    pub missing_alt: Option<LintSeverity>,
+    pub uppercase_tag: Option<LintSeverity>,
+    pub mismatched_nesting: Option<LintSeverity>,
+    // elements like p, li and td may leave out their closing tag, turn this off to require it anyway
+    pub allow_optional_end_tags: bool,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        return LintOptions {
+            malformed_tag: Some(LintSeverity::Error),
+            duplicate_attribute: Some(LintSeverity::Error),
+            duplicate_id: Some(LintSeverity::Error),
+            unclosed_element: Some(LintSeverity::Error),
+            stray_end_tag: Some(LintSeverity::Error),
+            deprecated_element: Some(LintSeverity::Warning),
+            missing_alt: Some(LintSeverity::Error),
+            uppercase_tag: Some(LintSeverity::Warning),
+            mismatched_nesting: Some(LintSeverity::Error),
+            allow_optional_end_tags: true,
+        };
+    }
+}
+
+impl LintOptions {
+
+    pub fn severity(&self, rule: LintRule) -> Option<LintSeverity> {
+        match rule {
+            LintRule::MalformedTag => {
+                return self.malformed_tag;
+            },
+            LintRule::DuplicateAttribute => {
+                return self.duplicate_attribute;
+            },
+            LintRule::DuplicateId => {
+                return self.duplicate_id;
+            },
+            LintRule::UnclosedElement => {
+                return self.unclosed_element;
+            },
+            LintRule::StrayEndTag => {
+                return self.stray_end_tag;
+            },
+            LintRule::DeprecatedElement => {
+                return self.deprecated_element;
+            },
+            LintRule::MissingAlt => {
+                return self.missing_alt;
+            },
+            LintRule::UppercaseTag => {
+                return self.uppercase_tag;
+            },
+            LintRule::MismatchedNesting => {
+                return self.mismatched_nesting;
+            },
+        }
+    }
+
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LintFinding {
+    pub rule: LintRule,
+    pub severity: LintSeverity,
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            LintSeverity::Warning => "warning",
+            LintSeverity::Error => "error",
+        };
+        return write!(f, "{}: {} [{}] (line {}, column {})", severity, self.message, self.rule.name(), self.span.line, self.span.column);
+    }
+}
+
+pub fn lint(html: &str) -> Vec<LintFinding> {
+    return lint_with_options(html, LintOptions::default());
+}
+
+// checks the whole document and reports every finding of the enabled rules, ordered by where they are in the source
+pub fn lint_with_options(html: &str, options: LintOptions) -> Vec<LintFinding> {
+    let mut linter = Linter { options, findings: vec![] };
+    let tokens = html_tokenize_lenient(html);
+    for diagnostic in &tokens.diagnostics {
+        linter.report_malformed(diagnostic);
+    }
+    // the elements still waiting on their closing tag, with where each was opened
+    let mut open: Vec<(String, Span)> = vec![];
+    // the first element to use each id
+    let mut ids: HashMap<String, Span> = HashMap::new();
+    for tok in tokens.tokens {
+        let span = tok.span;
+        match tok.token {
+            TokenHtml::Open { tag_name, outer_html } | TokenHtml::PreLikeOpen { tag_name, outer_html } => {
+                linter.check_start_tag(&tag_name, &outer_html, span, &mut ids);
+                open.push((tag_name, span));
+            },
+            TokenHtml::SelfClosing { tag_name, outer_html } => {
+                linter.check_start_tag(&tag_name, &outer_html, span, &mut ids);
+            },
+            TokenHtml::Close { tag_name, outer_html } | TokenHtml::PreLikeClose { tag_name, outer_html } => {
+                linter.check_uppercase(&outer_html, span);
+                linter.check_end_tag(&tag_name, &outer_html, span, &mut open);
+            },
+            _ => {},
+        }
+    }
+    for (tag_name, span) in open {
+        if !linter.may_omit_end_tag(&tag_name) {
+            linter.report(LintRule::UnclosedElement, format!("<{}> is never closed", tag_name), span);
+        }
+    }
+    linter.findings.sort_by_key(|finding| finding.span.start);
+    return linter.findings;
+}
+
+struct Linter {
+    options: LintOptions,
+    findings: Vec<LintFinding>,
+}
+
+impl Linter {
+
+    fn report(&mut self, rule: LintRule, message: String, span: Span) {
+        if let Some(severity) = self.options.severity(rule) {
+            self.findings.push(LintFinding { rule, severity, message, span });
+        }
+    }
+
+    fn report_malformed(&mut self, diagnostic: &RipError) {
+        let span = diagnostic.span().unwrap_or(Span::new(0, 0, 1, 1));
+        // the diagnostic's Display already names the position, the finding carries it in its span instead
+        let message = match diagnostic.to_string().rsplit_once(" (line ") {
+            Some((message, _)) => message.to_string(),
+            None => diagnostic.to_string(),
+        };
+        self.report(LintRule::MalformedTag, message, span);
+    }
+
+    fn may_omit_end_tag(&self, tag_name: &str) -> bool {
+        return self.options.allow_optional_end_tags && LINT_OPTIONAL_END_TAGS.contains(&tag_name);
+    }
+
+    fn check_start_tag(&mut self, tag_name: &str, outer_html: &str, span: Span, ids: &mut HashMap<String, Span>) {
+        self.check_uppercase(outer_html, span);
+        if LINT_DEPRECATED_ELEMENTS.contains(&tag_name) {
+            self.report(LintRule::DeprecatedElement, format!("<{}> is deprecated", tag_name), span);
+        }
+        let attributes = html_attributes(outer_html).unwrap_or_default();
+        for (i, attr) in attributes.iter().enumerate() {
+            if attributes[..i].iter().any(|earlier| earlier.name == attr.name) {
+                self.report(LintRule::DuplicateAttribute, format!("<{}> has the attribute '{}' more than once", tag_name, attr.name), span);
+            }
+        }
+        if tag_name == "img" && !attributes.iter().any(|attr| attr.name == "alt") {
+            self.report(LintRule::MissingAlt, "<img> has no alt attribute".to_string(), span);
+        }
+        // only the first id counts, a repeat of the attribute was reported above
+        if let Some(id) = attributes.iter().find(|attr| attr.name == "id") {
+            let id = html_decode_attribute(&id.value);
+            match ids.get(&id) {
+                Some(first) => {
+                    let message = format!("the id '{}' was already used on line {}, column {}", id, first.line, first.column);
+                    self.report(LintRule::DuplicateId, message, span);
+                },
+                None => {
+                    ids.insert(id, span);
+                },
+            }
+        }
+    }
+
+    // the tokenizer lowercases tag names, so the name is checked as it was written in the tag
+    fn check_uppercase(&mut self, outer_html: &str, span: Span) {
+        let written: String = outer_html.trim_start_matches(['<', '/', ' ']).chars().take_while(|c| !c.is_whitespace() && *c != '/' && *c != '>').collect();
+        if written.chars().any(|c| c.is_ascii_uppercase()) {
+            self.report(LintRule::UppercaseTag, format!("<{}> should be written in lowercase", written), span);
+        }
+    }
+
+    fn check_end_tag(&mut self, tag_name: &str, outer_html: &str, span: Span, open: &mut Vec<(String, Span)>) {
+        let i = match open.iter().rposition(|(open_name, _)| open_name == tag_name) {
+            Some(i) => i,
+            None => {
+                self.report(LintRule::StrayEndTag, format!("{} has no matching opening tag", outer_html), span);
+                return;
+            },
+        };
+        // elements opened since then are still open, apart from the ones whose end tag may be left out
+        let inner: Vec<(String, Span)> = open.drain(i + 1..).filter(|(open_name, _)| !self.may_omit_end_tag(open_name)).collect();
+        open.pop();
+        if let Some((inner_name, inner_span)) = inner.first() {
+            let message = format!("{} closes <{}> while <{}> from line {}, column {} is still open", outer_html, tag_name, inner_name, inner_span.line, inner_span.column);
+            self.report(LintRule::MismatchedNesting, message, span);
+        }
+        // they stay open so their own closing tags still find them
+        open.extend(inner);
+    }
+
+}
+
+// elements whose closing tag may be left out, the next sibling or the end of the parent closes them
+const LINT_OPTIONAL_END_TAGS: [&str; 21] = [
+    "body", "caption", "colgroup", "dd", "dt", "head", "html", "li", "optgroup", "option", "p", "rb", "rp", "rt",
+    "rtc", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+// obsolete elements that the html standard tells authors not to use
+const LINT_DEPRECATED_ELEMENTS: [&str; 28] = [
+    "acronym", "applet", "basefont", "bgsound", "big", "blink", "center", "dir", "font", "frame", "frameset", "isindex",
+    "keygen", "listing", "marquee", "menuitem", "multicol", "nextid", "nobr", "noembed", "noframes", "plaintext", "rb",
+    "rtc", "spacer", "strike", "tt", "xmp",
+];
@@ -0,0 +1,45 @@
+use crate::*;
+
+fn lint_rules(html: &str) -> Vec<(LintRule, usize, usize)> {
+    return lint(html).into_iter().map(|finding| (finding.rule, finding.span.line, finding.span.column)).collect();
+}
+
+#[test]
+fn test_lint_clean() {
+    assert!(lint("<ul>\n  <li>One\n  <li>Two\n</ul>\n<img src='a.png' alt=''>\n<p id='a'>Hi</p><p id='b'>There").is_empty());
+}
+
+#[test]
+fn test_lint_rules() {
+    assert!(lint_rules("<div class='a' class='b'></div>") == vec![(LintRule::DuplicateAttribute, 1, 1)]);
+    assert!(lint_rules("<p id='x'></p>\n<p id='x'></p>") == vec![(LintRule::DuplicateId, 2, 1)]);
+    assert!(lint_rules("<div>\n<section>") == vec![(LintRule::UnclosedElement, 1, 1), (LintRule::UnclosedElement, 2, 1)]);
+    assert!(lint_rules("<p>a</p></span>") == vec![(LintRule::StrayEndTag, 1, 9)]);
+    assert!(lint_rules("<center>a</center>") == vec![(LintRule::DeprecatedElement, 1, 1)]);
+    assert!(lint_rules("<img src='a.png'>") == vec![(LintRule::MissingAlt, 1, 1)]);
+    assert!(lint_rules("<DIV></Div>") == vec![(LintRule::UppercaseTag, 1, 1), (LintRule::UppercaseTag, 1, 6)]);
+    assert!(lint_rules("<b><i>a</b></i>") == vec![(LintRule::MismatchedNesting, 1, 8)]);
+    assert!(lint_rules("<div><hr//></div>") == vec![(LintRule::MalformedTag, 1, 6)]);
+}
+
+#[test]
+fn test_lint_findings() {
+    let findings = lint("<div>\n  <img src=x.png>\n  <font>a</font>\n");
+    assert!(findings.len() == 3);
+    assert!(findings[0].severity == LintSeverity::Error);
+    assert!(findings[0].to_string() == "error: <div> is never closed [unclosed-element] (line 1, column 1)");
+    assert!(findings[1].to_string() == "error: <img> has no alt attribute [missing-alt] (line 2, column 3)");
+    assert!(findings[2].severity == LintSeverity::Warning);
+    assert!(findings[2].span == Span::new(26, 32, 3, 3));
+}
+
+#[test]
+fn test_lint_options() {
+    let options = LintOptions { deprecated_element: None, missing_alt: Some(LintSeverity::Warning), ..LintOptions::default() };
+    let findings = lint_with_options("<center><img src=x.png></center>", options);
+    assert!(findings.len() == 1);
+    assert!((findings[0].rule, findings[0].severity) == (LintRule::MissingAlt, LintSeverity::Warning));
+    let options = LintOptions { allow_optional_end_tags: false, ..LintOptions::default() };
+    let findings = lint_with_options("<ul><li>One<li>Two</ul>", options);
+    assert!(findings.iter().map(|finding| finding.rule).collect::<Vec<_>>() == vec![LintRule::UnclosedElement, LintRule::UnclosedElement, LintRule::MismatchedNesting]);
+}
@@ -0,0 +1,287 @@
+#![allow(clippy::needless_return)]
+
+use rip::dom::{DomNode, DomNodeKind};
+use rip::selector::css_selector_from_str;
+use rip::*;
+use std::io::{self, Read, Write};
+
+#[cfg(test)]
+mod main_test;
+
+const RIP_USAGE: &str = "usage: rip <command> [options] [file ...]
+
+reads each file in turn, or stdin when no file or '-' is given
+
+commands:
+  tokens [--json]     print the token stream, one token per line or as one json array for all inputs
+  tree                print the tree a browser would build
+  fmt [--indent <n>] [--tabs] [--width <n>]
+                      pretty-print the markup, indenting n spaces or a tab per level and
+                      wrapping tags with attributes past n chars, 0 never wraps
+  query <selector>    print every element matching a css selector
+  lint                report problems, exits with 1 if any of them is an error
+";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = rip_run(&args, &mut io::stdin(), &mut io::stdout().lock(), &mut io::stderr());
+    std::process::exit(code);
+}
+
+// runs the command line and returns the exit code: 0 when all went well, 1 when lint found errors,
+// and 2 when the command line or an input couldn't be used
+fn rip_run(args: &[String], stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    match rip_command(args, stdin, out, err) {
+        Ok(code) => {
+            return code;
+        },
+        Err(e) => {
+            // a closed pipe just means whoever was reading has seen enough, like 'rip tokens page.html | head'
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                return 0;
+            }
+            let _ = writeln!(err, "rip: {}", e);
+            return 2;
+        },
+    }
+}
+
+fn rip_command(args: &[String], stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> io::Result<i32> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => {
+            write!(err, "{}", RIP_USAGE)?;
+            return Ok(2);
+        },
+    };
+    let mut json = false;
+    let mut format_options = HtmlFormatOptions::default();
+    let mut operands: Vec<&str> = vec![];
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" if command == "tokens" => {
+                json = true;
+            },
+            "--tabs" if command == "fmt" => {
+                format_options.use_tabs = true;
+            },
+            "--indent" | "--width" if command == "fmt" => {
+                let value = match args.next().and_then(|value| value.parse::<usize>().ok()) {
+                    Some(value) => value,
+                    None => {
+                        writeln!(err, "rip: {} needs a number", arg)?;
+                        return Ok(2);
+                    },
+                };
+                if arg == "--indent" {
+                    format_options.indent_width = value;
+                } else {
+                    format_options.max_line_width = value;
+                }
+            },
+            "-" => {
+                operands.push(arg);
+            },
+            flag if flag.starts_with('-') => {
+                writeln!(err, "rip: unknown option {} for {}", flag, command)?;
+                return Ok(2);
+            },
+            _ => {
+                operands.push(arg);
+            },
+        }
+    }
+    let selector = if command == "query" {
+        if operands.is_empty() {
+            writeln!(err, "rip: query needs a selector")?;
+            return Ok(2);
+        }
+        let selector = operands.remove(0);
+        // checked up front, so a bad selector is reported once instead of once per input
+        if let Err(e) = css_selector_from_str(selector) {
+            writeln!(err, "rip: {}", e)?;
+            return Ok(2);
+        }
+        selector
+    } else {
+        ""
+    };
+    if !matches!(command, "tokens" | "tree" | "fmt" | "query" | "lint") {
+        if command == "help" || command == "--help" || command == "-h" {
+            write!(out, "{}", RIP_USAGE)?;
+            return Ok(0);
+        }
+        writeln!(err, "rip: unknown command {}", command)?;
+        write!(err, "{}", RIP_USAGE)?;
+        return Ok(2);
+    }
+    if operands.is_empty() {
+        operands.push("-");
+    }
+    let mut code = 0;
+    // with --json the tokens of every input go into one array, written once they have all been read
+    let mut json_tokens: Vec<SpannedTokenHtml> = vec![];
+    for path in operands {
+        let html = rip_read_input(path, stdin)?;
+        let name = if path == "-" { "<stdin>" } else { path };
+        match command {
+            "tokens" => {
+                let result = html_tokenize_lenient(&html);
+                for diagnostic in &result.diagnostics {
+                    writeln!(err, "{}: {}", name, diagnostic)?;
+                }
+                if json {
+                    json_tokens.extend(result.tokens);
+                } else {
+                    for tok in &result.tokens {
+                        writeln!(out, "{}:{} {}", tok.span.line, tok.span.column, rip_token_text(&tok.token))?;
+                    }
+                }
+            },
+            "tree" => {
+                rip_write_tree(&html5_parse(&html), 0, out)?;
+            },
+            "fmt" => {
+                write!(out, "{}", html_format_with_options(&html, format_options))?;
+            },
+            "query" => {
+                let root = html5_parse(&html);
+                // the selector was checked above, so it can't fail here
+                for node in root.query_selector_all(selector).unwrap_or_default() {
+                    writeln!(out, "{}", node.to_html())?;
+                }
+            },
+            _ => {
+                for finding in lint(&html) {
+                    if finding.severity == LintSeverity::Error {
+                        code = 1;
+                    }
+                    writeln!(out, "{}: {}", name, finding)?;
+                }
+            },
+        }
+    }
+    if json {
+        rip_write_tokens_json(&json_tokens, out)?;
+    }
+    return Ok(code);
+}
+
+// files that aren't valid utf-8 are still read, with the bad bytes as U+FFFD
+fn rip_read_input(path: &str, stdin: &mut dyn Read) -> io::Result<String> {
+    let mut bytes: Vec<u8> = vec![];
+    if path == "-" {
+        stdin.read_to_end(&mut bytes)?;
+    } else {
+        bytes = std::fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+    }
+    return Ok(String::from_utf8_lossy(&bytes).into_owned());
+}
+
+fn rip_token_text(tok: &TokenHtml) -> String {
+    match tok {
+        TokenHtml::Open { outer_html, .. } => {
+            return format!("Open {}", outer_html);
+        },
+        TokenHtml::Close { outer_html, .. } => {
+            return format!("Close {}", outer_html);
+        },
+        TokenHtml::SelfClosing { outer_html, .. } => {
+            return format!("SelfClosing {}", outer_html);
+        },
+        TokenHtml::PreLikeOpen { outer_html, .. } => {
+            return format!("PreLikeOpen {}", outer_html);
+        },
+        TokenHtml::PreLikeClose { outer_html, .. } => {
+            return format!("PreLikeClose {}", outer_html);
+        },
+        TokenHtml::Doctype { outer_html, .. } => {
+            return format!("Doctype {}", outer_html);
+        },
+        TokenHtml::InnerText { text } => {
+            return format!("InnerText {:?}", text);
+        },
+        TokenHtml::Whitespace { text } => {
+            return format!("Whitespace {:?}", text);
+        },
+        TokenHtml::Comment { text } => {
+            return format!("Comment {:?}", text);
+        },
+    }
+}
+
+// an array of objects like {"type": "Open", "tag_name": "p", "outer_html": "<p>", "span": {...}},
+// the fields are the ones the TokenHtml variant has. this is the shape the serde feature gives a
+// SpannedTokenHtml, main_test checks the two against each other
+fn rip_write_tokens_json(toks: &[SpannedTokenHtml], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, tok) in toks.iter().enumerate() {
+        let fields = match &tok.token {
+            TokenHtml::Open { tag_name, outer_html } => format!("\"type\": \"Open\", \"tag_name\": {}, \"outer_html\": {}", rip_json_string(tag_name), rip_json_string(outer_html)),
+            TokenHtml::Close { tag_name, outer_html } => format!("\"type\": \"Close\", \"tag_name\": {}, \"outer_html\": {}", rip_json_string(tag_name), rip_json_string(outer_html)),
+            TokenHtml::SelfClosing { tag_name, outer_html } => format!("\"type\": \"SelfClosing\", \"tag_name\": {}, \"outer_html\": {}", rip_json_string(tag_name), rip_json_string(outer_html)),
+            TokenHtml::PreLikeOpen { tag_name, outer_html } => format!("\"type\": \"PreLikeOpen\", \"tag_name\": {}, \"outer_html\": {}", rip_json_string(tag_name), rip_json_string(outer_html)),
+            TokenHtml::PreLikeClose { tag_name, outer_html } => format!("\"type\": \"PreLikeClose\", \"tag_name\": {}, \"outer_html\": {}", rip_json_string(tag_name), rip_json_string(outer_html)),
+            TokenHtml::InnerText { text } => format!("\"type\": \"InnerText\", \"text\": {}", rip_json_string(text)),
+            TokenHtml::Whitespace { text } => format!("\"type\": \"Whitespace\", \"text\": {}", rip_json_string(text)),
+            TokenHtml::Comment { text } => format!("\"type\": \"Comment\", \"text\": {}", rip_json_string(text)),
+            TokenHtml::Doctype { name, public_id, system_id, outer_html } => {
+                let optional = |id: &Option<String>| id.as_deref().map(rip_json_string).unwrap_or("null".to_string());
+                format!("\"type\": \"Doctype\", \"name\": {}, \"public_id\": {}, \"system_id\": {}, \"outer_html\": {}", rip_json_string(name), optional(public_id), optional(system_id), rip_json_string(outer_html))
+            },
+        };
+        let span = tok.span;
+        let comma = if i + 1 < toks.len() { "," } else { "" };
+        writeln!(out, "  {{{}, \"span\": {{\"start\": {}, \"end\": {}, \"line\": {}, \"column\": {}}}}}{}", fields, span.start, span.end, span.line, span.column, comma)?;
+    }
+    writeln!(out, "]")?;
+    return Ok(());
+}
+
+fn rip_json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    return out;
+}
+
+// one node per line, indented two spaces per level: elements with their attributes, text quoted, then comments and doctypes
+fn rip_write_tree(node: &DomNode, depth: usize, out: &mut dyn Write) -> io::Result<()> {
+    let indent = "  ".repeat(depth);
+    match node.kind {
+        DomNodeKind::Root => {
+            for child in &node.children {
+                rip_write_tree(child, depth, out)?;
+            }
+        },
+        DomNodeKind::Element => {
+            let attributes: String = node.attributes.iter().map(|attr| format!(" {}={:?}", attr.name, attr.value)).collect();
+            writeln!(out, "{}<{}{}>", indent, node.tag_name, attributes)?;
+            for child in &node.children {
+                rip_write_tree(child, depth + 1, out)?;
+            }
+        },
+        DomNodeKind::Text => {
+            writeln!(out, "{}{:?}", indent, node.text)?;
+        },
+        DomNodeKind::Comment => {
+            writeln!(out, "{}<!--{}-->", indent, node.text)?;
+        },
+        DomNodeKind::Doctype => {
+            writeln!(out, "{}{}", indent, node.outer_html.trim())?;
+        },
+    }
+    return Ok(());
+}
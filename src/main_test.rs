@@ -0,0 +1,79 @@
+use super::*;
+
+fn rip_run_with(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let mut out: Vec<u8> = vec![];
+    let mut err: Vec<u8> = vec![];
+    let code = rip_run(&args, &mut stdin.as_bytes(), &mut out, &mut err);
+    return (code, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap());
+}
+
+#[test]
+fn test_rip_tokens() {
+    let (code, out, _) = rip_run_with(&["tokens"], "<p>Hi</p>");
+    assert!(code == 0);
+    assert!(out == "1:1 Open <p>\n1:4 InnerText \"Hi\"\n1:6 Close </p>\n");
+    let (code, out, _) = rip_run_with(&["tokens", "--json", "-"], "<p>\"Hi\"\n");
+    assert!(code == 0);
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert!(json[0] == serde_json::json!({ "type": "Open", "tag_name": "p", "outer_html": "<p>", "span": { "start": 0, "end": 3, "line": 1, "column": 1 } }));
+    assert!(json[1]["text"] == "\"Hi\"\n");
+    // malformed markup still gives tokens, the problems go to stderr
+    let (code, out, err) = rip_run_with(&["tokens"], "<hr//>");
+    assert!(code == 0);
+    assert!(out == "1:1 SelfClosing <hr//>\n");
+    assert!(err.starts_with("<stdin>: the following tag has more than 1 '/'"));
+}
+
+// the json rip writes by hand has to be what the serde feature makes of the same tokens
+#[cfg(feature = "serde")]
+#[test]
+fn test_rip_tokens_json_matches_serde() {
+    let html = "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\"><p title='a \"b\" \\ c'>tab\there\u{1}</p>\n<br/><!-- é --><script>a<b</script>";
+    let (code, out, _) = rip_run_with(&["tokens", "--json"], html);
+    assert!(code == 0);
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert!(json == serde_json::to_value(html_tokenize_lenient(html).tokens).unwrap());
+
+    // several inputs still make one array
+    let path = std::env::temp_dir().join(format!("rip_main_test_{}.html", std::process::id()));
+    std::fs::write(&path, "<b>x</b>").unwrap();
+    let path = path.to_str().unwrap().to_string();
+    let (code, out, _) = rip_run_with(&["tokens", "--json", "-", &path], "<i>");
+    std::fs::remove_file(&path).unwrap();
+    assert!(code == 0);
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    let mut tokens = html_tokenize_lenient("<i>").tokens;
+    tokens.extend(html_tokenize_lenient("<b>x</b>").tokens);
+    assert!(json == serde_json::to_value(tokens).unwrap());
+}
+
+#[test]
+fn test_rip_tree_fmt_and_query() {
+    let (_, out, _) = rip_run_with(&["tree"], "<p class=x>Hi<!--c-->");
+    assert!(out == "<html>\n  <head>\n  <body>\n    <p class=\"x\">\n      \"Hi\"\n      <!--c-->\n");
+    let (_, out, _) = rip_run_with(&["fmt"], "<div><p>Hi</p></div>");
+    assert!(out == "<div>\n  <p>Hi</p>\n</div>\n");
+    let (_, out, _) = rip_run_with(&["fmt", "--indent", "4"], "<div><p>Hi</p></div>");
+    assert!(out == "<div>\n    <p>Hi</p>\n</div>\n");
+    assert!(rip_run_with(&["fmt", "--width"], "").0 == 2);
+    let (code, out, _) = rip_run_with(&["query", "li.on"], "<ul><li class=on>1<li>2<li class=on>3</ul>");
+    assert!(code == 0);
+    assert!(out == "<li class=\"on\">1</li>\n<li class=\"on\">3</li>\n");
+}
+
+#[test]
+fn test_rip_lint_and_errors() {
+    let (code, out, _) = rip_run_with(&["lint"], "<div><img src=a.png></div>");
+    assert!(code == 1);
+    assert!(out == "<stdin>: error: <img> has no alt attribute [missing-alt] (line 1, column 6)\n");
+    let (code, out, _) = rip_run_with(&["lint"], "<center>old</center>");
+    assert!(code == 0);
+    assert!(out.contains("warning"));
+    assert!(rip_run_with(&[], "").0 == 2);
+    assert!(rip_run_with(&["nope"], "").0 == 2);
+    assert!(rip_run_with(&["query", "p["], "").0 == 2);
+    let (code, _, err) = rip_run_with(&["fmt", "/no/such/file.html"], "");
+    assert!(code == 2);
+    assert!(err.starts_with("rip: /no/such/file.html:"));
+}
@@ -0,0 +1,282 @@
+use crate::html_utils::{html_attributes, is_html_void_element, HtmlAttribute};
+use crate::tokenizer::{html_tokenize_lenient, TokenHtml};
+
+// called with a prelike element's tag name and body, returns the body to write in its place
+pub type HtmlMinifyHook<'a> = &'a dyn Fn(&str, &str) -> String;
+
+#[derive(Clone, Copy)]
+pub struct HtmlMinifyOptions<'a> {
+    pub remove_comments: bool,
+    // 'disabled="disabled"' and 'disabled=""' become 'disabled'
+    pub shorten_boolean_attributes: bool,
+    // values made of nothing that needs quoting lose their quotes, 'class="a"' becomes 'class=a'
+    pub remove_attribute_quotes: bool,
+    // drops end tags the html standard lets documents leave out, like '</li>' before the next '<li>'
+    pub remove_optional_end_tags: bool,
+    // the bodies of pre, textarea, script, style and xmp are copied as they are unless this is given
+    pub prelike_hook: Option<HtmlMinifyHook<'a>>,
+}
+
+impl Default for HtmlMinifyOptions<'_> {
+    fn default() -> Self {
+        return HtmlMinifyOptions {
+            remove_comments: true,
+            shorten_boolean_attributes: true,
+            remove_attribute_quotes: true,
+            remove_optional_end_tags: true,
+            prelike_hook: None,
+        };
+    }
+}
+
+impl std::fmt::Debug for HtmlMinifyOptions<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.debug_struct("HtmlMinifyOptions")
+            .field("remove_comments", &self.remove_comments)
+            .field("shorten_boolean_attributes", &self.shorten_boolean_attributes)
+            .field("remove_attribute_quotes", &self.remove_attribute_quotes)
+            .field("remove_optional_end_tags", &self.remove_optional_end_tags)
+            .field("prelike_hook", &self.prelike_hook.is_some())
+            .finish();
+    }
+}
+
+pub fn html_minify(html: &str) -> String {
+    return html_minify_with_options(html, HtmlMinifyOptions::default());
+}
+
+// writes the same document in as few bytes as it can without changing how it renders. whitespace next to
+// block elements goes, every other run of whitespace in text shrinks to one space, and tags are rewritten
+// in their shortest form. malformed markup is minified the way html_tokenize_lenient reads it
+pub fn html_minify_with_options(html: &str, options: HtmlMinifyOptions) -> String {
+    let toks = html_minify_tokens(html, options.remove_comments);
+    let mut out = String::with_capacity(html.len());
+    // the elements the next token sits in, to know what an end tag closes
+    let mut open: Vec<&str> = vec![];
+    for (i, tok) in toks.iter().enumerate() {
+        let prev = if i > 0 { toks.get(i - 1) } else { None };
+        let next = toks.get(i + 1);
+        match tok {
+            TokenHtml::Open { tag_name, outer_html } | TokenHtml::PreLikeOpen { tag_name, outer_html } => {
+                out.push_str(&html_minify_tag(tag_name, outer_html, false, &options));
+                open.push(tag_name);
+            },
+            TokenHtml::SelfClosing { tag_name, outer_html } => {
+                // void elements need no '/', anything else written as '<x/>' keeps it
+                out.push_str(&html_minify_tag(tag_name, outer_html, !is_html_void_element(tag_name), &options));
+            },
+            TokenHtml::Close { tag_name, .. } | TokenHtml::PreLikeClose { tag_name, .. } => {
+                let closes = open.iter().rposition(|name| name == tag_name);
+                let parent = match closes {
+                    Some(j) if j > 0 => Some(open[j - 1]),
+                    _ => None,
+                };
+                if let Some(j) = closes {
+                    open.truncate(j);
+                }
+                if options.remove_optional_end_tags && closes.is_some() && html_minify_end_tag_optional(tag_name, parent, next) {
+                    continue;
+                }
+                out.push_str("</");
+                out.push_str(tag_name);
+                out.push('>');
+            },
+            TokenHtml::InnerText { text } | TokenHtml::Whitespace { text } => {
+                match prev {
+                    Some(TokenHtml::PreLikeOpen { tag_name, .. }) => {
+                        match options.prelike_hook {
+                            Some(hook) => out.push_str(&hook(tag_name, text)),
+                            None => out.push_str(text),
+                        }
+                    },
+                    _ => {
+                        out.push_str(text);
+                    },
+                }
+            },
+            TokenHtml::Comment { text } => {
+                out.push_str("<!--");
+                out.push_str(text);
+                out.push_str("-->");
+            },
+            TokenHtml::Doctype { outer_html, .. } => {
+                out.push_str(outer_html.trim());
+            },
+        }
+    }
+    return out;
+}
+
+// the tokens with comments dropped when asked and the text between tags already minified,
+// so each token's neighbours are the ones it will end up next to
+fn html_minify_tokens(html: &str, remove_comments: bool) -> Vec<TokenHtml> {
+    let mut toks: Vec<TokenHtml> = vec![];
+    for tok in html_tokenize_lenient(html).tokens {
+        let tok = match tok.token {
+            TokenHtml::Comment { .. } if remove_comments => {
+                continue;
+            },
+            TokenHtml::Whitespace { text } => TokenHtml::InnerText { text },
+            tok => tok,
+        };
+        // comments are gone by now, the text on either side of one is joined into a single run.
+        // prelike bodies never sit next to other text, so joining can't reach into one
+        if let (Some(TokenHtml::InnerText { text: last }), TokenHtml::InnerText { text }) = (toks.last_mut(), &tok) {
+            last.push_str(text);
+            continue;
+        }
+        toks.push(tok);
+    }
+    let mut minified: Vec<TokenHtml> = Vec::with_capacity(toks.len());
+    for (i, tok) in toks.iter().enumerate() {
+        let prev = if i > 0 { toks.get(i - 1) } else { None };
+        match (tok, prev) {
+            (TokenHtml::InnerText { .. }, Some(TokenHtml::PreLikeOpen { .. })) => {
+                minified.push(tok.clone());
+            },
+            (TokenHtml::InnerText { text }, _) => {
+                let text = html_minify_text(text, prev, toks.get(i + 1));
+                if !text.is_empty() {
+                    minified.push(TokenHtml::InnerText { text });
+                }
+            },
+            _ => {
+                minified.push(tok.clone());
+            },
+        }
+    }
+    return minified;
+}
+
+// whitespace right next to a block element never renders, elsewhere a run of it shows as one space
+fn html_minify_text(text: &str, prev: Option<&TokenHtml>, next: Option<&TokenHtml>) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    let mut collapsed = collapsed.as_str();
+    if html_minify_is_block(prev) {
+        collapsed = collapsed.trim_start_matches(' ');
+    }
+    if html_minify_is_block(next) {
+        collapsed = collapsed.trim_end_matches(' ');
+    }
+    return collapsed.to_string();
+}
+
+fn html_minify_is_block(tok: Option<&TokenHtml>) -> bool {
+    return match tok {
+        // the start and end of the document count as block boundaries
+        None => true,
+        Some(TokenHtml::Open { tag_name, .. })
+        | Some(TokenHtml::Close { tag_name, .. })
+        | Some(TokenHtml::SelfClosing { tag_name, .. })
+        | Some(TokenHtml::PreLikeOpen { tag_name, .. })
+        | Some(TokenHtml::PreLikeClose { tag_name, .. }) => HTML_MINIFY_BLOCK_ELEMENTS.contains(&tag_name.as_str()),
+        Some(TokenHtml::Doctype { .. }) => true,
+        _ => false,
+    };
+}
+
+// the opening tag rebuilt from its name and attributes, tags whose attributes can't be read are kept as they are
+fn html_minify_tag(tag_name: &str, outer_html: &str, self_closing: bool, options: &HtmlMinifyOptions) -> String {
+    let attributes = match html_attributes(outer_html) {
+        Ok(attributes) => attributes,
+        Err(_) => {
+            return outer_html.to_string();
+        },
+    };
+    let mut out = format!("<{}", tag_name);
+    let mut last_unquoted = false;
+    for HtmlAttribute { name, value } in attributes {
+        out.push(' ');
+        out.push_str(&name);
+        last_unquoted = false;
+        let boolean = HTML_MINIFY_BOOLEAN_ATTRIBUTES.contains(&name.as_str()) && (value.is_empty() || value.eq_ignore_ascii_case(&name));
+        if options.shorten_boolean_attributes && boolean {
+            continue;
+        }
+        out.push('=');
+        // a value ending in '/' would read as the '/' of a self-closing tag
+        let needs_quotes = value.is_empty() || value.ends_with('/') || value.contains(|c: char| c.is_ascii_whitespace() || "\"'=<>`".contains(c));
+        if options.remove_attribute_quotes && !needs_quotes {
+            out.push_str(&value);
+            last_unquoted = true;
+            continue;
+        }
+        // html_attributes hands back the value as written, so it can only hold the quote it wasn't written in
+        let quote = if value.contains('"') { '\'' } else { '"' };
+        out.push(quote);
+        out.push_str(&value);
+        out.push(quote);
+    }
+    if self_closing {
+        if last_unquoted {
+            out.push(' ');
+        }
+        out.push('/');
+    }
+    out.push('>');
+    return out;
+}
+
+// whether the standard lets the end tag go given what comes right after it. parent is the element
+// the closed one sat in, and an end tag right after means the closed element was its parent's last child
+fn html_minify_end_tag_optional(tag_name: &str, parent: Option<&str>, next: Option<&TokenHtml>) -> bool {
+    let next_open = match next {
+        Some(TokenHtml::Open { tag_name, .. }) | Some(TokenHtml::PreLikeOpen { tag_name, .. }) | Some(TokenHtml::SelfClosing { tag_name, .. }) => Some(tag_name.as_str()),
+        _ => None,
+    };
+    let parent_ends = matches!(next, Some(TokenHtml::Close { .. }) | Some(TokenHtml::PreLikeClose { .. }));
+    let next_is = |names: &[&str]| next_open.is_some_and(|open| names.contains(&open));
+    return match tag_name {
+        "li" => next_is(&["li"]) || parent_ends,
+        "dt" => next_is(&["dt", "dd"]),
+        "dd" => next_is(&["dt", "dd"]) || parent_ends,
+        "p" => next_is(&HTML_MINIFY_P_CLOSERS) || (parent_ends && !parent.is_some_and(|parent| HTML_MINIFY_P_KEEPERS.contains(&parent))),
+        "rt" | "rp" => next_is(&["rt", "rp"]) || parent_ends,
+        "optgroup" => next_is(&["optgroup"]) || parent_ends,
+        "option" => next_is(&["option", "optgroup"]) || parent_ends,
+        "thead" => next_is(&["tbody", "tfoot"]),
+        "tbody" => next_is(&["tbody", "tfoot"]) || parent_ends,
+        "tfoot" => parent_ends,
+        "tr" => next_is(&["tr"]) || parent_ends,
+        "td" | "th" => next_is(&["td", "th"]) || parent_ends,
+        "head" => next_open.is_some(),
+        "body" | "html" => !matches!(next, Some(TokenHtml::Comment { .. })),
+        _ => false,
+    };
+}
+
+// elements whose start tag closes an open p
+const HTML_MINIFY_P_CLOSERS: [&str; 28] = [
+    "address", "article", "aside", "blockquote", "details", "dialog", "div", "dl", "fieldset", "figcaption", "figure",
+    "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hgroup", "hr", "main", "menu", "nav", "ol", "p", "pre",
+];
+
+// a p that is the last thing in one of these has to keep its end tag
+const HTML_MINIFY_P_KEEPERS: [&str; 7] = ["a", "audio", "del", "ins", "map", "noscript", "video"];
+
+const HTML_MINIFY_BOOLEAN_ATTRIBUTES: [&str; 25] = [
+    "allowfullscreen", "async", "autofocus", "autoplay", "checked", "controls", "default", "defer", "disabled",
+    "formnovalidate", "hidden", "inert", "ismap", "itemscope", "loop", "multiple", "muted", "nomodule", "novalidate",
+    "open", "playsinline", "readonly", "required", "reversed", "selected",
+];
+
+// elements that start on a line of their own, or never render at all, so whitespace next to them doesn't show
+const HTML_MINIFY_BLOCK_ELEMENTS: [&str; 63] = [
+    "address", "article", "aside", "base", "blockquote", "body", "caption", "center", "col", "colgroup", "dd", "details",
+    "dialog", "dir", "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form", "frame", "frameset", "h1",
+    "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup", "hr", "html", "legend", "li", "link", "main", "menu",
+    "meta", "nav", "noscript", "ol", "optgroup", "option", "p", "pre", "script", "section", "style", "summary", "table",
+    "tbody", "td", "template", "tfoot", "th", "thead", "title", "tr", "ul", "xmp",
+];
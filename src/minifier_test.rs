@@ -0,0 +1,36 @@
+use crate::*;
+
+#[test]
+fn test_html_minify() {
+    let html = "<!DOCTYPE html>\n<html>\n  <body>\n    <!-- nav -->\n    <ul class=\"menu\">\n      <li><a href=\"/\">Home</a></li>\n      <li>About   <b>us</b> </li>\n    </ul>\n  </body>\n</html>\n";
+    assert!(html_minify(html) == "<!DOCTYPE html><html><body><ul class=menu><li><a href=\"/\">Home</a><li>About <b>us</b></ul>");
+}
+
+#[test]
+fn test_html_minify_attributes() {
+    assert!(html_minify("<input type=\"checkbox\" checked=\"checked\" disabled=\"\" value=\"a b\">") == "<input type=checkbox checked disabled value=\"a b\">");
+    assert!(html_minify("<img src='x.png' alt='' title='say \"hi\"'/>") == "<img src=x.png alt=\"\" title='say \"hi\"'>");
+    assert!(html_minify("<a href=\"/docs/\">x</a><svg><path d=\"M0\"/></svg>") == "<a href=\"/docs/\">x</a><svg><path d=M0 /></svg>");
+    // entities are kept as written
+    assert!(html_minify("<a title=\"&quot;a&quot;\">x</a>") == "<a title=&quot;a&quot;>x</a>");
+}
+
+#[test]
+fn test_html_minify_optional_end_tags() {
+    assert!(html_minify("<table><tr><td>1</td><td>2</td></tr></table>") == "<table><tr><td>1<td>2</table>");
+    assert!(html_minify("<div><p>1</p><p>2</p></div>") == "<div><p>1<p>2</div>");
+    // a p at the end of an a keeps its end tag, and so does one followed by text
+    assert!(html_minify("<a href=#><p>1</p></a><div><p>2</p>3</div>") == "<a href=#><p>1</p></a><div><p>2</p>3</div>");
+    assert!(html_minify("<select><option>a</option><option>b</option></select>") == "<select><option>a<option>b</select>");
+}
+
+#[test]
+fn test_html_minify_options() {
+    let html = "<div>\n  <!-- keep -->\n  <pre>  a\n  b </pre>\n  <script> let a = 1; </script>\n</div>";
+    assert!(html_minify(html) == "<div><pre>  a\n  b </pre><script> let a = 1; </script></div>");
+    let trim = |_: &str, body: &str| body.trim().to_string();
+    let options = HtmlMinifyOptions { remove_comments: false, prelike_hook: Some(&trim), ..HtmlMinifyOptions::default() };
+    assert!(html_minify_with_options(html, options) == "<div><!-- keep --><pre>a\n  b</pre><script>let a = 1;</script></div>");
+    let options = HtmlMinifyOptions { remove_optional_end_tags: false, remove_attribute_quotes: false, shorten_boolean_attributes: false, ..HtmlMinifyOptions::default() };
+    assert!(html_minify_with_options("<ul><li hidden=\"\">a</li></ul>", options) == "<ul><li hidden=\"\">a</li></ul>");
+}
@@ -0,0 +1,605 @@
+use crate::dom::{DomNode, DomNodeKind};
+use crate::error::RipError;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CssCombinator {
+    Descendant,
+    Child,
+    NextSibling,
+    SubsequentSibling,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CssAttributeOp {
+    Exists,
+    Equals,
+    Prefix,
+    Suffix,
+    Contains,
+    Includes,
+    DashMatch,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CssAttributeSelector {
+    pub name: String,
+    pub op: CssAttributeOp,
+    pub value: String,
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CssPseudoClass {
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    // an+b
+    NthChild(i64, i64),
+    NthLastChild(i64, i64),
+    NthOfType(i64, i64),
+    NthLastOfType(i64, i64),
+    Not(CssSelector),
+    Empty,
+    Root,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CssCompoundSelector {
+    // None matches any tag, the same as '*'
+    pub tag_name: Option<String>,
+    pub ids: Vec<String>,
+    pub classes: Vec<String>,
+    pub attributes: Vec<CssAttributeSelector>,
+    pub pseudo_classes: Vec<CssPseudoClass>,
+}
+
+// compound selectors from left to right, each paired with the combinator that joins it to the one before it,
+// the first combinator is never used
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CssComplexSelector {
+    pub parts: Vec<(CssCombinator, CssCompoundSelector)>,
+}
+
+// a comma separated selector list, an element matches if any of the selectors match it
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CssSelector {
+    pub selectors: Vec<CssComplexSelector>,
+}
+
+// the view of a node the matching code works through, so a DomNode and a node in a DomTree are matched
+// by the same rules. a node is a cheap handle, like a reference
+pub(crate) trait CssNode: Copy {
+    fn kind(&self) -> &DomNodeKind;
+    fn tag_name(&self) -> &str;
+    fn text(&self) -> &str;
+    fn attribute(&self, name: &str) -> Option<&str>;
+    fn children(&self) -> impl Iterator<Item = Self>;
+}
+
+impl CssNode for &DomNode {
+
+    fn kind(&self) -> &DomNodeKind {
+        return &self.kind;
+    }
+
+    fn tag_name(&self) -> &str {
+        return &self.tag_name;
+    }
+
+    fn text(&self) -> &str {
+        return &self.text;
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        return self.attributes.iter().find(|attr| attr.name == name).map(|attr| attr.value.as_str());
+    }
+
+    fn children(&self) -> impl Iterator<Item = Self> {
+        return self.children.iter();
+    }
+
+}
+
+impl DomNode {
+
+    // the first element below this node that matches the selector, in document order
+    pub fn query_selector(&self, selector: &str) -> Result<Option<&DomNode>, RipError> {
+        return Ok(css_query(self, selector, true)?.into_iter().next());
+    }
+
+    // every element below this node that matches the selector, in document order,
+    // combinators can only look as far up as this node
+    pub fn query_selector_all(&self, selector: &str) -> Result<Vec<&DomNode>, RipError> {
+        return css_query(self, selector, false);
+    }
+
+}
+
+pub(crate) fn css_query<N: CssNode>(top: N, selector: &str, first_only: bool) -> Result<Vec<N>, RipError> {
+    let selector = css_selector_from_str(selector)?;
+    let mut found: Vec<N> = vec![];
+    let mut path: Vec<(N, usize)> = vec![(top, 0)];
+    css_collect_matches(&selector, &mut path, &mut found, first_only);
+    return Ok(found);
+}
+
+fn css_collect_matches<N: CssNode>(selector: &CssSelector, path: &mut Vec<(N, usize)>, found: &mut Vec<N>, first_only: bool) -> bool {
+    let node = path[path.len() - 1].0;
+    for (i, child) in node.children().enumerate() {
+        if *child.kind() != DomNodeKind::Element {
+            continue;
+        }
+        path.push((child, i));
+        if css_selector_matches(selector, path) {
+            found.push(child);
+            if first_only {
+                path.pop();
+                return true;
+            }
+        }
+        let done = css_collect_matches(selector, path, found, first_only);
+        path.pop();
+        if done {
+            return true;
+        }
+    }
+    return false;
+}
+
+// path runs from the top of the search down to the element being matched,
+// each entry holds a node and its index among its parent's children
+fn css_selector_matches<N: CssNode>(selector: &CssSelector, path: &[(N, usize)]) -> bool {
+    return selector.selectors.iter().any(|complex| css_complex_matches(complex, complex.parts.len() - 1, path));
+}
+
+fn css_complex_matches<N: CssNode>(complex: &CssComplexSelector, index: usize, path: &[(N, usize)]) -> bool {
+    let (combinator, compound) = &complex.parts[index];
+    if !css_compound_matches(compound, path) {
+        return false;
+    }
+    if index == 0 {
+        return true;
+    }
+    let parent_path = &path[..path.len() - 1];
+    match combinator {
+        CssCombinator::Child => {
+            return css_parent_is_element(parent_path) && css_complex_matches(complex, index - 1, parent_path);
+        },
+        CssCombinator::Descendant => {
+            let mut ancestor_path = parent_path;
+            while css_parent_is_element(ancestor_path) {
+                if css_complex_matches(complex, index - 1, ancestor_path) {
+                    return true;
+                }
+                ancestor_path = &ancestor_path[..ancestor_path.len() - 1];
+            }
+            return false;
+        },
+        CssCombinator::NextSibling => {
+            return match css_element_siblings_before(path).last() {
+                Some(sibling) => css_complex_matches(complex, index - 1, &css_sibling_path(parent_path, *sibling)),
+                None => false,
+            };
+        },
+        CssCombinator::SubsequentSibling => {
+            return css_element_siblings_before(path).into_iter().rev().any(|sibling| {
+                return css_complex_matches(complex, index - 1, &css_sibling_path(parent_path, sibling));
+            });
+        },
+    }
+}
+
+fn css_parent_is_element<N: CssNode>(path: &[(N, usize)]) -> bool {
+    return match path.last() {
+        Some((node, _)) => *node.kind() == DomNodeKind::Element,
+        None => false,
+    };
+}
+
+fn css_sibling_path<N: CssNode>(parent_path: &[(N, usize)], sibling: (N, usize)) -> Vec<(N, usize)> {
+    let mut path = parent_path.to_vec();
+    path.push(sibling);
+    return path;
+}
+
+// the element siblings that come before the last node in the path, in document order
+fn css_element_siblings_before<N: CssNode>(path: &[(N, usize)]) -> Vec<(N, usize)> {
+    if path.len() < 2 {
+        return vec![];
+    }
+    let (_, index) = path[path.len() - 1];
+    let parent = path[path.len() - 2].0;
+    return parent.children().take(index).enumerate().filter(|(_, sibling)| *sibling.kind() == DomNodeKind::Element).map(|(i, sibling)| (sibling, i)).collect();
+}
+
+fn css_compound_matches<N: CssNode>(compound: &CssCompoundSelector, path: &[(N, usize)]) -> bool {
+    let node = path[path.len() - 1].0;
+    if let Some(tag_name) = &compound.tag_name {
+        if !node.tag_name().eq_ignore_ascii_case(tag_name) {
+            return false;
+        }
+    }
+    for id in &compound.ids {
+        if node.attribute("id") != Some(id.as_str()) {
+            return false;
+        }
+    }
+    for class in &compound.classes {
+        let has_class = match node.attribute("class") {
+            Some(value) => value.split_whitespace().any(|c| c == class),
+            None => false,
+        };
+        if !has_class {
+            return false;
+        }
+    }
+    for attr in &compound.attributes {
+        if !css_attribute_matches(attr, node) {
+            return false;
+        }
+    }
+    for pseudo in &compound.pseudo_classes {
+        if !css_pseudo_class_matches(pseudo, path) {
+            return false;
+        }
+    }
+    return true;
+}
+
+fn css_attribute_matches<N: CssNode>(selector: &CssAttributeSelector, node: N) -> bool {
+    let value = match node.attribute(&selector.name) {
+        Some(value) => value,
+        None => return false,
+    };
+    let (value, expected) = if selector.case_insensitive {
+        (value.to_lowercase(), selector.value.to_lowercase())
+    } else {
+        (value.to_string(), selector.value.clone())
+    };
+    match selector.op {
+        CssAttributeOp::Exists => return true,
+        CssAttributeOp::Equals => return value == expected,
+        CssAttributeOp::Prefix => return !expected.is_empty() && value.starts_with(&expected),
+        CssAttributeOp::Suffix => return !expected.is_empty() && value.ends_with(&expected),
+        CssAttributeOp::Contains => return !expected.is_empty() && value.contains(&expected),
+        CssAttributeOp::Includes => return value.split_whitespace().any(|word| word == expected),
+        CssAttributeOp::DashMatch => return value == expected || value.starts_with(&format!("{}-", expected)),
+    }
+}
+
+fn css_pseudo_class_matches<N: CssNode>(pseudo: &CssPseudoClass, path: &[(N, usize)]) -> bool {
+    let node = path[path.len() - 1].0;
+    let (_, index) = path[path.len() - 1];
+    let siblings: Vec<N> = if path.len() < 2 {
+        // the node the search started from, we can't see its siblings
+        vec![node]
+    } else {
+        path[path.len() - 2].0.children().filter(|sibling| *sibling.kind() == DomNodeKind::Element).collect()
+    };
+    // 1-based positions among element siblings, counted from the front and from the back
+    let before = if path.len() < 2 {
+        0
+    } else {
+        path[path.len() - 2].0.children().take(index).filter(|sibling| *sibling.kind() == DomNodeKind::Element).count()
+    };
+    let position = before as i64 + 1;
+    let position_last = (siblings.len() - before) as i64;
+    let same_type: Vec<usize> = siblings.iter().enumerate().filter(|(_, sibling)| sibling.tag_name() == node.tag_name()).map(|(i, _)| i).collect();
+    let type_before = same_type.iter().filter(|i| **i < before).count();
+    let type_position = type_before as i64 + 1;
+    let type_position_last = (same_type.len() - type_before) as i64;
+    match pseudo {
+        CssPseudoClass::FirstChild => return position == 1,
+        CssPseudoClass::LastChild => return position_last == 1,
+        CssPseudoClass::OnlyChild => return siblings.len() == 1,
+        CssPseudoClass::FirstOfType => return type_position == 1,
+        CssPseudoClass::LastOfType => return type_position_last == 1,
+        CssPseudoClass::OnlyOfType => return same_type.len() == 1,
+        CssPseudoClass::NthChild(a, b) => return css_nth_matches(*a, *b, position),
+        CssPseudoClass::NthLastChild(a, b) => return css_nth_matches(*a, *b, position_last),
+        CssPseudoClass::NthOfType(a, b) => return css_nth_matches(*a, *b, type_position),
+        CssPseudoClass::NthLastOfType(a, b) => return css_nth_matches(*a, *b, type_position_last),
+        CssPseudoClass::Not(selector) => return !css_selector_matches(selector, path),
+        CssPseudoClass::Empty => {
+            return node.children().all(|child| *child.kind() == DomNodeKind::Comment || (*child.kind() == DomNodeKind::Text && child.text().is_empty()));
+        },
+        CssPseudoClass::Root => {
+            return path.len() >= 2 && *path[path.len() - 2].0.kind() == DomNodeKind::Root;
+        },
+    }
+}
+
+// true when position == a*n + b for some n >= 0
+fn css_nth_matches(a: i64, b: i64, position: i64) -> bool {
+    if a == 0 {
+        return position == b;
+    }
+    let diff = position - b;
+    return diff % a == 0 && diff / a >= 0;
+}
+
+pub fn css_selector_from_str(selector: &str) -> Result<CssSelector, RipError> {
+    let chars: Vec<char> = selector.chars().collect();
+    let mut i = 0;
+    let parsed = css_parse_selector_list(&chars, &mut i, selector)?;
+    if i != chars.len() {
+        return Err(css_invalid(selector));
+    }
+    return Ok(parsed);
+}
+
+fn css_invalid(selector: &str) -> RipError {
+    return RipError::InvalidSelector { selector: selector.to_string(), span: None };
+}
+
+fn css_skip_whitespace(chars: &[char], i: &mut usize) -> bool {
+    let start = *i;
+    while *i < chars.len() && chars[*i].is_whitespace() {
+        *i += 1;
+    }
+    return *i > start;
+}
+
+// stops at the end of input or at an unmatched ')', which closes a ':not('
+fn css_parse_selector_list(chars: &[char], i: &mut usize, src: &str) -> Result<CssSelector, RipError> {
+    let mut selectors: Vec<CssComplexSelector> = vec![];
+    loop {
+        css_skip_whitespace(chars, i);
+        selectors.push(css_parse_complex(chars, i, src)?);
+        css_skip_whitespace(chars, i);
+        if *i < chars.len() && chars[*i] == ',' {
+            *i += 1;
+            continue;
+        }
+        break;
+    }
+    return Ok(CssSelector { selectors });
+}
+
+fn css_parse_complex(chars: &[char], i: &mut usize, src: &str) -> Result<CssComplexSelector, RipError> {
+    let mut parts: Vec<(CssCombinator, CssCompoundSelector)> = vec![];
+    let mut combinator = CssCombinator::Descendant;
+    loop {
+        let compound = css_parse_compound(chars, i, src)?;
+        parts.push((combinator, compound));
+        let had_whitespace = css_skip_whitespace(chars, i);
+        if *i >= chars.len() || chars[*i] == ',' || chars[*i] == ')' {
+            break;
+        }
+        combinator = match chars[*i] {
+            '>' => CssCombinator::Child,
+            '+' => CssCombinator::NextSibling,
+            '~' => CssCombinator::SubsequentSibling,
+            _ => {
+                if !had_whitespace {
+                    return Err(css_invalid(src));
+                }
+                CssCombinator::Descendant
+            },
+        };
+        if combinator != CssCombinator::Descendant {
+            *i += 1;
+            css_skip_whitespace(chars, i);
+        }
+    }
+    return Ok(CssComplexSelector { parts });
+}
+
+fn css_is_ident_char(c: char) -> bool {
+    return c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii();
+}
+
+fn css_parse_ident(chars: &[char], i: &mut usize, src: &str) -> Result<String, RipError> {
+    let start = *i;
+    while *i < chars.len() && css_is_ident_char(chars[*i]) {
+        *i += 1;
+    }
+    if *i == start {
+        return Err(css_invalid(src));
+    }
+    return Ok(chars[start..*i].iter().collect());
+}
+
+fn css_parse_compound(chars: &[char], i: &mut usize, src: &str) -> Result<CssCompoundSelector, RipError> {
+    let mut compound = CssCompoundSelector::default();
+    let start = *i;
+    if *i < chars.len() && chars[*i] == '*' {
+        *i += 1;
+    } else if *i < chars.len() && css_is_ident_char(chars[*i]) {
+        compound.tag_name = Some(css_parse_ident(chars, i, src)?.to_lowercase());
+    }
+    while *i < chars.len() {
+        match chars[*i] {
+            '#' => {
+                *i += 1;
+                compound.ids.push(css_parse_ident(chars, i, src)?);
+            },
+            '.' => {
+                *i += 1;
+                compound.classes.push(css_parse_ident(chars, i, src)?);
+            },
+            '[' => {
+                *i += 1;
+                compound.attributes.push(css_parse_attribute(chars, i, src)?);
+            },
+            ':' => {
+                *i += 1;
+                compound.pseudo_classes.push(css_parse_pseudo_class(chars, i, src)?);
+            },
+            _ => {
+                break;
+            },
+        }
+    }
+    if *i == start {
+        return Err(css_invalid(src));
+    }
+    return Ok(compound);
+}
+
+// i sits just past the '['
+fn css_parse_attribute(chars: &[char], i: &mut usize, src: &str) -> Result<CssAttributeSelector, RipError> {
+    css_skip_whitespace(chars, i);
+    let name = css_parse_ident(chars, i, src)?.to_lowercase();
+    css_skip_whitespace(chars, i);
+    if *i >= chars.len() {
+        return Err(css_invalid(src));
+    }
+    if chars[*i] == ']' {
+        *i += 1;
+        return Ok(CssAttributeSelector { name, op: CssAttributeOp::Exists, value: String::new(), case_insensitive: false });
+    }
+    let op = match chars[*i] {
+        '=' => CssAttributeOp::Equals,
+        '^' => CssAttributeOp::Prefix,
+        '$' => CssAttributeOp::Suffix,
+        '*' => CssAttributeOp::Contains,
+        '~' => CssAttributeOp::Includes,
+        '|' => CssAttributeOp::DashMatch,
+        _ => return Err(css_invalid(src)),
+    };
+    *i += 1;
+    if op != CssAttributeOp::Equals {
+        if *i >= chars.len() || chars[*i] != '=' {
+            return Err(css_invalid(src));
+        }
+        *i += 1;
+    }
+    css_skip_whitespace(chars, i);
+    if *i >= chars.len() {
+        return Err(css_invalid(src));
+    }
+    let value = if chars[*i] == '"' || chars[*i] == '\'' {
+        let quote = chars[*i];
+        *i += 1;
+        let start = *i;
+        while *i < chars.len() && chars[*i] != quote {
+            *i += 1;
+        }
+        if *i >= chars.len() {
+            return Err(css_invalid(src));
+        }
+        let value: String = chars[start..*i].iter().collect();
+        *i += 1;
+        value
+    } else {
+        css_parse_ident(chars, i, src)?
+    };
+    css_skip_whitespace(chars, i);
+    let mut case_insensitive = false;
+    if *i < chars.len() && (chars[*i] == 'i' || chars[*i] == 'I') {
+        case_insensitive = true;
+        *i += 1;
+        css_skip_whitespace(chars, i);
+    } else if *i < chars.len() && (chars[*i] == 's' || chars[*i] == 'S') {
+        *i += 1;
+        css_skip_whitespace(chars, i);
+    }
+    if *i >= chars.len() || chars[*i] != ']' {
+        return Err(css_invalid(src));
+    }
+    *i += 1;
+    return Ok(CssAttributeSelector { name, op, value, case_insensitive });
+}
+
+// i sits just past the ':'
+fn css_parse_pseudo_class(chars: &[char], i: &mut usize, src: &str) -> Result<CssPseudoClass, RipError> {
+    let name = css_parse_ident(chars, i, src)?.to_lowercase();
+    let simple = match name.as_str() {
+        "first-child" => Some(CssPseudoClass::FirstChild),
+        "last-child" => Some(CssPseudoClass::LastChild),
+        "only-child" => Some(CssPseudoClass::OnlyChild),
+        "first-of-type" => Some(CssPseudoClass::FirstOfType),
+        "last-of-type" => Some(CssPseudoClass::LastOfType),
+        "only-of-type" => Some(CssPseudoClass::OnlyOfType),
+        "empty" => Some(CssPseudoClass::Empty),
+        "root" => Some(CssPseudoClass::Root),
+        _ => None,
+    };
+    if let Some(pseudo) = simple {
+        return Ok(pseudo);
+    }
+    if *i >= chars.len() || chars[*i] != '(' {
+        return Err(css_invalid(src));
+    }
+    *i += 1;
+    if name == "not" {
+        let selector = css_parse_selector_list(chars, i, src)?;
+        if *i >= chars.len() || chars[*i] != ')' {
+            return Err(css_invalid(src));
+        }
+        *i += 1;
+        return Ok(CssPseudoClass::Not(selector));
+    }
+    let start = *i;
+    while *i < chars.len() && chars[*i] != ')' {
+        *i += 1;
+    }
+    if *i >= chars.len() {
+        return Err(css_invalid(src));
+    }
+    let argument: String = chars[start..*i].iter().collect();
+    *i += 1;
+    let (a, b) = match css_parse_nth(&argument) {
+        Some(nth) => nth,
+        None => return Err(css_invalid(src)),
+    };
+    match name.as_str() {
+        "nth-child" => return Ok(CssPseudoClass::NthChild(a, b)),
+        "nth-last-child" => return Ok(CssPseudoClass::NthLastChild(a, b)),
+        "nth-of-type" => return Ok(CssPseudoClass::NthOfType(a, b)),
+        "nth-last-of-type" => return Ok(CssPseudoClass::NthLastOfType(a, b)),
+        _ => return Err(css_invalid(src)),
+    }
+}
+
+// parses the an+b syntax used by the nth pseudo classes, along with 'odd' and 'even'
+pub fn css_parse_nth(argument: &str) -> Option<(i64, i64)> {
+    let argument: String = argument.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+    if argument == "odd" {
+        return Some((2, 1));
+    }
+    if argument == "even" {
+        return Some((2, 0));
+    }
+    let (a_part, b_part) = match argument.find('n') {
+        Some(n) => (Some(&argument[..n]), &argument[n + 1..]),
+        None => (None, argument.as_str()),
+    };
+    let a = match a_part {
+        None => 0,
+        Some("") | Some("+") => 1,
+        Some("-") => -1,
+        Some(a) => css_parse_nth_integer(a)?,
+    };
+    let b = if b_part.is_empty() {
+        0
+    } else if a_part.is_some() && !(b_part.starts_with('+') || b_part.starts_with('-')) {
+        // with an 'n' present the b part needs its sign, '2n1' isn't valid
+        return None;
+    } else {
+        css_parse_nth_integer(b_part)?
+    };
+    return Some((a, b));
+}
+
+// a signed integer clamped to the range of an i32 the way browsers clamp it, which also keeps the
+// a*n+b arithmetic in css_nth_matches from overflowing
+fn css_parse_nth_integer(s: &str) -> Option<i64> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let limit = i32::MAX as i64 + 1;
+    let value = digits.bytes().fold(0, |value: i64, digit| (value * 10 + (digit - b'0') as i64).min(limit));
+    if negative {
+        return Some(-value);
+    }
+    return Some(value.min(i32::MAX as i64));
+}
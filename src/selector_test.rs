@@ -0,0 +1,117 @@
+use crate::dom::*;
+use crate::error::RipError;
+use crate::selector::*;
+
+const PAGE: &str = r#"<div id="main" class="page wide"><h1 class="title">Title</h1><ul class="items"><li class="a">One</li><li class="b active">Two</li><li lang="en-US">Three</li><li data-x="foo bar">Four</li></ul><p>Intro <a href="https://example.com/x.pdf" rel="nofollow">link</a></p><p class="last">End</p></div>"#;
+
+fn tag_names(nodes: Vec<&DomNode>) -> Vec<String> {
+    return nodes.iter().map(|node| node.tag_name.clone()).collect();
+}
+
+fn texts(nodes: Vec<&DomNode>) -> Vec<String> {
+    return nodes.iter().map(|node| node.text.clone()).collect();
+}
+
+#[test]
+fn test_query_selector_simple() {
+    let root = dom_tree_from_html(PAGE).unwrap();
+    assert!(tag_names(root.query_selector_all("h1").unwrap()) == vec!["h1"]);
+    assert!(tag_names(root.query_selector_all("H1").unwrap()) == vec!["h1"]);
+    assert!(texts(root.query_selector_all("li").unwrap()) == vec!["One", "Two", "Three", "Four"]);
+    assert!(tag_names(root.query_selector_all("#main").unwrap()) == vec!["div"]);
+    assert!(texts(root.query_selector_all(".active").unwrap()) == vec!["Two"]);
+    assert!(texts(root.query_selector_all("li.b.active").unwrap()) == vec!["Two"]);
+    assert!(tag_names(root.query_selector_all("div.page.wide#main").unwrap()) == vec!["div"]);
+    assert!(root.query_selector_all("*").unwrap().len() == 10);
+    assert!(root.query_selector_all(".missing").unwrap().is_empty());
+    assert!(root.query_selector("p").unwrap().unwrap().text == "Intro link");
+    assert!(root.query_selector("table").unwrap().is_none());
+}
+
+#[test]
+fn test_query_selector_attributes() {
+    let root = dom_tree_from_html(PAGE).unwrap();
+    assert!(texts(root.query_selector_all("[lang]").unwrap()) == vec!["Three"]);
+    assert!(texts(root.query_selector_all("[rel=nofollow]").unwrap()) == vec!["link"]);
+    assert!(texts(root.query_selector_all("a[href^='https://']").unwrap()) == vec!["link"]);
+    assert!(texts(root.query_selector_all("a[href$=\".pdf\"]").unwrap()) == vec!["link"]);
+    assert!(texts(root.query_selector_all("[href*=example]").unwrap()) == vec!["link"]);
+    assert!(texts(root.query_selector_all("[data-x~=bar]").unwrap()) == vec!["Four"]);
+    assert!(root.query_selector_all("[data-x~=ba]").unwrap().is_empty());
+    assert!(texts(root.query_selector_all("[lang|=en]").unwrap()) == vec!["Three"]);
+    assert!(root.query_selector_all("[lang|=e]").unwrap().is_empty());
+    assert!(texts(root.query_selector_all("[rel=NOFOLLOW i]").unwrap()) == vec!["link"]);
+    assert!(root.query_selector_all("[rel=NOFOLLOW]").unwrap().is_empty());
+    assert!(root.query_selector_all("[href^='']").unwrap().is_empty());
+}
+
+#[test]
+fn test_query_selector_combinators() {
+    let root = dom_tree_from_html(PAGE).unwrap();
+    assert!(texts(root.query_selector_all("div a").unwrap()) == vec!["link"]);
+    assert!(texts(root.query_selector_all("#main > p").unwrap()) == vec!["Intro link", "End"]);
+    assert!(root.query_selector_all("#main > a").unwrap().is_empty());
+    assert!(texts(root.query_selector_all("li.a + li").unwrap()) == vec!["Two"]);
+    assert!(texts(root.query_selector_all("li.a ~ li").unwrap()) == vec!["Two", "Three", "Four"]);
+    assert!(texts(root.query_selector_all("h1 ~ p").unwrap()) == vec!["Intro link", "End"]);
+    assert!(texts(root.query_selector_all("ul + p").unwrap()) == vec!["Intro link"]);
+    assert!(texts(root.query_selector_all("div > ul li.b").unwrap()) == vec!["Two"]);
+    assert!(texts(root.query_selector_all("div>ul>li[lang]").unwrap()) == vec!["Three"]);
+    assert!(texts(root.query_selector_all("h1, .last").unwrap()) == vec!["Title", "End"]);
+    assert!(texts(root.query_selector_all(".last , h1").unwrap()) == vec!["Title", "End"]);
+    // searching below a node still lets the node itself satisfy a combinator
+    let ul = root.query_selector("ul").unwrap().unwrap();
+    assert!(texts(ul.query_selector_all("ul > li.a").unwrap()) == vec!["One"]);
+    assert!(ul.query_selector_all("div li").unwrap().is_empty());
+}
+
+#[test]
+fn test_query_selector_pseudo_classes() {
+    let root = dom_tree_from_html(PAGE).unwrap();
+    assert!(texts(root.query_selector_all("li:first-child").unwrap()) == vec!["One"]);
+    assert!(texts(root.query_selector_all("li:last-child").unwrap()) == vec!["Four"]);
+    assert!(texts(root.query_selector_all("li:nth-child(2)").unwrap()) == vec!["Two"]);
+    assert!(texts(root.query_selector_all("li:nth-child(odd)").unwrap()) == vec!["One", "Three"]);
+    assert!(texts(root.query_selector_all("li:nth-child(even)").unwrap()) == vec!["Two", "Four"]);
+    assert!(texts(root.query_selector_all("li:nth-child(2n+1)").unwrap()) == vec!["One", "Three"]);
+    assert!(texts(root.query_selector_all("li:nth-child(-n+2)").unwrap()) == vec!["One", "Two"]);
+    assert!(texts(root.query_selector_all("li:nth-last-child(1)").unwrap()) == vec!["Four"]);
+    assert!(root.query_selector_all("li:nth-child(-n-9223372036854775808)").unwrap().is_empty());
+    assert!(texts(root.query_selector_all("li:nth-child(9223372036854775807n+9223372036854775807)").unwrap()).is_empty());
+    assert!(texts(root.query_selector_all("p:first-of-type").unwrap()) == vec!["Intro link"]);
+    assert!(texts(root.query_selector_all("p:last-of-type").unwrap()) == vec!["End"]);
+    assert!(texts(root.query_selector_all("p:nth-of-type(2)").unwrap()) == vec!["End"]);
+    assert!(texts(root.query_selector_all("#main > :only-of-type").unwrap()) == vec!["Title", "OneTwoThreeFour"]);
+    assert!(texts(root.query_selector_all("a:only-child").unwrap()) == vec!["link"]);
+    assert!(texts(root.query_selector_all("li:not(.a):not([lang])").unwrap()) == vec!["Two", "Four"]);
+    assert!(texts(root.query_selector_all("li:not(.a, .b)").unwrap()) == vec!["Three", "Four"]);
+    assert!(tag_names(root.query_selector_all(":root").unwrap()) == vec!["div"]);
+    let root = dom_tree_from_html("<div><p></p><p><!-- c --></p><p> </p></div>").unwrap();
+    assert!(root.query_selector_all("p:empty").unwrap().len() == 2);
+}
+
+#[test]
+fn test_css_selector_from_str() {
+    let selector = css_selector_from_str("ul > li.a:first-child").unwrap();
+    assert!(selector.selectors.len() == 1);
+    let parts = &selector.selectors[0].parts;
+    assert!(parts.len() == 2);
+    assert!(parts[0].1.tag_name == Some("ul".to_string()));
+    assert!(parts[1].0 == CssCombinator::Child);
+    assert!(parts[1].1.classes == vec!["a".to_string()]);
+    assert!(parts[1].1.pseudo_classes == vec![CssPseudoClass::FirstChild]);
+    assert!(css_parse_nth("3") == Some((0, 3)));
+    assert!(css_parse_nth("n") == Some((1, 0)));
+    assert!(css_parse_nth(" -2n + 3 ") == Some((-2, 3)));
+    // out of range values are clamped like browsers clamp them
+    assert!(css_parse_nth("-n-9223372036854775808") == Some((-1, i32::MIN as i64)));
+    assert!(css_parse_nth("99999999999999999999999n") == Some((i32::MAX as i64, 0)));
+    assert!(css_parse_nth("2n+-1").is_none());
+    assert!(css_parse_nth("2n1").is_none());
+    assert!(css_parse_nth("x").is_none());
+    for bad in ["", "div >", "> div", "div,", "[a", "[a=]", ".", "#", "a:nth-child(x)", "a:hover", "a:not(b", "div)", "a[b^c]"] {
+        assert!(css_selector_from_str(bad) == Err(RipError::InvalidSelector { selector: bad.to_string(), span: None }));
+    }
+    let root = dom_tree_from_html("<p>x</p>").unwrap();
+    assert!(root.query_selector_all("p[").is_err());
+}
@@ -0,0 +1,189 @@
+use crate::dom::{DomNamespace, DomNode, DomNodeKind};
+use crate::entities::{html_encode_attribute, html_encode_text};
+use crate::html_utils::{is_html_raw_text_element, is_html_void_element, HtmlAttribute};
+
+// the view of a node the serializer works through, so a DomNode and a node in a DomTree are written
+// out by the same rules. a node is a cheap handle, like a reference
+pub(crate) trait HtmlNode: Copy {
+    fn kind(&self) -> &DomNodeKind;
+    fn tag_name(&self) -> &str;
+    fn namespace(&self) -> DomNamespace;
+    fn attributes(&self) -> &[HtmlAttribute];
+    // the text of a text node or the body of a comment
+    fn text(&self) -> &str;
+    // the declaration a doctype was read from
+    fn doctype(&self) -> &str;
+    fn children(&self) -> impl Iterator<Item = Self>;
+}
+
+impl HtmlNode for &DomNode {
+
+    fn kind(&self) -> &DomNodeKind {
+        return &self.kind;
+    }
+
+    fn tag_name(&self) -> &str {
+        return &self.tag_name;
+    }
+
+    fn namespace(&self) -> DomNamespace {
+        return self.namespace;
+    }
+
+    fn attributes(&self) -> &[HtmlAttribute] {
+        return &self.attributes;
+    }
+
+    fn text(&self) -> &str {
+        return &self.text;
+    }
+
+    fn doctype(&self) -> &str {
+        return &self.outer_html;
+    }
+
+    fn children(&self) -> impl Iterator<Item = Self> {
+        return self.children.iter();
+    }
+
+}
+
+impl DomNode {
+
+    // writes the node back out as markup, built from the tree rather than copied from outer_html,
+    // so any changes made to the tree show up in the result. <noscript> text is escaped, the way a
+    // document parsed with scripting off holds it
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        html_write_node(self, &mut out, false, false);
+        return out;
+    }
+
+    // the markup of the node's children without the node itself
+    pub fn inner_html(&self) -> String {
+        let mut out = String::new();
+        let raw = html_is_raw_text_parent(self, false);
+        for child in &self.children {
+            html_write_node(child, &mut out, raw, false);
+        }
+        return out;
+    }
+
+}
+
+// the markup for a node and everything under it. raw is set when the node's parent is a raw text element
+pub(crate) fn html_node_to_html<N: HtmlNode>(node: N, raw: bool) -> String {
+    let mut out = String::new();
+    html_write_node(node, &mut out, raw, false);
+    return out;
+}
+
+// sets outer_html on a node and everything under it to the markup to_html writes for them, for builders that
+// put a tree together themselves instead of copying it out of a document. raw is set when the node's parent is
+// a raw text element. scripting is set for trees parsed with scripting on, where <noscript> holds raw text too
+pub(crate) fn dom_fill_outer_html(node: &mut DomNode, raw: bool, scripting: bool) {
+    let mut out = String::new();
+    dom_write_outer_html(node, &mut out, raw, scripting);
+}
+
+fn dom_write_outer_html(node: &mut DomNode, out: &mut String, raw: bool, scripting: bool) {
+    let start = out.len();
+    match node.kind {
+        DomNodeKind::Root => {
+            for child in &mut node.children {
+                dom_write_outer_html(child, out, raw, scripting);
+            }
+        },
+        DomNodeKind::Element => {
+            html_write_start_tag(&*node, out);
+            if html_is_void(&*node) {
+                node.outer_html = out[start..].to_string();
+                return;
+            }
+            let raw = html_is_raw_text_parent(&*node, scripting);
+            for child in &mut node.children {
+                dom_write_outer_html(child, out, raw, scripting);
+            }
+            html_write_end_tag(&*node, out);
+        },
+        DomNodeKind::Doctype => {
+            // the doctype keeps the declaration it was read from
+            html_write_node(&*node, out, raw, scripting);
+            return;
+        },
+        DomNodeKind::Text | DomNodeKind::Comment => {
+            html_write_node(&*node, out, raw, scripting);
+        },
+    }
+    node.outer_html = out[start..].to_string();
+}
+
+// raw is set while inside a raw text element, whose text was never decoded and must not be escaped
+fn html_write_node<N: HtmlNode>(node: N, out: &mut String, raw: bool, scripting: bool) {
+    match node.kind() {
+        DomNodeKind::Root => {
+            for child in node.children() {
+                html_write_node(child, out, raw, scripting);
+            }
+        },
+        DomNodeKind::Text => {
+            if raw {
+                out.push_str(node.text());
+            } else {
+                out.push_str(&html_encode_text(node.text()));
+            }
+        },
+        DomNodeKind::Comment => {
+            out.push_str("<!--");
+            out.push_str(node.text());
+            out.push_str("-->");
+        },
+        DomNodeKind::Doctype => {
+            // the tree only keeps the doctype's name, the original declaration still carries its identifiers
+            out.push_str(node.doctype().trim());
+        },
+        DomNodeKind::Element => {
+            html_write_start_tag(node, out);
+            if html_is_void(node) {
+                return;
+            }
+            let raw = html_is_raw_text_parent(node, scripting);
+            for child in node.children() {
+                html_write_node(child, out, raw, scripting);
+            }
+            html_write_end_tag(node, out);
+        },
+    }
+}
+
+fn html_write_start_tag<N: HtmlNode>(node: N, out: &mut String) {
+    out.push('<');
+    out.push_str(node.tag_name());
+    for attr in node.attributes() {
+        out.push(' ');
+        out.push_str(&attr.name);
+        out.push_str("=\"");
+        out.push_str(&html_encode_attribute(&attr.value));
+        out.push('"');
+    }
+    out.push('>');
+}
+
+fn html_write_end_tag<N: HtmlNode>(node: N, out: &mut String) {
+    out.push_str("</");
+    out.push_str(node.tag_name());
+    out.push('>');
+}
+
+// an svg <style> or <image> is an ordinary element, only html elements are void or hold raw text
+fn html_is_void<N: HtmlNode>(node: N) -> bool {
+    return node.namespace() == DomNamespace::Html && is_html_void_element(node.tag_name());
+}
+
+// textarea and pre are prelike too, but their text is decoded like everywhere else and gets escaped again
+pub(crate) fn html_is_raw_text_parent<N: HtmlNode>(node: N, scripting: bool) -> bool {
+    if *node.kind() != DomNodeKind::Element || node.namespace() != DomNamespace::Html {
+        return false;
+    }
+    return is_html_raw_text_element(node.tag_name()) || (scripting && node.tag_name() == "noscript");
+}
@@ -0,0 +1,66 @@
+use crate::dom::*;
+use crate::html5_parser::html5_parse;
+use crate::html_utils::HtmlAttribute;
+
+#[test]
+fn test_dom_node_to_html() {
+    let root = dom_tree_from_html("<div class='x'><h1>Title</h1> <p>Hello<br/>World</p></div>").unwrap();
+    assert!(root.to_html() == r#"<div class="x"><h1>Title</h1> <p>Hello<br>World</p></div>"#);
+
+    let root = dom_tree_from_html("<p>The &lt;em&gt; tag &amp; more</p>").unwrap();
+    assert!(root.children[0].text == "The <em> tag & more");
+    assert!(root.to_html() == "<p>The &lt;em&gt; tag &amp; more</p>");
+
+    let root = dom_tree_from_html(r#"<a href="/a?b=1&amp;c=2" title='say "hi"'>x</a>"#).unwrap();
+    assert!(root.children[0].attributes == vec![
+        HtmlAttribute::new("href", "/a?b=1&c=2"),
+        HtmlAttribute::new("title", "say \"hi\""),
+    ]);
+    assert!(root.to_html() == r#"<a href="/a?b=1&amp;c=2" title="say &quot;hi&quot;">x</a>"#);
+
+    let root = dom_tree_from_html("<input type=checkbox checked><img src=a.png alt=''>").unwrap();
+    assert!(root.to_html() == r#"<input type="checkbox" checked=""><img src="a.png" alt="">"#);
+
+    let root = dom_tree_from_html("<script>if (a < b && c > d) { x = '&amp;'; }</script>").unwrap();
+    assert!(root.to_html() == "<script>if (a < b && c > d) { x = '&amp;'; }</script>");
+
+    let root = dom_tree_from_html("<style>a > b { content: '&'; }</style><textarea><p>&lt;</textarea>").unwrap();
+    assert!(root.children[1].text == "<p><");
+    assert!(root.to_html() == "<style>a > b { content: '&'; }</style><textarea>&lt;p&gt;&lt;</textarea>");
+
+    let root = dom_tree_from_html("<pre>&lt;b&gt;bold</pre>").unwrap();
+    assert!(root.children[0].text == "<b>bold");
+    assert!(root.to_html() == "<pre>&lt;b&gt;bold</pre>");
+
+    let root = dom_tree_from_html("<!DOCTYPE html><html><body><!-- hi --><P>x</P></body></html>").unwrap();
+    assert!(root.to_html() == "<!DOCTYPE html><html><body><!-- hi --><p>x</p></body></html>");
+}
+
+#[test]
+fn test_dom_node_to_html_after_changes() {
+    let mut root = dom_tree_from_html("<ul><li>a</li></ul>").unwrap();
+    let mut li = DomNode::new_element("li", "<li class='new'>");
+    li.append(DomNode::new_text("b & c"));
+    root.children[0].append(li);
+    assert!(root.to_html() == r#"<ul><li>a</li><li class="new">b &amp; c</li></ul>"#);
+    assert!(root.children[0].inner_html() == r#"<li>a</li><li class="new">b &amp; c</li>"#);
+}
+
+#[test]
+fn test_dom_node_to_html_escapes_rcdata() {
+    // textarea and pre text is decoded by both builders, writing it back unescaped would turn it into markup
+    let html = "<textarea>&lt;/textarea&gt;&lt;script&gt;x</textarea><pre>&lt;b&gt;bold</pre>";
+    let expected = "<textarea>&lt;/textarea&gt;&lt;script&gt;x</textarea><pre>&lt;b&gt;bold</pre>";
+    assert!(dom_tree_from_html(html).unwrap().to_html() == expected);
+    let body = &html5_parse(html).children[0].children[1];
+    assert!(body.inner_html() == expected);
+    assert!(body.outer_html == format!("<body>{}</body>", expected));
+
+    let mut textarea = DomNode::new_element("textarea", "<textarea>");
+    textarea.append(DomNode::new_text("</textarea><script>x</script>"));
+    assert!(textarea.to_html() == "<textarea>&lt;/textarea&gt;&lt;script&gt;x&lt;/script&gt;</textarea>");
+
+    // raw text elements still come back as written
+    let root = html5_parse("<noembed>a &amp; <b></noembed><iframe>&lt;</iframe>");
+    assert!(root.children[0].children[1].inner_html() == "<noembed>a &amp; <b></noembed><iframe>&lt;</iframe>");
+}
@@ -0,0 +1,125 @@
+use std::cell::Cell;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    // byte offsets into the source, end is exclusive
+    pub start: usize,
+    pub end: usize,
+    // 1-based line and column (counted in chars) of the start offset
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        return Span { start, end, line, column };
+    }
+}
+
+// the html5 tokenizer works in char positions, this turns those positions into byte offsets, lines and columns
+#[derive(Debug)]
+pub(crate) struct SpanMap {
+    byte_offsets: Vec<usize>,
+    line_starts: Vec<usize>,
+    // where the source starts inside a larger document, used when tokenizing one chunk at a time
+    base: Span,
+}
+
+impl SpanMap {
+
+    #[cfg(test)]
+    pub(crate) fn new(source: &str) -> SpanMap {
+        return SpanMap::with_base(source, Span::new(0, 0, 1, 1));
+    }
+
+    // base.start, base.line and base.column give the position of the first char of source
+    pub(crate) fn with_base(source: &str, base: Span) -> SpanMap {
+        let mut byte_offsets: Vec<usize> = vec![];
+        let mut line_starts: Vec<usize> = vec![0];
+        for (i, (byte, c)) in source.char_indices().enumerate() {
+            byte_offsets.push(byte);
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        byte_offsets.push(source.len());
+        return SpanMap { byte_offsets, line_starts, base };
+    }
+
+    pub(crate) fn char_len(&self) -> usize {
+        return self.byte_offsets.len() - 1;
+    }
+
+    // start and end are char positions, end is exclusive
+    pub(crate) fn span(&self, start: usize, end: usize) -> Span {
+        let start = start.min(self.char_len());
+        let end = end.clamp(start, self.char_len());
+        let line_index = match self.line_starts.binary_search(&start) {
+            Ok(i) => { i },
+            Err(i) => { i - 1 },
+        };
+        let mut column = start - self.line_starts[line_index] + 1;
+        if line_index == 0 {
+            column += self.base.column - 1;
+        }
+        let base_byte = self.base.start;
+        return Span::new(base_byte + self.byte_offsets[start], base_byte + self.byte_offsets[end], self.base.line + line_index, column);
+    }
+
+}
+
+// turns byte offsets into spans for a tokenizer that moves forward through its source. lines and columns are
+// counted on from the last offset asked about instead of from an index built up front, so nothing is spent on
+// the part of the source a caller never gets to, and a walk through the whole source looks at each char once
+#[derive(Debug)]
+pub(crate) struct SpanCounter<'a> {
+    source: &'a str,
+    // where the source starts inside a larger document, used when tokenizing one chunk at a time
+    base: Span,
+    // the last offset a span was made for, with its line and column
+    last: Cell<(usize, usize, usize)>,
+}
+
+impl<'a> SpanCounter<'a> {
+
+    // base.start, base.line and base.column give the position of the first char of source
+    pub(crate) fn with_base(source: &'a str, base: Span) -> SpanCounter<'a> {
+        return SpanCounter { source, base, last: Cell::new((0, base.line, base.column)) };
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        return self.source.len();
+    }
+
+    // the offset moved back onto the start of the char it falls in, and kept inside the source
+    pub(crate) fn floor(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        return offset;
+    }
+
+    // start and end are byte offsets into the source, end is exclusive
+    pub(crate) fn span(&self, start: usize, end: usize) -> Span {
+        let start = self.floor(start);
+        let end = self.floor(end).max(start);
+        let (mut offset, mut line, mut column) = self.last.get();
+        // going backwards is rare, counting again from the start keeps it correct
+        if start < offset {
+            (offset, line, column) = (0, self.base.line, self.base.column);
+        }
+        for c in self.source[offset..start].chars() {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        self.last.set((start, line, column));
+        return Span::new(self.base.start + start, self.base.start + end, line, column);
+    }
+
+}
@@ -0,0 +1,42 @@
+use crate::span::*;
+
+#[test]
+fn test_span_map() {
+    let map = SpanMap::new("<p>\n  <b>x</b>\n</p>");
+    assert!(map.char_len() == 19);
+    assert!(map.span(0, 3) == Span::new(0, 3, 1, 1));
+    assert!(map.span(6, 9) == Span::new(6, 9, 2, 3));
+    assert!(map.span(3, 4) == Span::new(3, 4, 1, 4));
+    assert!(map.span(15, 19) == Span::new(15, 19, 3, 1));
+    assert!(map.span(19, 19) == Span::new(19, 19, 3, 5));
+    // multi-byte chars move byte offsets but columns are counted in chars
+    let map = SpanMap::new("<p>héllo</p>");
+    assert!(map.span(8, 12) == Span::new(9, 13, 1, 9));
+}
+
+#[test]
+fn test_span_map_with_base() {
+    // a source that starts partway through line 2 of a larger document, 10 bytes in
+    let map = SpanMap::with_base("ab\ncd", Span::new(10, 10, 2, 4));
+    assert!(map.span(0, 2) == Span::new(10, 12, 2, 4));
+    assert!(map.span(3, 5) == Span::new(13, 15, 3, 1));
+}
+
+#[test]
+fn test_span_counter() {
+    let counter = SpanCounter::with_base("<p>\n  <b>x</b>\n</p>", Span::new(0, 0, 1, 1));
+    assert!(counter.len() == 19);
+    assert!(counter.span(0, 3) == Span::new(0, 3, 1, 1));
+    assert!(counter.span(6, 9) == Span::new(6, 9, 2, 3));
+    // asking about an earlier offset counts again from the start
+    assert!(counter.span(3, 4) == Span::new(3, 4, 1, 4));
+    assert!(counter.span(15, 19) == Span::new(15, 19, 3, 1));
+    assert!(counter.span(19, 40) == Span::new(19, 19, 3, 5));
+    // offsets are in bytes, columns are counted in chars
+    let counter = SpanCounter::with_base("<p>héllo</p>", Span::new(0, 0, 1, 1));
+    assert!(counter.span(9, 13) == Span::new(9, 13, 1, 9));
+    assert!(counter.floor(5) == 4);
+    let counter = SpanCounter::with_base("ab\ncd", Span::new(10, 10, 2, 4));
+    assert!(counter.span(0, 2) == Span::new(10, 12, 2, 4));
+    assert!(counter.span(3, 5) == Span::new(13, 15, 3, 1));
+}
@@ -1,16 +1,25 @@
 
+use crate::entities::{html_decode_attribute, html_decode_text};
+use crate::error::RipError;
 use crate::html_utils::*;
-use rlex::{self, Rlex};
+use crate::span::{Span, SpanCounter};
+use std::borrow::Cow;
+use std::collections::VecDeque;
 
 
-#[derive(Debug, PartialEq, Eq)]
-enum LexerState {
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum LexerState {
     InTag,
     InText,
     InPreLike,
+    InComment,
 }
 
+// with the serde feature a token is an object tagged with its variant's name, like
+// {"type": "Open", "tag_name": "p", "outer_html": "<p>"} or {"type": "InnerText", "text": "hi"}
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "type"))]
 pub enum TokenHtml {
     Open { tag_name: String, outer_html: String },
     Close { tag_name: String, outer_html: String },
@@ -19,198 +28,806 @@ pub enum TokenHtml {
     PreLikeClose { tag_name: String, outer_html: String },
     InnerText { text: String },
     Whitespace { text: String },
+    Comment { text: String },
+    Doctype { name: String, public_id: Option<String>, system_id: Option<String>, outer_html: String },
 }
 
-pub fn new_token_html_from_tag(tag_str: &str) -> Result<TokenHtml, String> {
+// with the serde feature the token's fields sit next to the span, the same shape 'rip tokens --json' writes
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpannedTokenHtml {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub token: TokenHtml,
+    pub span: Span,
+}
+
+// the same tokens as TokenHtml, but made of slices of the source instead of owned strings.
+// tag names are lowercased, so a name only has to be copied when the source spelled it with uppercase chars,
+// and a doctype is rare enough that it keeps its parsed parts owned
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BorrowedTokenHtml<'a> {
+    Open { tag_name: Cow<'a, str>, outer_html: &'a str },
+    Close { tag_name: Cow<'a, str>, outer_html: &'a str },
+    SelfClosing { tag_name: Cow<'a, str>, outer_html: &'a str },
+    PreLikeOpen { tag_name: Cow<'a, str>, outer_html: &'a str },
+    PreLikeClose { tag_name: Cow<'a, str>, outer_html: &'a str },
+    InnerText { text: &'a str },
+    Whitespace { text: &'a str },
+    Comment { text: &'a str },
+    Doctype { name: String, public_id: Option<String>, system_id: Option<String>, outer_html: &'a str },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpannedBorrowedTokenHtml<'a> {
+    pub token: BorrowedTokenHtml<'a>,
+    pub span: Span,
+}
+
+impl BorrowedTokenHtml<'_> {
+    pub fn into_owned(self) -> TokenHtml {
+        match self {
+            BorrowedTokenHtml::Open { tag_name, outer_html } => {
+                return TokenHtml::Open { tag_name: tag_name.into_owned(), outer_html: outer_html.to_string() };
+            },
+            BorrowedTokenHtml::Close { tag_name, outer_html } => {
+                return TokenHtml::Close { tag_name: tag_name.into_owned(), outer_html: outer_html.to_string() };
+            },
+            BorrowedTokenHtml::SelfClosing { tag_name, outer_html } => {
+                return TokenHtml::SelfClosing { tag_name: tag_name.into_owned(), outer_html: outer_html.to_string() };
+            },
+            BorrowedTokenHtml::PreLikeOpen { tag_name, outer_html } => {
+                return TokenHtml::PreLikeOpen { tag_name: tag_name.into_owned(), outer_html: outer_html.to_string() };
+            },
+            BorrowedTokenHtml::PreLikeClose { tag_name, outer_html } => {
+                return TokenHtml::PreLikeClose { tag_name: tag_name.into_owned(), outer_html: outer_html.to_string() };
+            },
+            BorrowedTokenHtml::InnerText { text } => {
+                return TokenHtml::InnerText { text: text.to_string() };
+            },
+            BorrowedTokenHtml::Whitespace { text } => {
+                return TokenHtml::Whitespace { text: text.to_string() };
+            },
+            BorrowedTokenHtml::Comment { text } => {
+                return TokenHtml::Comment { text: text.to_string() };
+            },
+            BorrowedTokenHtml::Doctype { name, public_id, system_id, outer_html } => {
+                return TokenHtml::Doctype { name, public_id, system_id, outer_html: outer_html.to_string() };
+            },
+        }
+    }
+}
+
+impl<'a> From<BorrowedTokenHtml<'a>> for TokenHtml {
+    fn from(tok: BorrowedTokenHtml<'a>) -> TokenHtml {
+        return tok.into_owned();
+    }
+}
+
+impl SpannedBorrowedTokenHtml<'_> {
+    pub fn into_owned(self) -> SpannedTokenHtml {
+        return SpannedTokenHtml { token: self.token.into_owned(), span: self.span };
+    }
+}
+
+impl TokenHtml {
+    // the ordered name/value pairs of an opening tag, any other kind of token has none
+    pub fn attributes(&self) -> Vec<HtmlAttribute> {
+        match self {
+            TokenHtml::Open { tag_name: _, outer_html } | TokenHtml::SelfClosing { tag_name: _, outer_html } | TokenHtml::PreLikeOpen { tag_name: _, outer_html } => {
+                // the tag was already validated when the token was made, so parsing it again cannot fail
+                return html_attributes(outer_html).unwrap_or_default();
+            },
+            _ => {
+                return vec![];
+            }
+        }
+    }
+    // attributes with character references in their values decoded
+    pub fn attributes_decoded(&self) -> Vec<HtmlAttribute> {
+        return self.attributes().into_iter().map(|attr| HtmlAttribute { value: html_decode_attribute(&attr.value), name: attr.name }).collect();
+    }
+
+    // the text of an InnerText or Whitespace token with character references decoded,
+    // text from inside a script or style should be read as-is instead
+    pub fn text_decoded(&self) -> Option<String> {
+        match self {
+            TokenHtml::InnerText { text } | TokenHtml::Whitespace { text } => {
+                return Some(html_decode_text(text));
+            },
+            _ => {
+                return None;
+            }
+        }
+    }
+}
+
+pub fn new_token_html_from_tag(tag_str: &str) -> Result<TokenHtml, RipError> {
+    return new_borrowed_token_html_from_tag(tag_str).map(|tok| tok.into_owned());
+}
+
+pub fn new_borrowed_token_html_from_tag(tag_str: &str) -> Result<BorrowedTokenHtml<'_>, RipError> {
+    // a doctype is a declaration rather than a tag, it never opens or closes anything
+    if is_html_doctype(tag_str) {
+        let (name, public_id, system_id) = html_doctype(tag_str)?;
+        return Ok(BorrowedTokenHtml::Doctype { name, public_id, system_id, outer_html: tag_str });
+    }
     validate_token_html_backslash_count(tag_str)?;
     validate_token_html_quotes(tag_str)?;
-    let tag_name = html_tag_name(tag_str)?;
-    let format_breaking_tag_names = vec!["script".to_owned(), "style".to_owned(), "textarea".to_owned(), "xmp".to_owned(), "pre".to_owned()];     
-    let mut is_format_breaking = false;
-    if format_breaking_tag_names.contains(&tag_name) {
-        is_format_breaking = true;
-    } 
-    let tag_str_squeezed = &tag_str.replace(' ', "").clone();
-    let mut chars = tag_str_squeezed.chars();
+    return html_tag_token_unchecked(tag_str);
+}
+
+// the token for a tag without the slash and quote checks, lenient tokenizing falls back on it for tags that fail them
+fn html_tag_token_unchecked(tag_str: &str) -> Result<BorrowedTokenHtml<'_>, RipError> {
+    let tag_name = html_tag_name_borrowed(tag_str)?;
+    let is_format_breaking = is_html_pre_like_element(&tag_name);
+    // spaces don't count when looking for the '/' of a closing or self-closing tag
+    let mut chars = tag_str.chars().filter(|c| *c != ' ');
     let second_char = chars.nth(1);
     if second_char == Some('/') {
         if is_format_breaking {
-            return Ok(TokenHtml::PreLikeClose { tag_name: tag_name, outer_html: tag_str.to_string() });
+            return Ok(BorrowedTokenHtml::PreLikeClose { tag_name, outer_html: tag_str });
         }
-        return Ok(TokenHtml::Close { tag_name: tag_name, outer_html: tag_str.to_string() });
+        return Ok(BorrowedTokenHtml::Close { tag_name, outer_html: tag_str });
     }
     let second_to_last_char = chars.rev().nth(1);
-    if second_to_last_char == Some('/') {
-        return Ok(TokenHtml::SelfClosing { tag_name: tag_name, outer_html: tag_str.to_string() });
+    // void elements are self-closing whether or not they were written with the '/'
+    if second_to_last_char == Some('/') || is_html_void_element(&tag_name) {
+        return Ok(BorrowedTokenHtml::SelfClosing { tag_name, outer_html: tag_str });
     }
     if is_format_breaking {
-        return Ok(TokenHtml::PreLikeOpen { tag_name: tag_name, outer_html: tag_str.to_string() });
+        return Ok(BorrowedTokenHtml::PreLikeOpen { tag_name, outer_html: tag_str });
     }
-    return Ok(TokenHtml::Open { tag_name: tag_name, outer_html: tag_str.to_string() });
+    return Ok(BorrowedTokenHtml::Open { tag_name, outer_html: tag_str });
 }
 
-pub fn html_tokenize(source: &str) -> Result<Vec<TokenHtml>, String> {
-    let mut r: Rlex<LexerState, TokenHtml> = Rlex::new(source, LexerState::InTag);
-    r.trace_on();
-    while !r.at_end() {
-        match r.state() {
-            LexerState::InTag => {
-                let toks = handle_in_tag(&mut r)?;
-                match toks {
-                    Some(toks) => {
-                        for tok in toks {
-                            r.token_push(tok);
+pub fn html_tokenize(source: &str) -> Result<Vec<TokenHtml>, RipError> {
+    return HtmlTokenizer::new(source).collect();
+}
+
+pub fn html_tokenize_spanned(source: &str) -> Result<Vec<SpannedTokenHtml>, RipError> {
+    return HtmlTokenizer::new(source).spanned().collect();
+}
+
+pub fn html_tokenize_with_options(source: &str, options: HtmlTokenizerOptions) -> Result<Vec<TokenHtml>, RipError> {
+    return HtmlTokenizer::with_options(source, options).collect();
+}
+
+// never fails, anything malformed is repaired as well as it can be and reported in diagnostics instead
+pub fn html_tokenize_lenient(source: &str) -> LenientHtmlTokens {
+    let mut tokenizer = HtmlTokenizer::new(source).lenient();
+    let tokens = tokenizer.by_ref().collect();
+    return LenientHtmlTokens { tokens, diagnostics: tokenizer.tokenizer.diagnostics };
+}
+
+// tokens that point back into source instead of copying out of it
+pub fn html_tokenize_borrowed(source: &str) -> Result<Vec<BorrowedTokenHtml<'_>>, RipError> {
+    return HtmlTokenizer::new(source).borrowed().map(|tok| tok.map(|tok| tok.token)).collect();
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LenientHtmlTokens {
+    pub tokens: Vec<SpannedTokenHtml>,
+    // every problem that was worked around, in source order, each with the span it was found at
+    pub diagnostics: Vec<RipError>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct HtmlTokenizerOptions {
+    // by default a raw-text body like a script ends at the first closing tag for it, quotes or not, the way browsers read it.
+    // this turns on the older reading where quotes before the first '<' of the body hide any closing tag inside them,
+    // so 'console.log('</script>')' stays inside the script
+    pub quote_aware_raw_text: bool,
+}
+
+// runs the LexerState machine one step at a time, so tokens can be pulled off as they are found
+// and the caller can stop whenever it has seen enough
+#[derive(Debug)]
+pub struct HtmlTokenizer<'a> {
+    source: &'a str,
+    r: HtmlCursor<'a>,
+    map: SpanCounter<'a>,
+    // the cursor can't step past the last char, so we keep track of the first unconsumed byte ourselves
+    pos: usize,
+    pending: VecDeque<SpannedBorrowedTokenHtml<'a>>,
+    // the prelike tag we are inside of, and where it was opened
+    prelike_open: Option<(String, Span)>,
+    // how far the unfinished token at pos was already searched for its end, so feeding more input to a
+    // chunk tokenizer picks the search up there instead of starting over. 0 when nothing was searched yet
+    scanned: usize,
+    // false when more input may follow the source, a token that runs into the end of the source then waits instead
+    eof: bool,
+    options: HtmlTokenizerOptions,
+    failed: bool,
+    // errors are recorded in diagnostics and worked around instead of ending tokenizing
+    lenient: bool,
+    diagnostics: Vec<RipError>,
+}
+
+impl<'a> HtmlTokenizer<'a> {
+
+    pub fn new(source: &'a str) -> HtmlTokenizer<'a> {
+        return HtmlTokenizer::with_options(source, HtmlTokenizerOptions::default());
+    }
+
+    pub fn with_options(source: &'a str, options: HtmlTokenizerOptions) -> HtmlTokenizer<'a> {
+        return HtmlTokenizer::resume(source, LexerState::InTag, None, 0, Span::new(0, 0, 1, 1), true, options);
+    }
+
+    // picks tokenizing back up partway through a document, base is the position of the first char of source
+    pub(crate) fn resume(source: &'a str, state: LexerState, prelike_open: Option<(String, Span)>, scanned: usize, base: Span, eof: bool, options: HtmlTokenizerOptions) -> HtmlTokenizer<'a> {
+        // nothing is read or counted up front, so stopping after the first few tokens costs only what they took
+        return HtmlTokenizer {
+            source,
+            r: HtmlCursor { source, pos: 0, state },
+            map: SpanCounter::with_base(source, base),
+            pos: 0,
+            pending: VecDeque::new(),
+            prelike_open,
+            scanned,
+            eof,
+            options,
+            failed: false,
+            lenient: false,
+            diagnostics: vec![],
+        };
+    }
+
+    // what HtmlTokenizer::resume needs to carry on from where this tokenizer stopped: the number of source
+    // bytes consumed, the state, the open prelike tag, how far past them the next token was searched already,
+    // and the position to resume at
+    pub(crate) fn suspend(self) -> (usize, LexerState, Option<(String, Span)>, usize, Span) {
+        let base = self.map.span(self.pos, self.pos);
+        return (self.pos, *self.r.state(), self.prelike_open, self.scanned.saturating_sub(self.pos), base);
+    }
+
+    // the same tokens, each paired with where it sits in the source
+    pub fn spanned(self) -> SpannedHtmlTokenizer<'a> {
+        return SpannedHtmlTokenizer { tokenizer: self };
+    }
+
+    // spanned tokens that borrow from the source instead of owning their strings
+    pub fn borrowed(self) -> BorrowedHtmlTokenizer<'a> {
+        return BorrowedHtmlTokenizer { tokenizer: self };
+    }
+
+    // spanned tokens that keep coming past malformed markup, see LenientHtmlTokenizer
+    pub fn lenient(mut self) -> LenientHtmlTokenizer<'a> {
+        self.lenient = true;
+        return LenientHtmlTokenizer { tokenizer: self };
+    }
+
+    pub fn next_spanned(&mut self) -> Option<Result<SpannedTokenHtml, RipError>> {
+        return self.next_spanned_borrowed().map(|tok| tok.map(|tok| tok.into_owned()));
+    }
+
+    pub fn next_spanned_borrowed(&mut self) -> Option<Result<SpannedBorrowedTokenHtml<'a>, RipError>> {
+        loop {
+            if let Some(tok) = self.pending.pop_front() {
+                return Some(Ok(tok));
+            }
+            if self.failed {
+                return None;
+            }
+            if self.pos >= self.source.len() {
+                // a prelike tag still open at the very end never got its body closed
+                if self.eof {
+                    if let Some((tag_name, span)) = self.prelike_open.take() {
+                        self.failed = true;
+                        let err = RipError::UnclosedRawText { tag_name, span: Some(span) };
+                        if self.lenient {
+                            self.diagnostics.push(err);
+                            return None;
                         }
-                    },
-                    None => {
-                        continue;
+                        return Some(Err(err));
                     }
-                };
+                }
+                return None;
+            }
+            match self.step() {
+                Ok(Some(pos)) => {
+                    self.pos = pos;
+                    self.scanned = 0;
+                },
+                Ok(None) => {
+                    // the rest of the source is an unfinished token, it has to wait for more input
+                    return None;
+                },
+                Err(err) => {
+                    // an error ends tokenizing, there's no telling where the next token would start
+                    self.failed = true;
+                    if self.lenient {
+                        self.diagnostics.push(err);
+                        return None;
+                    }
+                    return Some(Err(err));
+                },
+            }
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<usize>, RipError> {
+        let r = &mut self.r;
+        r.goto_pos(self.pos);
+        let diagnostics = if self.lenient { Some(&mut self.diagnostics) } else { None };
+        let src = HtmlSource { source: self.source, map: &self.map, eof: self.eof, options: self.options };
+        let out = &mut self.pending;
+        match r.state() {
+            LexerState::InTag => {
+                return handle_in_tag(r, &src, out, &mut self.prelike_open, diagnostics);
             },
             LexerState::InText => {
-                let toks = handle_in_text(&mut r)?;
-                match toks {
-                    Some(toks) => {
-                        for tok in toks {
-                            r.token_push(tok);
-                        }
-                    },
-                    None => {
-                        continue;
-                    }
-                };
+                return handle_in_text(r, &src, out, &mut self.scanned);
             },
             LexerState::InPreLike => {
-                let toks = handle_in_pre_like(&mut r)?;
-                match toks {
-                    Some(toks) => {
-                        for tok in toks {
-                            r.token_push(tok);
-                        }
-                    },
-                    None => {
-                        continue;
-                    }
-                };
+                return handle_in_pre_like(r, &src, out, &mut self.prelike_open, &mut self.scanned, diagnostics.is_some());
             },
-        };
+            LexerState::InComment => {
+                return handle_in_comment(r, &src, out, &mut self.scanned, diagnostics);
+            },
+        }
+    }
+
+}
+
+impl Iterator for HtmlTokenizer<'_> {
+    type Item = Result<TokenHtml, RipError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.next_spanned().map(|tok| tok.map(|tok| tok.token));
+    }
+}
+
+#[derive(Debug)]
+pub struct SpannedHtmlTokenizer<'a> {
+    tokenizer: HtmlTokenizer<'a>,
+}
+
+impl Iterator for SpannedHtmlTokenizer<'_> {
+    type Item = Result<SpannedTokenHtml, RipError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.tokenizer.next_spanned();
     }
-    let toks = r.token_consume();
-    return Ok(toks);
 }
 
-fn handle_in_tag(r: &mut Rlex<LexerState, TokenHtml>) -> Result<Option<Vec<TokenHtml>>, String> {
+#[derive(Debug)]
+pub struct BorrowedHtmlTokenizer<'a> {
+    tokenizer: HtmlTokenizer<'a>,
+}
+
+impl<'a> Iterator for BorrowedHtmlTokenizer<'a> {
+    type Item = Result<SpannedBorrowedTokenHtml<'a>, RipError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.tokenizer.next_spanned_borrowed();
+    }
+}
+
+// yields every token it can make out of the source and never an error. a tag that fails its checks is still
+// read as the tag it looks like, a tag with no name becomes text, and a comment or raw-text body cut off by the
+// end of the source runs to the end. each repair is recorded in diagnostics with the span it happened at
+#[derive(Debug)]
+pub struct LenientHtmlTokenizer<'a> {
+    tokenizer: HtmlTokenizer<'a>,
+}
+
+impl LenientHtmlTokenizer<'_> {
+
+    pub fn diagnostics(&self) -> &[RipError] {
+        return &self.tokenizer.diagnostics;
+    }
+
+}
+
+impl Iterator for LenientHtmlTokenizer<'_> {
+    type Item = SpannedTokenHtml;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // errors never come out of a lenient tokenizer, they land in its diagnostics
+        return self.tokenizer.next_spanned().and_then(|tok| tok.ok());
+    }
+}
+
+// walks the source a char at a time. positions are byte offsets, and like the lexers this crate started out on
+// it never steps past the last char, so the handlers check at_end before moving on
+#[derive(Debug)]
+struct HtmlCursor<'a> {
+    source: &'a str,
+    pos: usize,
+    state: LexerState,
+}
+
+impl HtmlCursor<'_> {
+
+    fn state(&self) -> &LexerState {
+        return &self.state;
+    }
+
+    fn state_set(&mut self, state: LexerState) {
+        self.state = state;
+    }
+
+    fn pos(&self) -> usize {
+        return self.pos;
+    }
+
+    // the char at the cursor, '\0' only for an empty source
+    fn char(&self) -> char {
+        return self.source[self.pos..].chars().next().unwrap_or('\0');
+    }
+
+    fn at_end(&self) -> bool {
+        return self.pos + self.char().len_utf8() >= self.source.len();
+    }
+
+    fn next(&mut self) {
+        if !self.at_end() {
+            self.pos += self.char().len_utf8();
+        }
+    }
+
+    // a position past the end lands on the last char
+    fn goto_pos(&mut self, pos: usize) {
+        if pos < self.source.len() {
+            self.pos = pos;
+            return;
+        }
+        self.pos = self.source.char_indices().next_back().map(|(i, _)| i).unwrap_or(0);
+    }
+
+}
+
+// what every handler reads from besides the cursor itself
+struct HtmlSource<'s, 'a> {
+    source: &'a str,
+    map: &'s SpanCounter<'a>,
+    // false when more input may follow the source
+    eof: bool,
+    options: HtmlTokenizerOptions,
+}
+
+impl<'a> HtmlSource<'_, 'a> {
+    // the source text from start to end, end is exclusive. an offset inside a char or past the end
+    // is moved back to the start of that char or the end of the source
+    fn slice(&self, start: usize, end: usize) -> &'a str {
+        let start = self.map.floor(start);
+        return &self.source[start..self.map.floor(end).max(start)];
+    }
+}
+
+// each handler starts at r.pos(), pushes whatever tokens it finds onto out and returns the position it consumed up to,
+// or None when it ran out of source before its token ended and src.eof says more input is on the way. handlers
+// given scanned leave in it how far they got before running out, and start from there when handed it back.
+// handlers given diagnostics are tokenizing leniently, they record what went wrong there and carry on
+
+fn handle_in_tag<'a>(r: &mut HtmlCursor<'_>, src: &HtmlSource<'_, 'a>, out: &mut VecDeque<SpannedBorrowedTokenHtml<'a>>, prelike_open: &mut Option<(String, Span)>, mut diagnostics: Option<&mut Vec<RipError>>) -> Result<Option<usize>, RipError> {
+    let map = src.map;
     let start = r.pos();
-    while !r.at_end() {
-        if r.char() == '>' {
-            if !r.is_in_quote() {
-                break;
-            }
+    if r.char() != '<' {
+        // text sitting where a tag was expected, the text handler will pick it up
+        r.state_set(LexerState::InText);
+        return Ok(Some(start));
+    }
+    let head = src.slice(start, start + 4);
+    if head == "<!--" {
+        r.state_set(LexerState::InComment);
+        return Ok(Some(start));
+    }
+    if !src.eof && map.len() - start < 4 && "<!--".starts_with(head) {
+        return Ok(None);
+    }
+    // leniently a '<' with whitespace after it is text the way browsers read it, as in 'a < b',
+    // rather than the start of a tag running to the next '>'
+    if let Some(diagnostics) = diagnostics.as_mut() {
+        if start + 1 >= map.len() && !src.eof {
+            return Ok(None);
+        }
+        if src.slice(start + 1, map.len()).starts_with(|c: char| c.is_ascii_whitespace()) {
+            let span = map.span(start, start + 1);
+            diagnostics.push(RipError::UnexpectedToken { expected: "a tag name after '<'".to_string(), found: "whitespace".to_string(), span: Some(span) });
+            out.push_back(SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::InnerText { text: src.slice(start, start + 1) }, span });
+            r.state_set(LexerState::InText);
+            return Ok(Some(start + 1));
+        }
+    }
+    // quotes only count from the start of this tag, anything before it is text or another tag
+    let mut quote: Option<char> = None;
+    let mut end: Option<usize> = None;
+    loop {
+        let c = r.char();
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+            },
+            None => {
+                if c == '>' {
+                    end = Some(r.pos() + 1);
+                    break;
+                }
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+            },
+        }
+        if r.at_end() {
+            break;
         }
         r.next();
     }
-    let pos = r.pos();
-    let tag_str = r.str_from_rng(start, pos).to_owned();
-    // important! stepping off the '>' and into the next section
-    r.next();
-    let tok = new_token_html_from_tag(&tag_str)?;
+    // a stray quote hides every '>' after it, leniently the tag ends at the first '>' regardless
+    if end.is_none() && quote.is_some() && diagnostics.is_some() {
+        end = src.slice(start, map.len()).find('>').map(|i| start + i + 1);
+    }
+    let end = match end {
+        Some(end) => { end },
+        None if !src.eof => {
+            return Ok(None);
+        },
+        None => { map.len() },
+    };
+    let tag_str = src.slice(start, end);
+    let span = map.span(start, end);
+    let tok = match (new_borrowed_token_html_from_tag(tag_str), diagnostics) {
+        (Ok(tok), _) => { tok },
+        (Err(err), Some(diagnostics)) => {
+            diagnostics.push(err.with_span(span));
+            // a tag that can't even be given a name is read as the text it is made of
+            html_tag_token_unchecked(tag_str).unwrap_or(BorrowedTokenHtml::InnerText { text: tag_str })
+        },
+        (Err(err), None) => {
+            return Err(err.with_span(span));
+        },
+    };
     match tok {
-        TokenHtml::Open { tag_name: _, outer_html: _ } => {
-            if r.char() != '<' {
-                r.state_set(LexerState::InText);
-            }
+        BorrowedTokenHtml::Open { tag_name: _, outer_html: _ } => {
+            r.state_set(LexerState::InText);
         },
-        TokenHtml::Close { tag_name: _, outer_html: _ } => {
+        BorrowedTokenHtml::Close { tag_name: _, outer_html: _ } => {
             r.state_set(LexerState::InText);
         },
-        TokenHtml::PreLikeOpen { tag_name: _, outer_html: _ } => {
+        BorrowedTokenHtml::PreLikeOpen { ref tag_name, outer_html: _ } => {
+            *prelike_open = Some((tag_name.to_string(), span));
             r.state_set(LexerState::InPreLike);
         },
-        TokenHtml::PreLikeClose { tag_name: _, outer_html: _ } => {
-            r.state_set(LexerState::InTag);
+        BorrowedTokenHtml::PreLikeClose { tag_name: _, outer_html: _ } => {
+            r.state_set(LexerState::InText);
+        },
+        BorrowedTokenHtml::SelfClosing { tag_name: _, outer_html: _ } => {
+            r.state_set(LexerState::InText);
+        },
+        BorrowedTokenHtml::Doctype { .. } => {
+            r.state_set(LexerState::InText);
         },
-        TokenHtml::SelfClosing { tag_name: _, outer_html: _ } => {
+        BorrowedTokenHtml::InnerText { .. } => {
             r.state_set(LexerState::InText);
         },
         _ => {
-            return Err(format!("ERR_HTML_FORMAT: derived a TokenHtml::WhiteSpace or TokenHtml::InnerText from new_token_html_from_tag, which is not possible"));
+            return Err(RipError::UnexpectedToken { expected: "a tag".to_string(), found: format!("{:?}", tok), span: Some(span) });
         }
     }
-    return Ok(Some(vec![tok]));
+    out.push_back(SpannedBorrowedTokenHtml { token: tok, span });
+    return Ok(Some(end));
 }
 
-fn handle_in_text(r: &mut Rlex<LexerState, TokenHtml>) -> Result<Option<Vec<TokenHtml>>, String> {
+fn handle_in_text<'a>(r: &mut HtmlCursor<'_>, src: &HtmlSource<'_, 'a>, out: &mut VecDeque<SpannedBorrowedTokenHtml<'a>>, scanned: &mut usize) -> Result<Option<usize>, RipError> {
+    let map = src.map;
     let start = r.pos();
-    r.next_until('<');
-    r.prev();
-    r.state_set(LexerState::InTag);
-    if r.char() == '>' {
+    let mut end: Option<usize> = None;
+    // there's no '<' in the part of the text an earlier pass already went through
+    if *scanned > start {
+        r.goto_pos(*scanned);
+    }
+    loop {
+        if r.char() == '<' {
+            end = Some(r.pos());
+            break;
+        }
+        if r.at_end() {
+            break;
+        }
         r.next();
-        return Ok(None);
     }
-    let pos = r.pos();
-    let tag_text = r.str_from_rng(start, pos).to_owned();
-    r.next();
-    if tag_text.replace(" ", "").len() == 0 {
-        return Ok(Some(vec![TokenHtml::Whitespace { text: tag_text }]))
+    let end = match end {
+        Some(end) => { end },
+        // more text may be on the way, splitting it here would give a different token than reading it all at once
+        None if !src.eof => {
+            *scanned = map.len();
+            return Ok(None);
+        },
+        None => { map.len() },
+    };
+    r.state_set(LexerState::InTag);
+    if end == start {
+        return Ok(Some(start));
+    }
+    let text = src.slice(start, end);
+    let span = map.span(start, end);
+    if text.bytes().all(|b| b == b' ') {
+        out.push_back(SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::Whitespace { text }, span });
+        return Ok(Some(end));
     }
-    return Ok(Some(vec![TokenHtml::InnerText { text: tag_text }]));
+    out.push_back(SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::InnerText { text }, span });
+    return Ok(Some(end));
 }
 
-fn handle_in_pre_like(r: &mut Rlex<LexerState, TokenHtml>) -> Result<Option<Vec<TokenHtml>>, String> {
-    let prev_tok = match r.token_prev().cloned() {
-        Some(tok) => { tok },
+fn handle_in_comment<'a>(r: &mut HtmlCursor<'_>, src: &HtmlSource<'_, 'a>, out: &mut VecDeque<SpannedBorrowedTokenHtml<'a>>, scanned: &mut usize, diagnostics: Option<&mut Vec<RipError>>) -> Result<Option<usize>, RipError> {
+    let map = src.map;
+    // r sits on the '<' of '<!--', the comment runs until the first '-->' after it
+    let start = r.pos();
+    let text_start = start + 4;
+    let mut end: Option<usize> = None;
+    // '<!-->' and '<!--->' are empty comments, the way browsers read them
+    let abrupt = src.slice(text_start, text_start + 2);
+    if abrupt.starts_with('>') {
+        end = Some(text_start + 1);
+    } else if abrupt == "->" {
+        end = Some(text_start + 2);
+    }
+    r.goto_pos(text_start.max(*scanned));
+    while end.is_none() && text_start < map.len() {
+        let pos = r.pos();
+        if r.char() == '-' && src.slice(pos, pos + 3) == "-->" {
+            end = Some(pos + 3);
+            break;
+        }
+        if r.at_end() {
+            break;
+        }
+        r.next();
+    }
+    let end = match end {
+        Some(end) => { end },
+        None if !src.eof => {
+            // the last two chars may be the start of the '-->' the next input finishes
+            *scanned = map.floor(map.len().saturating_sub(2));
+            return Ok(None);
+        },
         None => {
-            return Err("ERR_HTML_FORMAT: ended up inside of a prelike token without a reference token to peek back on {}".to_string());
+            let err = RipError::UnclosedComment { span: Some(map.span(start, map.len())) };
+            let diagnostics = match diagnostics {
+                Some(diagnostics) => { diagnostics },
+                None => {
+                    return Err(err);
+                }
+            };
+            // the comment takes the rest of the source, the way browsers read it
+            diagnostics.push(err);
+            let text = src.slice(text_start, map.len());
+            out.push_back(SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::Comment { text }, span: map.span(start, map.len()) });
+            r.state_set(LexerState::InText);
+            return Ok(Some(map.len()));
         }
     };
-    let (tag_name, _outer_html) = match prev_tok {
-        TokenHtml::PreLikeOpen { tag_name, outer_html } => ( tag_name, outer_html ),
-        _ => {
-            return Err(format!("ERR_HTML_FORMAT: expected the previous token to be prelike {:?}", prev_tok));
+    // '<!---->' has no text at all, and neither do the abrupt ones
+    let text = src.slice(text_start, end.saturating_sub(3).max(text_start));
+    out.push_back(SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::Comment { text }, span: map.span(start, end) });
+    r.state_set(LexerState::InText);
+    return Ok(Some(end));
+}
+
+fn handle_in_pre_like<'a>(r: &mut HtmlCursor<'_>, src: &HtmlSource<'_, 'a>, out: &mut VecDeque<SpannedBorrowedTokenHtml<'a>>, prelike_open: &mut Option<(String, Span)>, scanned: &mut usize, lenient: bool) -> Result<Option<usize>, RipError> {
+    let map = src.map;
+    let (tag_name, open_span) = match prelike_open.as_ref() {
+        Some((tag_name, span)) => { (tag_name.as_str(), *span) },
+        None => {
+            let span = map.span(r.pos(), r.pos());
+            return Err(RipError::UnexpectedToken { expected: "a prelike opening tag".to_string(), found: "nothing".to_string(), span: Some(span) });
         }
     };
-    let tag_name_ref = &tag_name;
-    // we need to search for the next closing tag that matches the prev tag (which is pre-like)
+    // running out of source before the closing tag is only an error once we know no more input is coming
+    let unclosed = if src.eof {
+        Err(RipError::UnclosedRawText { tag_name: tag_name.to_string(), span: Some(open_span) })
+    } else {
+        Ok(None)
+    };
+    // one pass over the body looking for the closing tag, every '<' is tried once and a failed try
+    // never reads past the next '<', so the whole scan stays linear in the length of the body
     let text_start = r.pos();
-    if r.char() != '<' {
-        while !r.at_end() {
-            if r.char() == '<' {
-                if !r.is_in_quote() {
-                    break;
-                }
+    // an earlier pass only hands back where it got to once it was done tracking quotes
+    let scan_start = (*scanned).max(text_start);
+    let body = src.slice(scan_start, map.len());
+    // with quote_aware_raw_text, quotes are tracked from the start of the body until the first '<' outside of them,
+    // a backslash escapes the char after it
+    let mut tracking_quotes = src.options.quote_aware_raw_text && scan_start == text_start;
+    let mut in_big_quote = false;
+    let mut in_lil_quote = false;
+    let mut escaped = false;
+    let mut close: Option<(usize, usize)> = None;
+    for (i, c) in body.char_indices() {
+        let pos = scan_start + i;
+        if tracking_quotes {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_big_quote = !in_big_quote;
+            } else if c == '\'' {
+                in_lil_quote = !in_lil_quote;
+            }
+            if c == '<' && !in_big_quote && !in_lil_quote {
+                tracking_quotes = false;
+            }
+        }
+        if c == '<' && !tracking_quotes {
+            if let Some(len) = html_prelike_close_len(&body[i..], tag_name) {
+                close = Some((pos, pos + len));
+                break;
             }
-            r.next();
         }
     }
-    let original_start = r.pos();
-    let mut reset_count = 0;
-    while !r.at_end() {
-        let mut inner_count = 0;
-        while inner_count < reset_count {
-            r.next();
-            r.next_until('<');
-            inner_count += 1;
+    let (close_tag_start, close_tag_end) = match close {
+        Some(close) => { close },
+        // leniently the body runs to the end of the source, the missing closing tag is
+        // reported once the tokenizer finds the prelike tag still open at the end
+        None if lenient && src.eof => {
+            html_push_prelike_text(src, out, text_start, map.len());
+            return Ok(Some(map.len()));
+        },
+        None => {
+            // a closing tag cut off by the end of the source can only start at the last '<', everything
+            // before that is done with. while quotes are still tracked the next pass has to start over
+            if !tracking_quotes {
+                *scanned = match body.rfind('<') {
+                    Some(i) => { scan_start + i },
+                    None => { map.len() },
+                };
+            }
+            return unclosed;
         }
-        let close_tag_start = r.pos();
-        r.next_until('>');
-        r.state_set(LexerState::InTag);
-        let pos = r.pos();
-        r.next();
-        let close_tag = r.str_from_rng(close_tag_start, pos);
-        let close_tag_condensed = close_tag.replace(' ', "");
-        if close_tag_condensed != format!("</{}>", tag_name_ref) {
-            reset_count+=1;
-            r.goto_pos(original_start);
-            continue;
-        }
-        let prelike_text = r.str_from_rng(text_start, close_tag_start-1);
-        let close_tok = TokenHtml::PreLikeClose { tag_name: tag_name_ref.clone(), outer_html: close_tag.to_string() };
-        if prelike_text.replace(' ', "").len() == 0 {
-            return Ok(Some(vec![TokenHtml::Whitespace { text: prelike_text.to_string() }, close_tok]));
-        }
-        return Ok(Some(vec![TokenHtml::InnerText { text: prelike_text.to_string() } , close_tok]));
-    }
-    // we should exit in the loop because we MUST find a closing prelike tag
-    return Err(format!("ERR_HTML_FORMAT: failed to find a closing tag for "));
+    };
+    let close_tag = src.slice(close_tag_start, close_tag_end);
+    r.state_set(LexerState::InText);
+    html_push_prelike_text(src, out, text_start, close_tag_start);
+    // the name only needs copying when the closing tag spelled it in another case
+    let written_name = &close_tag[2..2 + tag_name.len()];
+    let close_name = if written_name == tag_name { Cow::Borrowed(written_name) } else { Cow::Owned(tag_name.to_string()) };
+    let close_tok = BorrowedTokenHtml::PreLikeClose { tag_name: close_name, outer_html: close_tag };
+    out.push_back(SpannedBorrowedTokenHtml { token: close_tok, span: map.span(close_tag_start, close_tag_end) });
+    *prelike_open = None;
+    return Ok(Some(close_tag_end));
+}
+
+fn html_push_prelike_text<'a>(src: &HtmlSource<'_, 'a>, out: &mut VecDeque<SpannedBorrowedTokenHtml<'a>>, start: usize, end: usize) {
+    if end == start {
+        return;
+    }
+    let text = src.slice(start, end);
+    let span = src.map.span(start, end);
+    if text.bytes().all(|b| b == b' ') {
+        out.push_back(SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::Whitespace { text }, span });
+    } else {
+        out.push_back(SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::InnerText { text }, span });
+    }
+}
+
+// the length in bytes of the closing tag for tag_name that rest starts with, if it starts with one.
+// '</' and the name have to be written together, the name matched without regard to case, and only
+// ascii whitespace and '/' may follow it before the '>'
+fn html_prelike_close_len(rest: &str, tag_name: &str) -> Option<usize> {
+    let name_end = 2 + tag_name.len();
+    let name = match rest.get(2..name_end) {
+        Some(name) if rest.starts_with("</") => { name },
+        _ => {
+            return None;
+        },
+    };
+    if !name.eq_ignore_ascii_case(tag_name) {
+        return None;
+    }
+    for (i, c) in rest[name_end..].char_indices() {
+        match c {
+            '>' => {
+                return Some(name_end + i + 1);
+            },
+            '/' => {},
+            c if c.is_ascii_whitespace() => {},
+            _ => {
+                return None;
+            },
+        }
+    }
+    return None;
 }
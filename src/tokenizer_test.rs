@@ -1,4 +1,5 @@
 use crate::*;
+use std::borrow::Cow;
 
 #[test]
 fn test_html_tokenize() {
@@ -49,7 +50,9 @@ fn test_html_tokenize() {
     let toks = html_tokenize("<script>console.log('</script>')</script>").unwrap();
     assert!(toks == vec![
         TokenHtml::PreLikeOpen { tag_name: "script".to_string(), outer_html: "<script>".to_string() }, 
-        TokenHtml::InnerText { text: "console.log('</script>')".to_string() },
+        TokenHtml::InnerText { text: "console.log('".to_string() },
+        TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script>".to_string() }, 
+        TokenHtml::InnerText { text: "')".to_string() },
         TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script>".to_string() }, 
     ]);
 
@@ -327,4 +330,343 @@ fn test_html_tokenize() {
     ]);
 
 
-}
\ No newline at end of file
+}
+#[test]
+fn test_html_tokenize_errors() {
+    assert!(html_tokenize("<script>let a = 1;") == Err(RipError::UnclosedRawText { tag_name: "script".to_string(), span: Some(Span::new(0, 8, 1, 1)) }));
+    assert!(matches!(html_tokenize("<div><hr//></div>"), Err(RipError::TooManySlashes { .. })));
+    assert!(matches!(html_tokenize("<div class='x></div>"), Err(RipError::UnbalancedQuotes { .. })));
+}
+
+#[test]
+fn test_html_tokenize_spanned() {
+    let toks = html_tokenize_spanned("<div class='x'>\n  <p>Hi</p>\n</div>").unwrap();
+    assert!(toks == vec![
+        SpannedTokenHtml { token: TokenHtml::Open { tag_name: "div".to_string(), outer_html: "<div class='x'>".to_string() }, span: Span::new(0, 15, 1, 1) },
+        SpannedTokenHtml { token: TokenHtml::InnerText { text: "\n  ".to_string() }, span: Span::new(15, 18, 1, 16) },
+        SpannedTokenHtml { token: TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p>".to_string() }, span: Span::new(18, 21, 2, 3) },
+        SpannedTokenHtml { token: TokenHtml::InnerText { text: "Hi".to_string() }, span: Span::new(21, 23, 2, 6) },
+        SpannedTokenHtml { token: TokenHtml::Close { tag_name: "p".to_string(), outer_html: "</p>".to_string() }, span: Span::new(23, 27, 2, 8) },
+        SpannedTokenHtml { token: TokenHtml::InnerText { text: "\n".to_string() }, span: Span::new(27, 28, 2, 12) },
+        SpannedTokenHtml { token: TokenHtml::Close { tag_name: "div".to_string(), outer_html: "</div>".to_string() }, span: Span::new(28, 34, 3, 1) },
+    ]);
+
+    let toks = html_tokenize_spanned("<pre>é</pre>").unwrap();
+    assert!(toks[1].span == Span::new(5, 7, 1, 6));
+    assert!(toks[2].span == Span::new(7, 13, 1, 7));
+
+    // every token's span slices its own text back out of the source
+    let source = "<h1>Hello</h1>\n<script>let a = '<br/>';</script><br/>tail";
+    let toks = html_tokenize_spanned(source).unwrap();
+    for tok in &toks {
+        let text = match &tok.token {
+            TokenHtml::InnerText { text } | TokenHtml::Whitespace { text } => text,
+            TokenHtml::Open { outer_html, .. } | TokenHtml::Close { outer_html, .. } | TokenHtml::SelfClosing { outer_html, .. } => outer_html,
+            TokenHtml::PreLikeOpen { outer_html, .. } | TokenHtml::PreLikeClose { outer_html, .. } => outer_html,
+            TokenHtml::Comment { .. } => continue,
+            TokenHtml::Doctype { outer_html, .. } => outer_html,
+        };
+        assert!(&source[tok.span.start..tok.span.end] == text);
+    }
+    assert!(toks.last().unwrap().token == TokenHtml::InnerText { text: "tail".to_string() });
+
+    let err = html_tokenize_spanned("<p>\n  <hr//>\n</p>").unwrap_err();
+    assert!(err.span() == Some(Span::new(6, 12, 2, 3)));
+
+    assert!(html_tokenize_spanned("").unwrap() == vec![]);
+    let toks = html_tokenize("<script></script>").unwrap();
+    assert!(toks == vec![
+        TokenHtml::PreLikeOpen { tag_name: "script".to_string(), outer_html: "<script>".to_string() },
+        TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script>".to_string() },
+    ]);
+}
+
+#[test]
+fn test_html_tokenize_comments() {
+    let toks = html_tokenize("<!-- hello -->").unwrap();
+    assert!(toks == vec![
+        TokenHtml::Comment { text: " hello ".to_string() },
+    ]);
+
+    let toks = html_tokenize("<div><!-- a > b / 'c' \"d\" --><p>Hi</p></div>").unwrap();
+    assert!(toks == vec![
+        TokenHtml::Open { tag_name: "div".to_string(), outer_html: "<div>".to_string() },
+        TokenHtml::Comment { text: " a > b / 'c' \"d\" ".to_string() },
+        TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p>".to_string() },
+        TokenHtml::InnerText { text: "Hi".to_string() },
+        TokenHtml::Close { tag_name: "p".to_string(), outer_html: "</p>".to_string() },
+        TokenHtml::Close { tag_name: "div".to_string(), outer_html: "</div>".to_string() },
+    ]);
+
+    let toks = html_tokenize("<p>Hello<!---->World</p>").unwrap();
+    assert!(toks == vec![
+        TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p>".to_string() },
+        TokenHtml::InnerText { text: "Hello".to_string() },
+        TokenHtml::Comment { text: "".to_string() },
+        TokenHtml::InnerText { text: "World".to_string() },
+        TokenHtml::Close { tag_name: "p".to_string(), outer_html: "</p>".to_string() },
+    ]);
+
+    // a comment closed right after it opens is empty
+    let toks = html_tokenize("<!-->x<!--->y<!-- -->").unwrap();
+    assert!(toks == vec![
+        TokenHtml::Comment { text: "".to_string() },
+        TokenHtml::InnerText { text: "x".to_string() },
+        TokenHtml::Comment { text: "".to_string() },
+        TokenHtml::InnerText { text: "y".to_string() },
+        TokenHtml::Comment { text: " ".to_string() },
+    ]);
+    let toks = html_tokenize_spanned("<!--->").unwrap();
+    assert!(toks[0].span == Span::new(0, 6, 1, 1));
+
+    let toks = html_tokenize("<!--[if IE]><p class='ie'>old</p><![endif]-->  <!-- <div> -->").unwrap();
+    assert!(toks == vec![
+        TokenHtml::Comment { text: "[if IE]><p class='ie'>old</p><![endif]".to_string() },
+        TokenHtml::Whitespace { text: "  ".to_string() },
+        TokenHtml::Comment { text: " <div> ".to_string() },
+    ]);
+
+    // quotes in a comment don't leak into the tags that follow it
+    let toks = html_tokenize("<!-- don't --><p class=\"a\">x</p>").unwrap();
+    assert!(toks[1] == TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p class=\"a\">".to_string() });
+
+    let toks = html_tokenize_spanned("<p>\n<!-- x --></p>").unwrap();
+    assert!(toks[2].span == Span::new(4, 14, 2, 1));
+
+    assert!(html_tokenize("<p><!-- never closed </p>") == Err(RipError::UnclosedComment { span: Some(Span::new(3, 25, 1, 4)) }));
+}
+
+#[test]
+fn test_html_tokenize_doctype() {
+    let toks = html_tokenize("<!DOCTYPE html><html><body>Hi</body></html>").unwrap();
+    assert!(toks == vec![
+        TokenHtml::Doctype { name: "html".to_string(), public_id: None, system_id: None, outer_html: "<!DOCTYPE html>".to_string() },
+        TokenHtml::Open { tag_name: "html".to_string(), outer_html: "<html>".to_string() },
+        TokenHtml::Open { tag_name: "body".to_string(), outer_html: "<body>".to_string() },
+        TokenHtml::InnerText { text: "Hi".to_string() },
+        TokenHtml::Close { tag_name: "body".to_string(), outer_html: "</body>".to_string() },
+        TokenHtml::Close { tag_name: "html".to_string(), outer_html: "</html>".to_string() },
+    ]);
+
+    let toks = html_tokenize("<!doctype html>\n<p>x</p>").unwrap();
+    assert!(toks[0] == TokenHtml::Doctype { name: "html".to_string(), public_id: None, system_id: None, outer_html: "<!doctype html>".to_string() });
+    assert!(toks[1] == TokenHtml::InnerText { text: "\n".to_string() });
+}
+
+#[test]
+fn test_html_tokenize_void_elements() {
+    let toks = html_tokenize("<p>Hello<br>World<img src='a.png'><input type=text></p>").unwrap();
+    assert!(toks == vec![
+        TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p>".to_string() },
+        TokenHtml::InnerText { text: "Hello".to_string() },
+        TokenHtml::SelfClosing { tag_name: "br".to_string(), outer_html: "<br>".to_string() },
+        TokenHtml::InnerText { text: "World".to_string() },
+        TokenHtml::SelfClosing { tag_name: "img".to_string(), outer_html: "<img src='a.png'>".to_string() },
+        TokenHtml::SelfClosing { tag_name: "input".to_string(), outer_html: "<input type=text>".to_string() },
+        TokenHtml::Close { tag_name: "p".to_string(), outer_html: "</p>".to_string() },
+    ]);
+}
+
+#[test]
+fn test_token_html_decoded() {
+    let toks = html_tokenize("<a href='/search?q=1&amp;copy=2' title=&quot;hi&quot;>&lt;em&gt;</a>").unwrap();
+    assert!(toks[0].attributes_decoded() == vec![
+        HtmlAttribute::new("href", "/search?q=1&copy=2"),
+        HtmlAttribute::new("title", "\"hi\""),
+    ]);
+    assert!(toks[1].text_decoded() == Some("<em>".to_string()));
+    assert!(toks[1] == TokenHtml::InnerText { text: "&lt;em&gt;".to_string() });
+    assert!(toks[2].text_decoded().is_none());
+}
+
+#[test]
+fn test_html_tokenizer_iterator() {
+    let mut tokenizer = HtmlTokenizer::new("<html><head><title>Page</title></head><body><hr//></body></html>");
+    let mut title: Option<String> = None;
+    while let Some(tok) = tokenizer.next() {
+        if tok.unwrap() == (TokenHtml::Open { tag_name: "title".to_string(), outer_html: "<title>".to_string() }) {
+            if let Some(Ok(TokenHtml::InnerText { text })) = tokenizer.next() {
+                title = Some(text);
+            }
+            break;
+        }
+    }
+    // stopping at the title means the broken tag further down is never reached
+    assert!(title == Some("Page".to_string()));
+
+    let toks: Vec<Result<TokenHtml, RipError>> = HtmlTokenizer::new("<p>a</p><hr//><p>b</p>").collect();
+    assert!(toks.len() == 4);
+    assert!(toks[2] == Ok(TokenHtml::Close { tag_name: "p".to_string(), outer_html: "</p>".to_string() }));
+    assert!(matches!(toks[3], Err(RipError::TooManySlashes { .. })));
+
+    let first_two: Vec<SpannedTokenHtml> = HtmlTokenizer::new("<p>a</p>").spanned().take(2).map(|tok| tok.unwrap()).collect();
+    assert!(first_two == vec![
+        SpannedTokenHtml { token: TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p>".to_string() }, span: Span::new(0, 3, 1, 1) },
+        SpannedTokenHtml { token: TokenHtml::InnerText { text: "a".to_string() }, span: Span::new(3, 4, 1, 4) },
+    ]);
+
+    assert!(HtmlTokenizer::new("").next().is_none());
+    let count = HtmlTokenizer::new("<ul><li>1</li><li>2</li></ul>").filter(|tok| matches!(tok, Ok(TokenHtml::Open { .. }))).count();
+    assert!(count == 3);
+}
+
+#[test]
+fn test_html_tokenize_borrowed() {
+    let source = "<!DOCTYPE html><DIV class='a'>Hi &amp; bye<br/><!-- c --><script>let a = 1;</script></DIV>";
+    let toks = html_tokenize_borrowed(source).unwrap();
+    let owned: Vec<TokenHtml> = toks.clone().into_iter().map(|tok| tok.into_owned()).collect();
+    assert!(owned == html_tokenize(source).unwrap());
+
+    // text and markup are slices of the source rather than copies of it
+    let within = |s: &str| {
+        let start = s.as_ptr() as usize - source.as_ptr() as usize;
+        start + s.len() <= source.len()
+    };
+    for tok in &toks {
+        match tok {
+            BorrowedTokenHtml::InnerText { text } | BorrowedTokenHtml::Whitespace { text } | BorrowedTokenHtml::Comment { text } => assert!(within(text)),
+            BorrowedTokenHtml::Open { outer_html, .. } | BorrowedTokenHtml::Close { outer_html, .. } | BorrowedTokenHtml::SelfClosing { outer_html, .. } => assert!(within(outer_html)),
+            BorrowedTokenHtml::PreLikeOpen { outer_html, .. } | BorrowedTokenHtml::PreLikeClose { outer_html, .. } => assert!(within(outer_html)),
+            BorrowedTokenHtml::Doctype { outer_html, .. } => assert!(within(outer_html)),
+        }
+    }
+    // names only get copied when they have to be lowercased
+    assert!(matches!(&toks[1], BorrowedTokenHtml::Open { tag_name: Cow::Owned(name), .. } if name == "div"));
+    assert!(matches!(&toks[3], BorrowedTokenHtml::SelfClosing { tag_name: Cow::Borrowed("br"), .. }));
+    assert!(matches!(&toks[7], BorrowedTokenHtml::PreLikeClose { tag_name: Cow::Borrowed("script"), outer_html: "</script>" }));
+
+    let toks: Vec<SpannedBorrowedTokenHtml> = HtmlTokenizer::new("<p>é</p>").borrowed().collect::<Result<_, _>>().unwrap();
+    assert!(toks[1] == SpannedBorrowedTokenHtml { token: BorrowedTokenHtml::InnerText { text: "é" }, span: Span::new(3, 5, 1, 4) });
+    assert!(TokenHtml::from(toks[1].token.clone()) == TokenHtml::InnerText { text: "é".to_string() });
+    assert!(html_tokenize_borrowed("<hr//>").is_err());
+}
+
+#[test]
+fn test_html_tokenize_prelike_close_tags() {
+    let toks = html_tokenize("<script>a < b</script >").unwrap();
+    assert!(toks[2] == TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script >".to_string() });
+    let toks = html_tokenize("<SCRIPT>a < b</SCRIPT>").unwrap();
+    assert!(toks == vec![
+        TokenHtml::PreLikeOpen { tag_name: "script".to_string(), outer_html: "<SCRIPT>".to_string() },
+        TokenHtml::InnerText { text: "a < b".to_string() },
+        TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</SCRIPT>".to_string() },
+    ]);
+    let toks = html_tokenize("<style>p{}</Style\n>").unwrap();
+    assert!(toks[2] == TokenHtml::PreLikeClose { tag_name: "style".to_string(), outer_html: "</Style\n>".to_string() });
+
+    // close tags for other elements, longer names and close tags with attributes all stay part of the body
+    let toks = html_tokenize("<script></scripts></p></script x></script>").unwrap();
+    assert!(toks[1] == TokenHtml::InnerText { text: "</scripts></p></script x>".to_string() });
+    assert!(matches!(html_tokenize("<script>a</scrip"), Err(RipError::UnclosedRawText { .. })));
+
+    // the name has to follow '</' straight away, spaces only come after it
+    let toks = html_tokenize("<script>a</scr ipt>b</script>").unwrap();
+    assert!(toks[1] == TokenHtml::InnerText { text: "a</scr ipt>b".to_string() });
+    let toks = html_tokenize("<script>if (x < /script>/.test(s)) {}</script>").unwrap();
+    assert!(toks[1] == TokenHtml::InnerText { text: "if (x < /script>/.test(s)) {}".to_string() });
+    let toks = html_tokenize("<script>a</ script>b</script / >").unwrap();
+    assert!(toks[1] == TokenHtml::InnerText { text: "a</ script>b".to_string() });
+    assert!(toks[2] == TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script / >".to_string() });
+
+    // a body full of '<' is still tokenized in one pass
+    let body = "if(a<b){c<d}".repeat(20_000);
+    let toks = html_tokenize(&format!("<script>{}</script>", body)).unwrap();
+    assert!(toks[1] == TokenHtml::InnerText { text: body });
+}
+
+#[test]
+fn test_html_tokenize_quote_aware_raw_text() {
+    let options = HtmlTokenizerOptions { quote_aware_raw_text: true };
+    let toks = html_tokenize_with_options("<script>console.log('</script>')</script>", options).unwrap();
+    assert!(toks == vec![
+        TokenHtml::PreLikeOpen { tag_name: "script".to_string(), outer_html: "<script>".to_string() },
+        TokenHtml::InnerText { text: "console.log('</script>')".to_string() },
+        TokenHtml::PreLikeClose { tag_name: "script".to_string(), outer_html: "</script>".to_string() },
+    ]);
+
+    // an apostrophe in prose would hide the closing tag from the quote-aware reading
+    let source = "<pre>don't <b>stop</b></pre><p>x</p>";
+    let toks = html_tokenize(source).unwrap();
+    assert!(toks[1] == TokenHtml::InnerText { text: "don't <b>stop</b>".to_string() });
+    assert!(toks[2] == TokenHtml::PreLikeClose { tag_name: "pre".to_string(), outer_html: "</pre>".to_string() });
+    assert!(matches!(html_tokenize_with_options(source, options), Err(RipError::UnclosedRawText { .. })));
+
+    let toks = html_tokenize("<textarea>it's</textarea>").unwrap();
+    assert!(toks[1] == TokenHtml::InnerText { text: "it's".to_string() });
+}
+
+#[test]
+fn test_html_tokenize_lenient() {
+    let result = html_tokenize_lenient("<div><hr//><p class='x>Hi</p></div>");
+    let toks: Vec<TokenHtml> = result.tokens.into_iter().map(|tok| tok.token).collect();
+    assert!(toks == vec![
+        TokenHtml::Open { tag_name: "div".to_string(), outer_html: "<div>".to_string() },
+        TokenHtml::SelfClosing { tag_name: "hr".to_string(), outer_html: "<hr//>".to_string() },
+        TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p class='x>".to_string() },
+        TokenHtml::InnerText { text: "Hi".to_string() },
+        TokenHtml::Close { tag_name: "p".to_string(), outer_html: "</p>".to_string() },
+        TokenHtml::Close { tag_name: "div".to_string(), outer_html: "</div>".to_string() },
+    ]);
+    assert!(result.diagnostics.len() == 2);
+    assert!(matches!(result.diagnostics[0], RipError::TooManySlashes { span: Some(Span { start: 5, end: 11, line: 1, column: 6 }), .. }));
+    assert!(matches!(result.diagnostics[1], RipError::UnbalancedQuotes { span: Some(Span { start: 11, end: 23, line: 1, column: 12 }), .. }));
+
+    // a missing closing tag leaves the rest of the source as the body
+    let result = html_tokenize_lenient("<p>1</p>\n<script>let a = 1;");
+    let toks: Vec<TokenHtml> = result.tokens.into_iter().map(|tok| tok.token).collect();
+    assert!(toks[4..] == [
+        TokenHtml::PreLikeOpen { tag_name: "script".to_string(), outer_html: "<script>".to_string() },
+        TokenHtml::InnerText { text: "let a = 1;".to_string() },
+    ]);
+    assert!(result.diagnostics == vec![RipError::UnclosedRawText { tag_name: "script".to_string(), span: Some(Span::new(9, 17, 2, 1)) }]);
+
+    let result = html_tokenize_lenient("a <> b<!-- c");
+    let toks: Vec<TokenHtml> = result.tokens.into_iter().map(|tok| tok.token).collect();
+    assert!(toks == vec![
+        TokenHtml::InnerText { text: "a ".to_string() },
+        TokenHtml::InnerText { text: "<>".to_string() },
+        TokenHtml::InnerText { text: " b".to_string() },
+        TokenHtml::Comment { text: " c".to_string() },
+    ]);
+    assert!(result.diagnostics.len() == 2);
+    assert!(result.diagnostics[1] == RipError::UnclosedComment { span: Some(Span::new(6, 12, 1, 7)) });
+
+    // a '<' with whitespace after it is text, not a tag running to the next '>'
+    let result = html_tokenize_lenient("<p>a < b</p>");
+    let toks: Vec<TokenHtml> = result.tokens.into_iter().map(|tok| tok.token).collect();
+    assert!(toks == vec![
+        TokenHtml::Open { tag_name: "p".to_string(), outer_html: "<p>".to_string() },
+        TokenHtml::InnerText { text: "a ".to_string() },
+        TokenHtml::InnerText { text: "<".to_string() },
+        TokenHtml::InnerText { text: " b".to_string() },
+        TokenHtml::Close { tag_name: "p".to_string(), outer_html: "</p>".to_string() },
+    ]);
+    assert!(result.diagnostics == vec![RipError::UnexpectedToken { expected: "a tag name after '<'".to_string(), found: "whitespace".to_string(), span: Some(Span::new(5, 6, 1, 6)) }]);
+    let result = html_tokenize_lenient("1 <\n2");
+    assert!(result.tokens[1].token == TokenHtml::InnerText { text: "<".to_string() });
+    assert!(result.diagnostics.len() == 1);
+
+    // well-formed input gives exactly what html_tokenize_spanned does
+    let source = "<ul><li>One</li></ul>";
+    let result = html_tokenize_lenient(source);
+    assert!(result.tokens == html_tokenize_spanned(source).unwrap());
+    assert!(result.diagnostics.is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_html_tokenize_serde() {
+    let toks = html_tokenize_spanned("<!DOCTYPE html><p class=a>hi</p>").unwrap();
+    let json = serde_json::to_value(&toks).unwrap();
+    assert!(json[0] == serde_json::json!({
+        "type": "Doctype", "name": "html", "public_id": null, "system_id": null, "outer_html": "<!DOCTYPE html>",
+        "span": { "start": 0, "end": 15, "line": 1, "column": 1 },
+    }));
+    assert!(json[1]["type"] == "Open");
+    assert!(json[1]["tag_name"] == "p");
+    assert!(json[2] == serde_json::json!({ "type": "InnerText", "text": "hi", "span": { "start": 26, "end": 28, "line": 1, "column": 27 } }));
+    let back: Vec<SpannedTokenHtml> = serde_json::from_value(json).unwrap();
+    assert!(back == toks);
+    let tok: TokenHtml = serde_json::from_str(r#"{"type": "Comment", "text": " x "}"#).unwrap();
+    assert!(tok == TokenHtml::Comment { text: " x ".to_string() });
+}